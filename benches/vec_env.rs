@@ -0,0 +1,100 @@
+// cargo bench --bench vec_env
+// steps the same number of boards with VecSnakeEnv and with a Vec of SnakeGame,
+// both with the same cheap random policy, and prints steps per second for each
+use snake::{
+    env::{Encoding, EnvConfig},
+    snake::{Direction, SnakeGame},
+    vec_env::VecSnakeEnv,
+};
+use std::time::{Duration, Instant};
+
+const BOARDS: usize = 1024;
+const STEPS: usize = 2000;
+
+// xorshift so the policy costs next to nothing in both loops
+fn policy(state: &mut u64) -> Direction {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    // mostly straight on, a turn now and then
+    match *state % 16 {
+        0 => Direction::Up,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        3 => Direction::Right,
+        _ => Direction::Left,
+    }
+}
+
+fn report(name: &str, steps: usize, elapsed: Duration) {
+    println!(
+        "{:<14} {:>10} steps in {:>8.2?}  {:>12.0} steps/s",
+        name,
+        steps,
+        elapsed,
+        steps as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    for (border, encoding) in [(true, Encoding::Features), (false, Encoding::Features)] {
+        let config = EnvConfig {
+            width: 20,
+            height: 20,
+            border,
+            encoding,
+            ..EnvConfig::default()
+        };
+        println!(
+            "{} boards of {}x{} {}",
+            BOARDS,
+            config.width,
+            config.height,
+            if border { "with borders" } else { "wrapping" }
+        );
+
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        let mut env = VecSnakeEnv::new(config.clone(), BOARDS, 1).unwrap();
+        let mut actions = vec![Direction::Left; BOARDS];
+        let started = Instant::now();
+        for _ in 0..STEPS {
+            for action in actions.iter_mut() {
+                *action = policy(&mut state);
+            }
+            env.step(&actions);
+        }
+        report("VecSnakeEnv", BOARDS * STEPS, started.elapsed());
+
+        let mut observations = Vec::new();
+        let started = Instant::now();
+        for _ in 0..STEPS {
+            for action in actions.iter_mut() {
+                *action = policy(&mut state);
+            }
+            env.step(&actions);
+            env.observe(&mut observations);
+        }
+        report("  + observe", BOARDS * STEPS, started.elapsed());
+
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        let mut games: Vec<SnakeGame> = (0..BOARDS)
+            .map(|board| SnakeGame::new_seeded(config.width, config.height, board as u64))
+            .collect();
+        let started = Instant::now();
+        for step in 0..STEPS {
+            for (board, game) in games.iter_mut().enumerate() {
+                game.steer(0, policy(&mut state));
+                game.tick(border);
+                if game.game_over {
+                    *game = SnakeGame::new_seeded(
+                        config.width,
+                        config.height,
+                        (step * BOARDS + board) as u64,
+                    );
+                }
+            }
+        }
+        report("SnakeGame", BOARDS * STEPS, started.elapsed());
+        println!();
+    }
+}
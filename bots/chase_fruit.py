@@ -0,0 +1,35 @@
+#!/usr/bin/env python3
+# tiny example bot for --bot1 / --bot2
+# reads one json board per line on stdin, answers with a direction per line on stdout
+import json
+import sys
+
+OPPOSITE = {"up": "down", "down": "up", "left": "right", "right": "left"}
+STEP = {"up": (0, -1), "down": (0, 1), "left": (-1, 0), "right": (1, 0)}
+
+for line in sys.stdin:
+    board = json.loads(line)
+    me = board["snakes"][board["you"]]
+    head = me["body"][0]
+    taken = {(p["x"], p["y"]) for snake in board["snakes"] for p in snake["body"]}
+    fruit = board["fruits"][0]
+
+    def target(direction):
+        dx, dy = STEP[direction]
+        x, y = head["x"] + dx, head["y"] + dy
+        if board["wrap"]:
+            x, y = x % board["width"], y % board["height"]
+        return x, y
+
+    def safe(direction):
+        x, y = target(direction)
+        inside = 0 <= x < board["width"] and 0 <= y < board["height"]
+        return inside and (x, y) not in taken
+
+    def distance(direction):
+        x, y = target(direction)
+        return abs(x - fruit["x"]) + abs(y - fruit["y"])
+
+    moves = [d for d in STEP if d != OPPOSITE[me["direction"]]]
+    moves.sort(key=lambda d: (not safe(d), distance(d)))
+    print(moves[0], flush=True)
@@ -0,0 +1,137 @@
+use snake::{
+    arena::{Rules, Tournament},
+    bots::Bot,
+};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: snake-arena --bot <bot> --bot <bot> [--bot <bot> ...] [options]
+
+  --bot <bot>           add a bot to the tournament: greedy, hamilton, an http://
+                        battlesnake url or a command speaking json on stdin/stdout
+  --games <n>           games every pair of bots plays, default 100
+  --size <w>x<h>        board size, default 40x20
+  --borders             walls around the board instead of wrapping round
+  --max-ticks <n>       games longer than this are a draw, default 5000
+  --threads <n>         how many games run at once, default every cpu
+  --seed <n>            seed of the first game, default 0
+  --move-timeout <ms>   how long outside bots get to answer each move, default 200";
+
+fn main() {
+    let tournament = match parse(std::env::args().skip(1)) {
+        Ok(Some(tournament)) => tournament,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let started = Instant::now();
+    let standings = tournament.run(|done, total| {
+        if done % 50 == 0 || done == total {
+            eprint!("\r{}/{} games", done, total);
+            let _ = std::io::stderr().flush();
+        }
+    });
+    eprintln!();
+
+    let standings = match standings {
+        Ok(standings) => standings,
+        Err(e) => {
+            eprintln!("a game could not be played: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "{} games on {}x{} {} in {:.1?}\n",
+        standings.iter().map(|s| s.games).sum::<u32>() / 2,
+        tournament.rules.width,
+        tournament.rules.height,
+        if tournament.rules.border {
+            "with borders"
+        } else {
+            "wrapping"
+        },
+        started.elapsed()
+    );
+    println!(
+        "{:>4}  {:<24} {:>6} {:>6} {:>6} {:>6} {:>6} {:>6} {:>9} {:>9}",
+        "rank", "bot", "elo", "games", "wins", "losses", "draws", "win%", "avg len", "avg ticks"
+    );
+    for (rank, standing) in standings.iter().enumerate() {
+        let mut name = standing.name.clone();
+        name.truncate(24);
+        println!(
+            "{:>4}  {:<24} {:>6.0} {:>6} {:>6} {:>6} {:>6} {:>5.1}% {:>9.1} {:>9.1}",
+            rank + 1,
+            name,
+            standing.elo,
+            standing.games,
+            standing.wins,
+            standing.losses,
+            standing.draws,
+            standing.win_rate() * 100.0,
+            standing.average_length(),
+            standing.average_ticks()
+        );
+    }
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Tournament>, String> {
+    let mut tournament = Tournament {
+        bots: Vec::new(),
+        rules: Rules {
+            width: 40,
+            height: 20,
+            border: false,
+            max_ticks: 5000,
+        },
+        games_per_pair: 100,
+        seed: 0,
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        timeout: Duration::from_millis(200),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+        let number = |value: String| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} takes a number", flag))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--bot" => tournament.bots.push(Bot::parse(&value()?)),
+            "--games" => tournament.games_per_pair = number(value()?)? as usize,
+            "--size" => {
+                let size = value()?;
+                let (width, height) = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or(format!("--size wants something like 40x20, got {}", size))?;
+                if width < 8 || height < 4 {
+                    return Err(String::from("boards smaller than 8x4 have no room to play"));
+                }
+                tournament.rules.width = width;
+                tournament.rules.height = height;
+            }
+            "--borders" => tournament.rules.border = true,
+            "--max-ticks" => tournament.rules.max_ticks = number(value()?)?,
+            "--threads" => tournament.threads = number(value()?)? as usize,
+            "--seed" => tournament.seed = number(value()?)?,
+            "--move-timeout" => tournament.timeout = Duration::from_millis(number(value()?)?),
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    if tournament.bots.len() < 2 {
+        return Err(String::from("a tournament needs at least two bots"));
+    }
+    Ok(Some(tournament))
+}
@@ -0,0 +1,77 @@
+use snake::leaderboard;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: snake-leaderboard [options]
+
+  --port <port>         where to listen, default 7879
+  --bind <address>      who can reach it, default 127.0.0.1 for this computer only,
+                        0.0.0.0 for everyone
+  --file <file>         where the scores and their replays are kept,
+                        default snake-leaderboard.json
+
+the game sends in single player scores with a replay of the game, each one is
+played again here and only goes in if it ends the same way. see snake --leaderboard";
+
+struct Options {
+    port: u16,
+    bind: IpAddr,
+    file: PathBuf,
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let listener = match std::net::TcpListener::bind((options.bind, options.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on port {} because: {}", options.port, e);
+            std::process::exit(1);
+        }
+    };
+    println!("listening on port {}", options.port);
+    if let Err(e) = leaderboard::serve(listener, options.file.clone()) {
+        eprintln!("Could not read {} because: {}", options.file.display(), e);
+        std::process::exit(1);
+    }
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        port: 7879,
+        bind: IpAddr::from(Ipv4Addr::LOCALHOST),
+        file: PathBuf::from("snake-leaderboard.json"),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--port" => {
+                options.port = value()?
+                    .parse()
+                    .map_err(|_| String::from("--port takes a port"))?;
+            }
+            "--bind" => {
+                options.bind = value()?
+                    .parse()
+                    .map_err(|_| String::from("--bind takes an ip address"))?;
+            }
+            "--file" => options.file = PathBuf::from(value()?),
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    Ok(Some(options))
+}
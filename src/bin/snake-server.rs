@@ -0,0 +1,75 @@
+use snake::server;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "usage: snake-server [options]
+
+  --port <port>         where to listen for players, default 7878
+  --scores <file>       keep the high scores in this json file, otherwise
+                        they only last as long as the server
+  --grace <seconds>     how long a player who drops out of a game keeps
+                        their seat, default 30
+
+players connect with snake --server <host:port>, make or join a room and get ready,
+each room starts as soon as it is full and everyone in it is";
+
+struct Options {
+    port: u16,
+    scores: Option<PathBuf>,
+    grace: Duration,
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let listener = match std::net::TcpListener::bind(("0.0.0.0", options.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on port {} because: {}", options.port, e);
+            std::process::exit(1);
+        }
+    };
+    println!("listening on port {}", options.port);
+    server::serve(listener, options.scores, options.grace);
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        port: 7878,
+        scores: None,
+        grace: server::GRACE,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--port" => {
+                options.port = value()?
+                    .parse()
+                    .map_err(|_| String::from("--port takes a port"))?;
+            }
+            "--scores" => options.scores = Some(PathBuf::from(value()?)),
+            "--grace" => {
+                let seconds = value()?
+                    .parse()
+                    .map_err(|_| String::from("--grace takes a number of seconds"))?;
+                options.grace = Duration::from_secs(seconds);
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    Ok(Some(options))
+}
@@ -0,0 +1,67 @@
+use snake::telnet;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: snake-telnet [options]
+
+  --port <port>         where to listen, default 2323
+  --scores <file>       keep the high scores in this json file, otherwise
+                        they only last as long as the server
+
+anyone can play with telnet <host> <port>, on their own or against someone
+else who is connected";
+
+struct Options {
+    port: u16,
+    scores: Option<PathBuf>,
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let listener = match std::net::TcpListener::bind(("0.0.0.0", options.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on port {} because: {}", options.port, e);
+            std::process::exit(1);
+        }
+    };
+    println!("listening on port {}", options.port);
+    if let Err(e) = telnet::serve(listener, options.scores.clone()) {
+        eprintln!("Could not read the high scores because: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        port: 2323,
+        scores: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--port" => {
+                options.port = value()?
+                    .parse()
+                    .map_err(|_| String::from("--port takes a port"))?;
+            }
+            "--scores" => options.scores = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    Ok(Some(options))
+}
@@ -0,0 +1,128 @@
+use super::controller::Controller;
+use super::snake::{Direction, Point, SnakeGame};
+use crossterm::event::KeyCode;
+use std::collections::VecDeque;
+
+// which cells are taken by any snake, used by the bots to look before they leap
+pub struct Occupancy {
+    width: u16,
+    height: u16,
+    cells: Vec<bool>,
+}
+
+impl Occupancy {
+    pub fn of(game: &SnakeGame) -> Self {
+        let mut occupancy = Occupancy {
+            width: game.width(),
+            height: game.height(),
+            cells: vec![false; game.width() as usize * game.height() as usize],
+        };
+
+        for player in 0..game.players() {
+            for segment in game.body(player) {
+                occupancy.set(*segment, true);
+            }
+        }
+
+        occupancy
+    }
+
+    pub fn is_free(&self, point: Point) -> bool {
+        point.x < self.width && point.y < self.height && !self.cells[self.index(point)]
+    }
+
+    pub fn set(&mut self, point: Point, taken: bool) {
+        // the multiplayer spawn pokes one segment past the right edge
+        if point.x < self.width && point.y < self.height {
+            let index = self.index(point);
+            self.cells[index] = taken;
+        }
+    }
+
+    // counts free cells reachable from start, stops early once it has seen `limit`
+    pub fn reachable(&self, game: &SnakeGame, start: Point, border: bool, limit: usize) -> usize {
+        let mut seen = vec![false; self.cells.len()];
+        let mut queue = VecDeque::from([start]);
+        seen[self.index(start)] = true;
+        let mut count = 0;
+
+        while let Some(point) = queue.pop_front() {
+            count += 1;
+            if count >= limit {
+                break;
+            }
+
+            for direction in Direction::ALL {
+                if let Some(next) = game.next_point(point, direction, border) {
+                    if self.is_free(next) && !seen[self.index(next)] {
+                        seen[self.index(next)] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    fn index(&self, point: Point) -> usize {
+        point.y as usize * self.width as usize + point.x as usize
+    }
+}
+
+// manhattan distance, going round the edges when there are no walls
+pub fn distance(game: &SnakeGame, a: Point, b: Point, border: bool) -> u16 {
+    let dx = a.x.abs_diff(b.x);
+    let dy = a.y.abs_diff(b.y);
+
+    if border {
+        dx + dy
+    } else {
+        dx.min(game.width().saturating_sub(dx)) + dy.min(game.height().saturating_sub(dy))
+    }
+}
+
+// heads for the closest fruit but never into a wall, a snake or a pocket smaller than itself
+pub struct GreedyBot {
+    border: bool,
+}
+
+impl GreedyBot {
+    pub fn new(border: bool) -> Self {
+        GreedyBot { border }
+    }
+}
+
+impl Controller for GreedyBot {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        let current = game.direction_of(player);
+        let head = game.body(player)[0];
+        let length = game.body(player).len();
+        let occupancy = Occupancy::of(game);
+
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| *direction != current.opposite())
+            .filter_map(|direction| {
+                let next = game.next_point(head, direction, self.border)?;
+                if !occupancy.is_free(next) {
+                    return None;
+                }
+
+                let roomy = occupancy.reachable(game, next, self.border, length) >= length;
+                let closest = game
+                    .fruits()
+                    .iter()
+                    .map(|fruit| distance(game, next, *fruit, self.border))
+                    .min()
+                    .unwrap_or(0);
+
+                Some((direction, roomy, closest))
+            })
+            .max_by_key(|(direction, roomy, closest)| {
+                (*roomy, std::cmp::Reverse(*closest), *direction == current)
+            })
+            .map(|(direction, _, _)| direction)
+            .unwrap_or(current)
+    }
+}
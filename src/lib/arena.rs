@@ -0,0 +1,178 @@
+use super::bots::Bot;
+use super::snake::{Multiplayer, SnakeGame};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// headless bot vs bot matches on the very same SnakeGame the terminal game runs
+
+#[derive(Clone, Debug)]
+pub struct Rules {
+    pub width: u16,
+    pub height: u16,
+    pub border: bool,
+    pub max_ticks: u64, // a game still going after this is a draw
+}
+
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub winner: Option<usize>, // 0 or 1 for the bot that won, None for a draw
+    pub lengths: [usize; 2],
+    pub scores: [u16; 2],
+    pub ticks: u64,
+}
+
+pub fn play(bots: [&Bot; 2], rules: &Rules, seed: u64, timeout: Duration) -> io::Result<Outcome> {
+    let mut controllers = [
+        bots[0].build(rules.border, timeout)?,
+        bots[1].build(rules.border, timeout)?,
+    ];
+    let mut game = SnakeGame::multiplayer_new_seeded(rules.width, rules.height, seed);
+
+    while !game.game_over && game.ticks() < rules.max_ticks {
+        for (player, controller) in controllers.iter_mut().enumerate() {
+            let direction = controller.next_direction(&game, player, &[]);
+            game.steer(player, direction);
+        }
+        game.tick(rules.border);
+    }
+
+    Ok(Outcome {
+        winner: match (game.game_over, game.winner) {
+            (true, 1) => Some(0),
+            (true, 2) => Some(1),
+            _ => None,
+        },
+        lengths: [game.body(0).len(), game.body(1).len()],
+        scores: [game.score(0), game.score(1)],
+        ticks: game.ticks(),
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub total_length: u64,
+    pub total_ticks: u64,
+    pub elo: f64,
+}
+
+impl Standing {
+    fn new(name: String) -> Self {
+        Standing {
+            name,
+            games: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            total_length: 0,
+            total_ticks: 0,
+            elo: 1500.0,
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.total_length as f64 / self.games.max(1) as f64
+    }
+
+    pub fn average_ticks(&self) -> f64 {
+        self.total_ticks as f64 / self.games.max(1) as f64
+    }
+}
+
+// standard elo with k = 16, score is 1 for a win, 0.5 for a draw and 0 for a loss
+pub fn elo(rating: f64, opponent: f64, score: f64) -> f64 {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0));
+    rating + 16.0 * (score - expected)
+}
+
+pub struct Tournament {
+    pub bots: Vec<Bot>,
+    pub rules: Rules,
+    pub games_per_pair: usize, // half of them with the sides swapped
+    pub seed: u64,
+    pub threads: usize,
+    pub timeout: Duration,
+}
+
+impl Tournament {
+    // every pair plays games_per_pair games spread over all threads
+    // `progress` gets told how many games are done out of how many
+    // ratings are worked out in game order afterwards so the table is the same on every run
+    pub fn run(&self, progress: impl Fn(usize, usize) + Sync) -> io::Result<Vec<Standing>> {
+        let mut pairings = Vec::new();
+        for a in 0..self.bots.len() {
+            for b in a + 1..self.bots.len() {
+                for game in 0..self.games_per_pair {
+                    pairings.push(if game % 2 == 0 { [a, b] } else { [b, a] });
+                }
+            }
+        }
+
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let outcomes: Mutex<Vec<Option<io::Result<Outcome>>>> =
+            Mutex::new((0..pairings.len()).map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some([a, b]) = pairings.get(index) else {
+                        break;
+                    };
+
+                    let outcome = play(
+                        [&self.bots[*a], &self.bots[*b]],
+                        &self.rules,
+                        self.seed.wrapping_add(index as u64),
+                        self.timeout,
+                    );
+                    outcomes.lock().unwrap()[index] = Some(outcome);
+                    progress(done.fetch_add(1, Ordering::Relaxed) + 1, pairings.len());
+                });
+            }
+        });
+
+        let mut standings: Vec<Standing> =
+            self.bots.iter().map(|bot| Standing::new(bot.name())).collect();
+        for (pairing, outcome) in pairings.iter().zip(outcomes.into_inner().unwrap()) {
+            let outcome = outcome.expect("every game was played")?;
+
+            for (side, bot) in pairing.iter().enumerate() {
+                let standing = &mut standings[*bot];
+                standing.games += 1;
+                standing.total_length += outcome.lengths[side] as u64;
+                standing.total_ticks += outcome.ticks;
+                match outcome.winner {
+                    Some(winner) if winner == side => standing.wins += 1,
+                    Some(_) => standing.losses += 1,
+                    None => standing.draws += 1,
+                }
+            }
+
+            let score = match outcome.winner {
+                Some(0) => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            let [a, b] = *pairing;
+            let (rating_a, rating_b) = (standings[a].elo, standings[b].elo);
+            standings[a].elo = elo(rating_a, rating_b, score);
+            standings[b].elo = elo(rating_b, rating_a, 1.0 - score);
+        }
+
+        standings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        Ok(standings)
+    }
+}
@@ -0,0 +1,265 @@
+use super::arena::Rules;
+use super::bots::Bot;
+use super::evolve::Training;
+use super::leaderboard;
+use super::netsim::Conditions;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+pub const USAGE: &str = "usage: snake [options]
+       snake train [options]   evolve a neural network bot, see snake train --help
+
+  --bot1 <bot>          let a bot drive player 1, one of
+                          greedy | hamilton       a built in bot
+                          mcts | mcts:<ms>        tree search, thinking 50ms or <ms> a move
+                          http://host:port        a battlesnake server
+                          file.json               a genome saved by snake train
+                          anything else           a command speaking json on stdin/stdout
+  --bot2 <bot>          same for player 2 in multiplayer
+  --move-timeout <ms>   how long an outside bot gets to answer each tick, default 200
+  --battlesnake <port>  serve a built in bot as a battlesnake instead of playing, only
+                        to this computer unless given as <address>:<port>, 0.0.0.0:8000
+                        for everyone
+  --ai <name>           which built in bot --battlesnake serves, default greedy
+  --host <port>         host a two player game over the network, you are player 1
+  --join <host:port>    join a game someone is hosting, or pick one from the
+                        welcome screen to find games on your network
+  --server <host:port>  play on a snake-server, pick or make a room there
+  --watch <host:port>   watch a hosted game, or whatever is playing on a snake-server
+  --broadcast <port>    let others --watch the game you play here
+  --control <path>      answer scripts on a unix socket there while you play, they can
+                        ask how the game is going, pause it, change the speed and drop
+                        fruit, see the README
+  --name <name>         what your hosted game is called on the network, and you on a
+                        snake-server, default the computer's name
+  --lockstep            host a lockstep game, both sides run the game and only swap moves
+  --input-delay <ticks> how far ahead lockstep moves are sent, default 3 or 1 with --rollback
+  --rollback <ticks>    host a lockstep game that guesses the other snake's moves up to
+                        this many ticks ahead and rolls back when it guessed wrong
+  --netsim <settings>   pretend the network to the other side is a bad one, for testing
+                        latency=120ms,jitter=40ms,loss=2%,reorder=1%,bandwidth=8kB,seed=1
+                        anything left out is perfect
+  --lag <ms>            the same as --netsim latency=<ms>
+  --jitter <ms>         the same as --netsim jitter=<ms>
+  --genome <file>       trained snake the welcome screen offers to play against,
+                        default snake-genome.json
+  --leaderboard <url>   snake-leaderboard your single player scores are sent to and
+                        the welcome screen shows, default http://127.0.0.1:7879";
+
+pub const TRAIN_USAGE: &str = "usage: snake train [options]
+
+  --out <file>          where the best genome is saved, default snake-genome.json
+  --generations <n>     default 50
+  --population <n>      genomes per generation, default 100
+  --games <n>           games each genome plays per generation, default 4
+  --hidden <n,n,..>     hidden layer sizes, default 16
+  --size <w>x<h>        board size, default 20x20
+  --wrap                wrap round the edges instead of walls
+  --max-ticks <n>       games are cut off here, default 2000
+  --opponent <bot>      train in versus games against a bot instead of alone
+  --elite <n>           best genomes copied as they are, default 4
+  --mutation <rate>     chance of each weight being nudged, default 0.1
+  --seed <n>            default 0
+  --threads <n>         default every cpu";
+
+// command line flags, everything else is picked on the welcome screen
+pub struct Args {
+    pub help: bool,
+    pub bots: [Option<Bot>; 2],
+    pub move_timeout: Duration,
+    pub battlesnake: Option<SocketAddr>,
+    pub ai: Bot,
+    pub genome: String,
+    pub host: Option<u16>,
+    pub join: Option<String>,
+    pub server: Option<String>,
+    pub watch: Option<String>,
+    pub broadcast: Option<u16>,
+    pub control: Option<String>,
+    pub name: Option<String>,
+    pub lockstep: bool,
+    pub input_delay: Option<u64>,
+    pub rollback: u64,
+    pub netsim: Conditions,
+    pub leaderboard: String,
+}
+
+pub struct TrainArgs {
+    pub training: Training,
+    pub out: String,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            help: false,
+            bots: [None, None],
+            move_timeout: Duration::from_millis(200),
+            battlesnake: None,
+            ai: Bot::Greedy,
+            genome: String::from("snake-genome.json"),
+            host: None,
+            join: None,
+            server: None,
+            watch: None,
+            broadcast: None,
+            control: None,
+            name: None,
+            lockstep: false,
+            input_delay: None,
+            rollback: 0,
+            netsim: Conditions::default(),
+            leaderboard: String::from(leaderboard::DEFAULT_URL),
+        };
+
+        // on top of --netsim wherever they come
+        let (mut lag, mut jitter) = (None, None);
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--bot1" => parsed.bots[0] = Some(Bot::parse(&value()?)),
+                "--bot2" => parsed.bots[1] = Some(Bot::parse(&value()?)),
+                "--move-timeout" => {
+                    let ms = value()?
+                        .parse()
+                        .map_err(|_| String::from("--move-timeout takes milliseconds"))?;
+                    parsed.move_timeout = Duration::from_millis(ms);
+                }
+                "--battlesnake" => {
+                    let value = value()?;
+                    let address = match value.parse::<u16>() {
+                        Ok(port) => Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+                        Err(_) => value.parse(),
+                    };
+                    let address = address
+                        .map_err(|_| String::from("--battlesnake takes a port or address:port"))?;
+                    parsed.battlesnake = Some(address);
+                }
+                "--ai" => {
+                    parsed.ai = match Bot::parse(&value()?) {
+                        bot @ (Bot::Greedy | Bot::Hamilton | Bot::Mcts(_)) => bot,
+                        other => return Err(format!("{} is not a built in bot", other.name())),
+                    }
+                }
+                "--genome" => parsed.genome = value()?,
+                "--host" => {
+                    let port = value()?
+                        .parse()
+                        .map_err(|_| String::from("--host takes a port"))?;
+                    parsed.host = Some(port);
+                }
+                "--join" => parsed.join = Some(value()?),
+                "--server" => parsed.server = Some(value()?),
+                "--watch" => parsed.watch = Some(value()?),
+                "--broadcast" => {
+                    let port = value()?
+                        .parse()
+                        .map_err(|_| String::from("--broadcast takes a port"))?;
+                    parsed.broadcast = Some(port);
+                }
+                "--control" => parsed.control = Some(value()?),
+                "--name" => parsed.name = Some(value()?),
+                "--lockstep" => parsed.lockstep = true,
+                "--input-delay" => {
+                    let ticks = value()?
+                        .parse()
+                        .map_err(|_| String::from("--input-delay takes a number of ticks"))?;
+                    parsed.input_delay = Some(ticks);
+                }
+                "--rollback" => {
+                    parsed.lockstep = true;
+                    parsed.rollback = value()?
+                        .parse()
+                        .map_err(|_| String::from("--rollback takes a number of ticks"))?;
+                }
+                "--netsim" => {
+                    parsed.netsim =
+                        Conditions::parse(&value()?).map_err(|e| format!("--netsim: {}", e))?;
+                }
+                "--lag" | "--jitter" => {
+                    let ms = value()?
+                        .parse()
+                        .map_err(|_| format!("{} takes milliseconds", flag))?;
+                    if flag == "--lag" {
+                        lag = Some(Duration::from_millis(ms));
+                    } else {
+                        jitter = Some(Duration::from_millis(ms));
+                    }
+                }
+                "--leaderboard" => parsed.leaderboard = value()?,
+                _ => return Err(format!("unknown option {}", flag)),
+            }
+        }
+        parsed.netsim.latency = lag.unwrap_or(parsed.netsim.latency);
+        parsed.netsim.jitter = jitter.unwrap_or(parsed.netsim.jitter);
+
+        Ok(parsed)
+    }
+}
+
+impl TrainArgs {
+    // None when help was asked for
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut parsed = TrainArgs {
+            training: Training::default(),
+            out: String::from("snake-genome.json"),
+        };
+        let training = &mut parsed.training;
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+            let number = |value: String| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} takes a number", flag))
+            };
+            match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--out" => parsed.out = value()?,
+                "--generations" => training.generations = number(value()?)? as u32,
+                "--population" => training.population = number(value()?)? as usize,
+                "--games" => training.games = number(value()?)?.max(1) as usize,
+                "--hidden" => {
+                    training.hidden = value()?
+                        .split(',')
+                        .map(|size| size.trim().parse().ok().filter(|size| *size > 0))
+                        .collect::<Option<_>>()
+                        .ok_or(String::from("--hidden wants sizes like 16 or 24,8"))?;
+                }
+                "--size" => {
+                    let size = value()?;
+                    let (width, height) = size
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                        .ok_or(format!("--size wants something like 20x20, got {}", size))?;
+                    if width < 8 || height < 4 {
+                        return Err(String::from("boards smaller than 8x4 have no room to play"));
+                    }
+                    training.rules = Rules {
+                        width,
+                        height,
+                        ..training.rules.clone()
+                    };
+                }
+                "--wrap" => training.rules.border = false,
+                "--max-ticks" => training.rules.max_ticks = number(value()?)?,
+                "--opponent" => training.opponent = Some(Bot::parse(&value()?)),
+                "--elite" => training.elite = number(value()?)? as usize,
+                "--mutation" => {
+                    training.mutation_rate = value()?
+                        .parse()
+                        .map_err(|_| String::from("--mutation takes a rate like 0.1"))?;
+                }
+                "--seed" => training.seed = number(value()?)?,
+                "--threads" => training.threads = number(value()?)? as usize,
+                _ => return Err(format!("unknown option {}", flag)),
+            }
+        }
+
+        Ok(Some(parsed))
+    }
+}
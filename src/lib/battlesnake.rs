@@ -0,0 +1,366 @@
+use super::board::{Board, SnakeView};
+use super::controller::Controller;
+use super::http::{self, Request, Response};
+use super::snake::{Direction, Point, SnakeGame};
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// the battlesnake api (https://docs.battlesnake.com/api), so our games can call out to
+// battlesnake bot servers and our bots can be called by battlesnake games
+// battlesnake counts y from the bottom, we count from the top, so every y gets flipped
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveRequest {
+    pub game: GameInfo,
+    pub turn: u64,
+    pub board: BattleBoard,
+    pub you: Battlesnake,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameInfo {
+    pub id: String,
+    pub ruleset: Ruleset,
+    #[serde(default)]
+    pub map: String,
+    #[serde(default)]
+    pub timeout: u64,
+    #[serde(default)]
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub name: String, // "standard" has walls, "wrapped" goes round the edges
+    #[serde(default)]
+    pub version: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BattleBoard {
+    pub height: u16,
+    pub width: u16,
+    pub food: Vec<Coord>,
+    #[serde(default)]
+    pub hazards: Vec<Coord>,
+    pub snakes: Vec<Battlesnake>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Battlesnake {
+    pub id: String,
+    pub name: String,
+    pub health: u32,
+    pub body: Vec<Coord>,
+    #[serde(default)]
+    pub latency: String,
+    pub head: Coord,
+    pub length: u32,
+    #[serde(default)]
+    pub shout: String,
+    #[serde(default)]
+    pub squad: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Coord {
+    pub x: u16,
+    pub y: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveResponse {
+    #[serde(rename = "move")]
+    pub direction: Direction,
+    #[serde(default)]
+    pub shout: String,
+}
+
+// what GET / answers with
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnakeInfo {
+    pub apiversion: String,
+    pub author: String,
+    pub color: String,
+    pub head: String,
+    pub tail: String,
+    pub version: String,
+}
+
+fn to_coord(point: Point, height: u16) -> Coord {
+    Coord {
+        x: point.x,
+        y: height - 1 - point.y.min(height - 1),
+    }
+}
+
+fn to_point(coord: Coord, height: u16) -> Point {
+    Point {
+        x: coord.x,
+        y: height - 1 - coord.y.min(height - 1),
+    }
+}
+
+// our game as battlesnake would describe it to `player`
+pub fn move_request(
+    game: &SnakeGame,
+    border: bool,
+    player: usize,
+    id: &str,
+    timeout: Duration,
+) -> MoveRequest {
+    let height = game.height();
+    let snake = |player: usize| {
+        let body: Vec<Coord> = game
+            .body(player)
+            .iter()
+            .map(|point| to_coord(*point, height))
+            .collect();
+        Battlesnake {
+            id: format!("player{}", player + 1),
+            name: format!("Player {}", player + 1),
+            health: 100,
+            head: body[0],
+            length: body.len() as u32,
+            body,
+            latency: String::from("0"),
+            shout: String::new(),
+            squad: String::new(),
+        }
+    };
+
+    MoveRequest {
+        game: GameInfo {
+            id: id.to_string(),
+            ruleset: Ruleset {
+                name: String::from(if border { "standard" } else { "wrapped" }),
+                version: String::from("v1.0.0"),
+            },
+            map: String::from("standard"),
+            timeout: timeout.as_millis() as u64,
+            source: String::from("custom"),
+        },
+        turn: game.ticks(),
+        board: BattleBoard {
+            height,
+            width: game.width(),
+            food: game
+                .fruits()
+                .iter()
+                .map(|fruit| to_coord(*fruit, height))
+                .collect(),
+            hazards: Vec::new(),
+            snakes: (0..game.players()).map(snake).collect(),
+        },
+        you: snake(player),
+    }
+}
+
+// a battlesnake board in our terms, with `you` as player1 and the closest other snake as player2
+// a SnakeGame only holds two snakes so every other snake gets folded into player2's body,
+// that way the bots still see them as something not to run into
+pub fn to_board(request: &MoveRequest) -> Board {
+    let height = request.board.height;
+    let wrap = request.game.ruleset.name == "wrapped";
+    let body = |snake: &Battlesnake| -> Vec<Point> {
+        snake
+            .body
+            .iter()
+            .map(|coord| to_point(*coord, height))
+            .collect()
+    };
+    let view = |body: Vec<Point>, length: u32| SnakeView {
+        direction: heading(&body, wrap),
+        score: length.saturating_sub(3) as u16,
+        body,
+    };
+
+    let you = to_point(request.you.head, height);
+    let mut others: Vec<&Battlesnake> = request
+        .board
+        .snakes
+        .iter()
+        .filter(|snake| snake.id != request.you.id && !snake.body.is_empty())
+        .collect();
+    others.sort_by_key(|snake| {
+        let head = to_point(snake.head, height);
+        head.x.abs_diff(you.x) + head.y.abs_diff(you.y)
+    });
+
+    let mut snakes = vec![view(body(&request.you), request.you.length)];
+    if let Some((closest, rest)) = others.split_first() {
+        let mut folded = body(closest);
+        folded.extend(rest.iter().flat_map(|snake| body(snake)));
+        snakes.push(view(folded, closest.length));
+    }
+
+    Board {
+        tick: request.turn,
+        width: request.board.width,
+        height,
+        wrap,
+        you: 0,
+        snakes,
+        fruits: request
+            .board
+            .food
+            .iter()
+            .map(|coord| to_point(*coord, height))
+            .collect(),
+        game_over: false,
+        winner: 0,
+    }
+}
+
+// which way a snake is going, worked out from its head and neck
+fn heading(body: &[Point], wrap: bool) -> Direction {
+    let (Some(head), Some(neck)) = (body.first(), body.get(1)) else {
+        return Direction::Up;
+    };
+
+    let step = |from: u16, to: u16| -> i32 {
+        let delta = to as i32 - from as i32;
+        if wrap && delta.abs() > 1 {
+            -delta.signum()
+        } else {
+            delta.signum()
+        }
+    };
+
+    match (step(neck.x, head.x), step(neck.y, head.y)) {
+        (1, _) => Direction::Right,
+        (-1, _) => Direction::Left,
+        (_, 1) => Direction::Down,
+        (_, -1) => Direction::Up,
+        _ => Direction::Up, // stacked at the start of a game, anything goes
+    }
+}
+
+// a snake driven by a battlesnake server, e.g. http://localhost:8000
+// calls /start before the first move, /move every tick and /end once the game is dropped
+pub struct BattlesnakeClient {
+    url: String,
+    border: bool,
+    timeout: Duration,
+    id: String,
+    last: Option<String>,
+}
+
+impl BattlesnakeClient {
+    pub fn new(url: &str, border: bool, timeout: Duration) -> Self {
+        BattlesnakeClient {
+            url: url.trim_end_matches('/').to_string(),
+            border,
+            timeout,
+            id: format!("{:016x}", rand::random::<u64>()),
+            last: None,
+        }
+    }
+}
+
+impl Controller for BattlesnakeClient {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        let current = game.direction_of(player);
+        let request = move_request(game, self.border, player, &self.id, self.timeout);
+        let Ok(body) = serde_json::to_string(&request) else {
+            return current;
+        };
+
+        if self.last.is_none() {
+            let _ = http::post(&format!("{}/start", self.url), &body, self.timeout);
+        }
+
+        let direction = http::post(&format!("{}/move", self.url), &body, self.timeout)
+            .ok()
+            .and_then(|reply| serde_json::from_str::<MoveResponse>(&reply).ok())
+            .map_or(current, |reply| reply.direction);
+
+        self.last = Some(body);
+        direction
+    }
+}
+
+impl Drop for BattlesnakeClient {
+    fn drop(&mut self) {
+        if let Some(last) = &self.last {
+            let _ = http::post(&format!("{}/end", self.url), last, self.timeout);
+        }
+    }
+}
+
+// a game that has gone this long without a move never got its /end, its bot is dropped
+const IDLE: Duration = Duration::from_secs(60);
+
+// each game's bot and when it was last asked for a move
+type Games = HashMap<String, (Instant, Box<dyn Controller + Send>)>;
+
+// puts a game's bot back, clearing out the games that were left without an /end
+fn keep(games: &Mutex<Games>, id: String, bot: Box<dyn Controller + Send>) {
+    let mut games = games.lock().unwrap();
+    games.retain(|_, (used, _)| used.elapsed() < IDLE);
+    games.insert(id, (Instant::now(), bot));
+}
+
+// serves one of our bots as a battlesnake, `new_bot` makes a fresh one for every game
+// since some of them remember things between moves. blocks for as long as the listener lives
+pub fn serve<F>(listener: TcpListener, name: &str, new_bot: F)
+where
+    F: Fn(bool) -> Box<dyn Controller + Send> + Send + Sync + 'static,
+{
+    let games: Mutex<Games> = Mutex::new(HashMap::new());
+    let info = SnakeInfo {
+        apiversion: String::from("1"),
+        author: String::from("rust-snake"),
+        color: String::from("#3fa34d"),
+        head: String::from("default"),
+        tail: String::from("default"),
+        version: format!("{} {}", name, env!("CARGO_PKG_VERSION")),
+    };
+    let state = Arc::new((games, info, new_bot));
+
+    http::serve(listener, move |request: Request| {
+        let (games, info, new_bot) = &*state;
+
+        if request.method == "GET" {
+            return Response::json(serde_json::to_string(info).unwrap_or_default());
+        }
+
+        let parsed: MoveRequest = match serde_json::from_str(&request.body) {
+            Ok(parsed) => parsed,
+            Err(e) => return Response::bad_request(&e.to_string()),
+        };
+        let border = parsed.game.ruleset.name != "wrapped";
+        let id = parsed.game.id.clone();
+
+        match request.path.trim_end_matches('/') {
+            "/start" => {
+                keep(games, id, new_bot(border));
+                Response::json(String::from("{}"))
+            }
+            "/end" => {
+                games.lock().unwrap().remove(&id);
+                Response::json(String::from("{}"))
+            }
+            "/move" => {
+                let Some(game) = SnakeGame::from_board(&to_board(&parsed)) else {
+                    return Response::bad_request("no snakes on the board");
+                };
+                // the bot is taken out while it thinks so every other game isnt kept waiting,
+                // battlesnake asks for one move at a time per game
+                let bot = games.lock().unwrap().remove(&id);
+                let mut bot = bot.map_or_else(|| new_bot(border), |(_, bot)| bot);
+                let reply = MoveResponse {
+                    direction: bot.next_direction(&game, 0, &[]),
+                    shout: String::new(),
+                };
+                keep(games, id, bot);
+                Response::json(serde_json::to_string(&reply).unwrap_or_default())
+            }
+            _ => Response::not_found(),
+        }
+    });
+}
@@ -0,0 +1,46 @@
+use super::snake::{Direction, Point, SnakeGame};
+use serde::{Deserialize, Serialize};
+
+// everything someone outside the game needs to know about it at one tick
+// this is what gets written out as json to bots and anything else listening
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub tick: u64,
+    pub width: u16,
+    pub height: u16,
+    pub wrap: bool, // true when there are no borders and the edges teleport
+    pub you: usize, // which of the snakes the reader is driving
+    pub snakes: Vec<SnakeView>,
+    pub fruits: Vec<Point>,
+    pub game_over: bool,
+    pub winner: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnakeView {
+    pub body: Vec<Point>, // head first
+    pub direction: Direction,
+    pub score: u16,
+}
+
+impl Board {
+    pub fn of(game: &SnakeGame, border: bool, you: usize) -> Self {
+        Board {
+            tick: game.ticks(),
+            width: game.width(),
+            height: game.height(),
+            wrap: !border,
+            you,
+            snakes: (0..game.players())
+                .map(|player| SnakeView {
+                    body: game.body(player).iter().copied().collect(),
+                    direction: game.direction_of(player),
+                    score: game.score(player),
+                })
+                .collect(),
+            fruits: game.fruits().to_vec(),
+            game_over: game.game_over,
+            winner: game.winner,
+        }
+    }
+}
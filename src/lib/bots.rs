@@ -0,0 +1,66 @@
+use super::ai::GreedyBot;
+use super::battlesnake::BattlesnakeClient;
+use super::controller::Controller;
+use super::external::ProcessBot;
+use super::hamilton::HamiltonBot;
+use super::mcts::MctsBot;
+use super::neural::{Genome, NeuralBot};
+use std::time::Duration;
+
+pub const BUILT_IN: [&str; 3] = ["greedy", "hamilton", "mcts"];
+
+// every kind of bot that can be picked by name on the command line
+// a name of a built in bot (mcts:<ms> sets how long mcts thinks per move), an http:// url
+// of a battlesnake server, a .json genome saved by `snake train` or else a shell command
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bot {
+    Greedy,
+    Hamilton,
+    Mcts(Duration),
+    Battlesnake(String),
+    Trained(String),
+    Process(String),
+}
+
+impl Bot {
+    pub fn parse(spec: &str) -> Self {
+        match spec {
+            "greedy" => Bot::Greedy,
+            "hamilton" => Bot::Hamilton,
+            "mcts" => Bot::Mcts(Duration::from_millis(50)),
+            mcts if mcts.starts_with("mcts:") && mcts[5..].parse::<u64>().is_ok() => {
+                Bot::Mcts(Duration::from_millis(mcts[5..].parse().unwrap_or(50)))
+            }
+            url if url.starts_with("http://") => Bot::Battlesnake(url.to_string()),
+            path if path.ends_with(".json") => Bot::Trained(path.to_string()),
+            command => Bot::Process(command.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Bot::Greedy => String::from("greedy"),
+            Bot::Hamilton => String::from("hamilton"),
+            Bot::Mcts(budget) => format!("mcts:{}", budget.as_millis()),
+            Bot::Battlesnake(url) => url.clone(),
+            Bot::Trained(path) => path.clone(),
+            Bot::Process(command) => command.clone(),
+        }
+    }
+
+    // timeout is how long bots outside this process get to answer each move
+    pub fn build(
+        &self,
+        border: bool,
+        timeout: Duration,
+    ) -> std::io::Result<Box<dyn Controller + Send>> {
+        Ok(match self {
+            Bot::Greedy => Box::new(GreedyBot::new(border)),
+            Bot::Hamilton => Box::new(HamiltonBot::new(border)),
+            Bot::Mcts(budget) => Box::new(MctsBot::new(border, *budget)),
+            Bot::Battlesnake(url) => Box::new(BattlesnakeClient::new(url, border, timeout)),
+            Bot::Trained(path) => Box::new(NeuralBot::new(Genome::load(path)?, border)),
+            Bot::Process(command) => Box::new(ProcessBot::spawn(command, border, timeout)?),
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use super::tm_logic::read_keys;
+use crossterm::event::KeyCode;
+use crossterm::{cursor, execute, style::Print, terminal};
+use std::collections::VecDeque;
+use std::io::stdout;
+use std::time::{Duration, Instant};
+
+// talking during network games. t opens a line to type in at the bottom of the screen, enter
+// sends it and escape throws it away. the number keys send a canned emote without typing.
+// the arrows keep steering while typing so a message never costs a life, wasd dont since
+// those letters are needed for the message
+
+const EMOTES: [&str; 3] = ["gg", "nice", "oops"]; // on 1, 2 and 3
+const SHOWN_FOR: Duration = Duration::from_secs(10);
+const SHOWN: usize = 4; // lines at most, the oldest go first
+pub const LONGEST: usize = 80; // characters in one message
+
+pub struct Chat {
+    pub name: String, // who we are to everyone else
+    emotes: bool,     // off for watchers, their number keys pick who to follow
+    typing: Option<String>,
+    heard: VecDeque<(String, Instant)>,
+}
+
+impl Chat {
+    pub fn new(name: &str) -> Self {
+        Chat {
+            name: String::from(name),
+            emotes: true,
+            typing: None,
+            heard: VecDeque::new(),
+        }
+    }
+
+    pub fn watching(name: &str) -> Self {
+        Chat {
+            emotes: false,
+            ..Chat::new(name)
+        }
+    }
+
+    // poll_keys for network games: the keys that are left for the game and whatever was said
+    // this time, which is already on our screen. None once the player presses q outside chat
+    pub fn poll(&mut self) -> Option<(Vec<KeyCode>, Vec<String>)> {
+        let mut keys = Vec::new();
+        let mut said = Vec::new();
+
+        for key in read_keys() {
+            match (&mut self.typing, key) {
+                (Some(line), KeyCode::Char(c)) => {
+                    if line.chars().count() < LONGEST {
+                        line.push(c);
+                    }
+                }
+                (Some(line), KeyCode::Backspace) => {
+                    line.pop();
+                }
+                (Some(line), KeyCode::Enter) => {
+                    let text = String::from(line.trim());
+                    self.typing = None;
+                    if !text.is_empty() {
+                        said.push(text);
+                    }
+                }
+                (Some(_), KeyCode::Esc) => self.typing = None,
+                (None, KeyCode::Char('t' | 'T')) => self.typing = Some(String::new()),
+                (None, KeyCode::Char(c @ '1'..='3')) if self.emotes => {
+                    said.push(String::from(EMOTES[c as usize - '1' as usize]))
+                }
+                (None, KeyCode::Char('q' | 'Q')) => return None,
+                (_, key) => keys.push(key),
+            }
+        }
+
+        for text in &said {
+            self.heard("you", text);
+        }
+        Some((keys, said))
+    }
+
+    // names and text come off the network, so nothing in them gets to move the cursor or
+    // talk to the terminal in escape sequences
+    pub fn heard(&mut self, from: &str, text: &str) {
+        let printable = |s: &str| -> String { s.chars().filter(|c| !c.is_control()).collect() };
+        let text: String = printable(text).chars().take(LONGEST).collect();
+        self.heard
+            .push_back((format!("{}: {}", printable(from), text), Instant::now()));
+        if self.heard.len() > SHOWN {
+            self.heard.pop_front();
+        }
+    }
+
+    // the recent lines above the bottom row and the one being typed on it, over whatever
+    // was drawn this frame
+    pub fn draw(&mut self) {
+        while self
+            .heard
+            .front()
+            .is_some_and(|(_, when)| when.elapsed() > SHOWN_FOR)
+        {
+            self.heard.pop_front();
+        }
+
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let fit = |line: &str| -> String { line.chars().take(width as usize).collect() };
+        let mut stdout = stdout();
+        let bottom = height.saturating_sub(1);
+        if let Some(line) = &self.typing {
+            let _ = execute!(
+                stdout,
+                cursor::MoveTo(0, bottom),
+                Print(fit(&format!("say: {}_", line)))
+            );
+        }
+        let top = bottom.saturating_sub(self.heard.len() as u16);
+        for (i, (line, _)) in self.heard.iter().enumerate() {
+            let _ = execute!(stdout, cursor::MoveTo(0, top + i as u16), Print(fit(line)));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use super::snake::{Point, SnakeGame};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+// a local socket other programs can use to look at and poke the game being played here, for
+// scripts, stream overlays and testing the game itself. one json object per line each way and
+// every request gets one answer, as soon as the game gets to it on its next frame
+//
+//   {"type":"status"}                 {"type":"status","tick":120,"paused":false,"speed_ms":50,
+//                                      "players":[{"score":3,"length":9,"alive":true}]}
+//   {"type":"pause"}                  {"type":"ok"}
+//   {"type":"resume"}                 {"type":"ok"}
+//   {"type":"speed","speed_ms":30}    {"type":"ok"}
+//   {"type":"spawn_fruit","x":4,"y":7}   {"type":"ok","x":4,"y":7}   leave out x and y for anywhere
+//   anything it cant do               {"type":"error","reason":"..."}
+//
+// it is a unix domain socket at the path given, so only on systems that have them
+
+const SPEEDS: std::ops::RangeInclusive<u64> = 10..=1000; // ms a tick, as the welcome screen has
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Pause,
+    Resume,
+    Speed {
+        speed_ms: u64,
+    },
+    SpawnFruit {
+        #[serde(default)]
+        x: Option<u16>,
+        #[serde(default)]
+        y: Option<u16>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Answer {
+    Status {
+        tick: u64,
+        paused: bool,
+        speed_ms: u64,
+        players: Vec<PlayerStatus>,
+    },
+    Ok {
+        #[serde(flatten, skip_serializing_if = "Option::is_none")]
+        fruit: Option<Point>,
+    },
+    Error {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerStatus {
+    pub score: u16,
+    pub length: usize,
+    pub alive: bool,
+}
+
+// what the game loop goes by that the socket can change
+pub struct Settings {
+    pub paused: bool,
+    pub speed: Duration,
+    pub meddled: bool, // fruit or speed changed, the replay wouldnt check out any more
+    pub border: bool,  // of the game being played, fixed for the whole of it
+}
+
+impl Settings {
+    pub fn new(speed_ms: u64, border: bool) -> Self {
+        Settings {
+            paused: false,
+            speed: Duration::from_millis(speed_ms),
+            meddled: false,
+            border,
+        }
+    }
+}
+
+pub struct Control {
+    requests: Receiver<(Request, Sender<Answer>)>,
+    path: PathBuf,
+}
+
+impl Control {
+    // a socket file left behind by a game that crashed is taken over, one still answering isnt
+    #[cfg(unix)]
+    pub fn listen(path: &Path) -> io::Result<Self> {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::{UnixListener, UnixStream};
+        use std::thread;
+
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another game is using it",
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let Ok(reader) = stream.try_clone().map(BufReader::new) else {
+                        return;
+                    };
+                    for line in reader.lines() {
+                        let Ok(line) = line else { break };
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        let answer = match serde_json::from_str(&line) {
+                            Ok(request) => {
+                                let (answer, answered) = mpsc::channel();
+                                if sender.send((request, answer)).is_err() {
+                                    break;
+                                }
+                                // nothing back means the game is over
+                                let Ok(answer) = answered.recv() else { break };
+                                answer
+                            }
+                            Err(e) => Answer::Error {
+                                reason: e.to_string(),
+                            },
+                        };
+                        let mut json = serde_json::to_string(&answer).unwrap();
+                        json.push('\n');
+                        if stream.write_all(json.as_bytes()).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Control {
+            requests,
+            path: path.to_path_buf(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen(_path: &Path) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this system has no unix domain sockets",
+        ))
+    }
+
+    // everything asked since last time, once a frame
+    pub fn answer(&self, game: &mut SnakeGame, settings: &mut Settings) {
+        for (request, answer) in self.requests.try_iter() {
+            let _ = answer.send(handle(request, game, settings));
+        }
+    }
+}
+
+// the socket file goes with the game
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn handle(request: Request, game: &mut SnakeGame, settings: &mut Settings) -> Answer {
+    match request {
+        Request::Status => Answer::Status {
+            tick: game.ticks(),
+            paused: settings.paused,
+            speed_ms: settings.speed.as_millis() as u64,
+            players: (0..game.players())
+                .map(|player| PlayerStatus {
+                    score: game.score(player),
+                    length: game.body(player).len(),
+                    alive: alive(game, player),
+                })
+                .collect(),
+        },
+        Request::Pause | Request::Resume => {
+            settings.paused = matches!(request, Request::Pause);
+            Answer::Ok { fruit: None }
+        }
+        Request::Speed { speed_ms } if SPEEDS.contains(&speed_ms) => {
+            settings.speed = Duration::from_millis(speed_ms);
+            settings.meddled = true;
+            Answer::Ok { fruit: None }
+        }
+        Request::Speed { .. } => Answer::Error {
+            reason: format!(
+                "the speed is {} to {}ms a tick",
+                SPEEDS.start(),
+                SPEEDS.end()
+            ),
+        },
+        Request::SpawnFruit { x, y } => {
+            let at = match (x, y) {
+                (Some(x), Some(y)) => Some(Point { x, y }),
+                (None, None) => None,
+                _ => {
+                    return Answer::Error {
+                        reason: String::from("a fruit needs both x and y, or neither"),
+                    }
+                }
+            };
+            match game.place_fruit(at, settings.border) {
+                Ok(fruit) => {
+                    settings.meddled = true;
+                    Answer::Ok { fruit: Some(fruit) }
+                }
+                Err(reason) => Answer::Error { reason },
+            }
+        }
+    }
+}
+
+// whoever crashed is dead, a single player who filled the board won instead
+fn alive(game: &SnakeGame, player: usize) -> bool {
+    !game.game_over || game.winner as usize == player + 1
+}
@@ -0,0 +1,161 @@
+use super::snake::{Direction, SnakeGame};
+use crossterm::event::KeyCode;
+use std::sync::mpsc::Receiver;
+
+// anything that can drive a snake: a person on the keyboard, a bot, a replay or a peer
+// asked exactly once per tick per snake, the answer is handed to SnakeGame::steer
+// keys are everything polled from the terminal this tick, so two people on one
+// keyboard can share them without stealing each others key presses
+pub trait Controller {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, keys: &[KeyCode]) -> Direction;
+}
+
+pub enum KeyCluster {
+    Arrows,
+    Wasd,
+}
+
+pub struct KeyboardController {
+    cluster: KeyCluster,
+}
+
+impl KeyboardController {
+    pub fn new(cluster: KeyCluster) -> Self {
+        KeyboardController { cluster }
+    }
+
+    pub fn arrows() -> Self {
+        Self::new(KeyCluster::Arrows)
+    }
+
+    pub fn wasd() -> Self {
+        Self::new(KeyCluster::Wasd)
+    }
+
+    fn map(&self, key: KeyCode) -> Option<Direction> {
+        match (&self.cluster, key) {
+            (KeyCluster::Arrows, KeyCode::Up) => Some(Direction::Up),
+            (KeyCluster::Arrows, KeyCode::Down) => Some(Direction::Down),
+            (KeyCluster::Arrows, KeyCode::Left) => Some(Direction::Left),
+            (KeyCluster::Arrows, KeyCode::Right) => Some(Direction::Right),
+            (KeyCluster::Wasd, KeyCode::Char('w')) => Some(Direction::Up),
+            (KeyCluster::Wasd, KeyCode::Char('s')) => Some(Direction::Down),
+            (KeyCluster::Wasd, KeyCode::Char('a')) => Some(Direction::Left),
+            (KeyCluster::Wasd, KeyCode::Char('d')) => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl KeyboardController {
+    // the last key of our cluster that isnt a u-turn from `current` wins
+    pub fn turn(&self, current: Direction, keys: &[KeyCode]) -> Direction {
+        keys.iter()
+            .rev()
+            .filter_map(|key| self.map(*key))
+            .find(|direction| *direction != current.opposite())
+            .unwrap_or(current)
+    }
+}
+
+impl Controller for KeyboardController {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, keys: &[KeyCode]) -> Direction {
+        self.turn(game.direction_of(player), keys)
+    }
+}
+
+// plays the same moves over and over, handy for demos and for poking at the engine
+pub struct ScriptedController {
+    moves: Vec<Direction>,
+    next: usize,
+}
+
+impl ScriptedController {
+    pub fn new(moves: Vec<Direction>) -> Self {
+        ScriptedController { moves, next: 0 }
+    }
+}
+
+impl Controller for ScriptedController {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        if self.moves.is_empty() {
+            return game.direction_of(player);
+        }
+
+        let direction = self.moves[self.next % self.moves.len()];
+        self.next += 1;
+        direction
+    }
+}
+
+// plays back the moves a RecordingController saw, once, then keeps going straight
+// only matches the original game if it was started with the same seed
+pub struct ReplayController {
+    moves: std::vec::IntoIter<Direction>,
+}
+
+impl ReplayController {
+    pub fn new(moves: Vec<Direction>) -> Self {
+        ReplayController {
+            moves: moves.into_iter(),
+        }
+    }
+}
+
+impl Controller for ReplayController {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        self.moves.next().unwrap_or(game.direction_of(player))
+    }
+}
+
+// wraps another controller and remembers every direction it handed out
+pub struct RecordingController<C: Controller> {
+    inner: C,
+    moves: Vec<Direction>,
+}
+
+impl<C: Controller> RecordingController<C> {
+    pub fn new(inner: C) -> Self {
+        RecordingController {
+            inner,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn moves(&self) -> &[Direction] {
+        &self.moves
+    }
+
+    pub fn into_replay(self) -> ReplayController {
+        ReplayController::new(self.moves)
+    }
+}
+
+impl<C: Controller> Controller for RecordingController<C> {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, keys: &[KeyCode]) -> Direction {
+        let direction = self.inner.next_direction(game, player, keys);
+        self.moves.push(direction);
+        direction
+    }
+}
+
+// directions arriving from somewhere else, a socket thread for example
+// if nothing new came in this tick the snake keeps going the way it was
+pub struct RemoteController {
+    inbox: Receiver<Direction>,
+}
+
+impl RemoteController {
+    pub fn new(inbox: Receiver<Direction>) -> Self {
+        RemoteController { inbox }
+    }
+}
+
+impl Controller for RemoteController {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        self.inbox
+            .try_iter()
+            .last()
+            .unwrap_or(game.direction_of(player))
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// finding games on the local network without typing addresses
+// a host waiting for players shouts an Announcement once a second on DISCOVERY_PORT, to the
+// broadcast address, to a multicast group and to loopback so games on the same machine
+// show up too. the join screen listens there and lists whatever it heard recently
+
+pub const DISCOVERY_PORT: u16 = 47777;
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 77, 77);
+const EVERY: Duration = Duration::from_secs(1);
+const FORGET: Duration = Duration::from_secs(3); // a host not heard from in this long is gone
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub name: String,
+    pub port: u16, // where the game itself is, on the address the announcement came from
+    pub players: u8,
+    pub max_players: u8,
+    pub width: u16,
+    pub height: u16,
+    pub border: bool,
+    pub speed_ms: u64,
+    pub mode: String, // "hosted", "lockstep" or "rollback"
+}
+
+impl Announcement {
+    // one line for the join screen
+    pub fn describe(&self) -> String {
+        format!(
+            "{}  {}/{} players  {}x{}  {}  {}",
+            self.name,
+            self.players,
+            self.max_players,
+            self.width,
+            self.height,
+            if self.border { "borders" } else { "wrapping" },
+            self.mode
+        )
+    }
+}
+
+// the name games on this machine go by, unless one is given
+pub fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("snake"))
+}
+
+// keeps announcing until dropped
+pub struct Announcer {
+    stop: Arc<AtomicBool>,
+}
+
+impl Announcer {
+    pub fn start(announcement: Announcement) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_broadcast(true)?;
+        let _ = socket.set_multicast_loop_v4(true);
+        let line = serde_json::to_vec(&announcement)?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                // any of these can fail depending on the network, the others may still get through
+                for address in [Ipv4Addr::BROADCAST, GROUP, Ipv4Addr::LOCALHOST] {
+                    let _ = socket.send_to(&line, (address, DISCOVERY_PORT));
+                }
+                thread::sleep(EVERY);
+            }
+        });
+
+        Ok(Announcer { stop })
+    }
+}
+
+impl Drop for Announcer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// listens for announcements until dropped
+pub struct Browser {
+    heard: Arc<Mutex<HashMap<SocketAddr, (Announcement, Instant)>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Browser {
+    pub fn start() -> io::Result<Self> {
+        let socket = shared_socket()?;
+        let _ = socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED);
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+
+        let heard = Arc::new(Mutex::new(HashMap::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (seen, stopped) = (Arc::clone(&heard), Arc::clone(&stop));
+
+        thread::spawn(move || {
+            let mut buffer = [0; 2048];
+            while !stopped.load(Ordering::Relaxed) {
+                let Ok((length, from)) = socket.recv_from(&mut buffer) else {
+                    continue;
+                };
+                let Ok(announcement) = serde_json::from_slice::<Announcement>(&buffer[..length])
+                else {
+                    continue;
+                };
+                // the same host comes in once per way it was sent, the port keeps them apart
+                let game = SocketAddr::new(from.ip(), announcement.port);
+                seen.lock()
+                    .unwrap()
+                    .insert(game, (announcement, Instant::now()));
+            }
+        });
+
+        Ok(Browser { heard, stop })
+    }
+
+    // games heard from lately with where to connect to them, in a steady order
+    pub fn games(&self) -> Vec<(SocketAddr, Announcement)> {
+        let mut heard = self.heard.lock().unwrap();
+        heard.retain(|_, (_, when)| when.elapsed() < FORGET);
+
+        // one host heard over loopback and the lan is the same game, keep the lan address
+        let mut games: Vec<(SocketAddr, Announcement)> = Vec::new();
+        let mut listed: Vec<(SocketAddr, Announcement)> = heard
+            .iter()
+            .map(|(address, (announcement, _))| (*address, announcement.clone()))
+            .collect();
+        listed.sort_by_key(|(address, _)| (address.ip().is_loopback(), *address));
+        for (address, announcement) in listed {
+            let duplicate = games.iter().any(|(seen, other)| {
+                *other == announcement
+                    && seen.port() == address.port()
+                    && address.ip().is_loopback()
+            });
+            if !duplicate {
+                games.push((address, announcement));
+            }
+        }
+
+        games.sort_by(|a, b| a.1.name.cmp(&b.1.name).then(a.0.cmp(&b.0)));
+        games
+    }
+}
+
+// the discovery port, shared with any other join screen open on this machine. every one of
+// them hears announcements sent to broadcast or the group, but the ones sent to loopback
+// only reach one
+fn shared_socket() -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)).into())?;
+    Ok(socket.into())
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
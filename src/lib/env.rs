@@ -0,0 +1,327 @@
+use super::controller::Controller;
+use super::snake::{Direction, Multiplayer, Point, SnakeGame};
+
+// a gym style environment for training agents on the game we actually play
+// the agent is always player1, an optional opponent controller plays player2
+//
+//   let mut env = SnakeEnv::new(EnvConfig::default())?;
+//   let mut observation = env.reset(42);
+//   loop {
+//       let (next, reward, done, info) = env.step(Direction::ALL[policy(&observation)]);
+//       if done { break; }
+//       observation = next;
+//   }
+
+#[derive(Clone, Debug)]
+pub struct Rewards {
+    pub fruit: f32,
+    pub death: f32,
+    pub step: f32, // every tick, usually a small penalty so the agent doesnt dawdle
+    pub win: f32,  // filled the board or outlived the opponent
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            fruit: 1.0,
+            death: -1.0,
+            step: -0.01,
+            win: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Encoding {
+    // channels of (height + 2) x (width + 2), the extra ring is where the walls are
+    // own head, own body, other snake, fruit, wall
+    Grid,
+    // channels of (2r + 1) x (2r + 1) centred on the head: snake, fruit, wall
+    Window(u16),
+    // danger up/down/left/right, heading one hot, fruit up/down/left/right,
+    // fruit dx and dy over the board size, length over the board size
+    Features,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    pub width: u16,
+    pub height: u16,
+    pub border: bool,
+    pub max_ticks: u64, // episodes get cut off here, see Info::truncated
+    pub rewards: Rewards,
+    pub encoding: Encoding,
+}
+
+impl EnvConfig {
+    // the snake starts three long across the middle and the first fruit goes inside the
+    // edges, anything smaller has no room for them
+    pub fn check(&self) -> Result<(), String> {
+        if self.width < 5 || self.height < 3 {
+            return Err(format!(
+                "a {}x{} board is too small, it needs to be at least 5x3",
+                self.width, self.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            width: 20,
+            height: 20,
+            border: true,
+            max_ticks: 2000,
+            rewards: Rewards::default(),
+            encoding: Encoding::Grid,
+        }
+    }
+}
+
+// a flat f32 tensor, row major in the order of shape
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Info {
+    pub score: u16,
+    pub length: usize,
+    pub ticks: u64,
+    pub ate: bool,
+    pub won: bool,
+    pub truncated: bool, // done because of max_ticks, not because the game ended
+}
+
+pub struct SnakeEnv {
+    config: EnvConfig, // checked in new, so it cant be changed after
+    game: SnakeGame,
+    opponent: Option<Box<dyn Controller>>,
+}
+
+impl SnakeEnv {
+    pub fn new(config: EnvConfig) -> Result<Self, String> {
+        config.check()?;
+        let game = SnakeGame::new_seeded(config.width, config.height, 0);
+        Ok(SnakeEnv {
+            config,
+            game,
+            opponent: None,
+        })
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    // the opponent plays player2 from the next reset on
+    pub fn with_opponent(mut self, opponent: Box<dyn Controller>) -> Self {
+        self.opponent = Some(opponent);
+        self
+    }
+
+    pub fn game(&self) -> &SnakeGame {
+        &self.game
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = if self.opponent.is_some() {
+            SnakeGame::multiplayer_new_seeded(self.config.width, self.config.height, seed)
+        } else {
+            SnakeGame::new_seeded(self.config.width, self.config.height, seed)
+        };
+        self.observe()
+    }
+
+    // once done is true call reset before stepping again
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool, Info) {
+        let score = self.game.score(0);
+
+        self.game.steer(0, action);
+        if let Some(opponent) = &mut self.opponent {
+            let direction = opponent.next_direction(&self.game, 1, &[]);
+            self.game.steer(1, direction);
+        }
+        self.game.tick(self.config.border);
+
+        let rewards = &self.config.rewards;
+        let ate = self.game.score(0) > score;
+        let won = self.game.game_over && self.game.winner == 1;
+        let lost = self.game.game_over && !won;
+        let truncated = !self.game.game_over && self.game.ticks() >= self.config.max_ticks;
+
+        let mut reward = rewards.step;
+        if ate {
+            reward += rewards.fruit;
+        }
+        if won {
+            reward += rewards.win;
+        }
+        if lost && self.game.winner != 3 {
+            reward += rewards.death;
+        }
+
+        let info = Info {
+            score: self.game.score(0),
+            length: self.game.body(0).len(),
+            ticks: self.game.ticks(),
+            ate,
+            won,
+            truncated,
+        };
+        (self.observe(), reward, self.game.game_over || truncated, info)
+    }
+
+    pub fn observe(&self) -> Observation {
+        match self.config.encoding {
+            Encoding::Grid => grid(&self.game, self.config.border),
+            Encoding::Window(radius) => window(&self.game, self.config.border, radius),
+            Encoding::Features => features(&self.game, self.config.border),
+        }
+    }
+}
+
+pub fn grid(game: &SnakeGame, border: bool) -> Observation {
+    let (width, height) = (game.width() as usize + 2, game.height() as usize + 2);
+    let plane = width * height;
+    let mut data = vec![0.0; 5 * plane];
+    let mut mark = |channel: usize, point: Point| {
+        if point.x < game.width() && point.y < game.height() {
+            data[channel * plane + (point.y as usize + 1) * width + point.x as usize + 1] = 1.0;
+        }
+    };
+
+    mark(0, game.body(0)[0]);
+    for segment in game.body(0).iter().skip(1) {
+        mark(1, *segment);
+    }
+    if game.players() == 2 {
+        for segment in game.body(1) {
+            mark(2, *segment);
+        }
+    }
+    for fruit in game.fruits() {
+        mark(3, *fruit);
+    }
+
+    if border {
+        for y in 0..height {
+            for x in 0..width {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    data[4 * plane + y * width + x] = 1.0;
+                }
+            }
+        }
+    }
+
+    Observation {
+        shape: vec![5, height, width],
+        data,
+    }
+}
+
+pub fn window(game: &SnakeGame, border: bool, radius: u16) -> Observation {
+    let side = 2 * radius as usize + 1;
+    let plane = side * side;
+    let mut data = vec![0.0; 3 * plane];
+    let head = game.body(0)[0];
+    let (width, height) = (game.width() as i32, game.height() as i32);
+
+    let mut taken = vec![false; (width * height) as usize];
+    for player in 0..game.players() {
+        for segment in game.body(player) {
+            if (segment.x as i32) < width && (segment.y as i32) < height {
+                taken[segment.y as usize * width as usize + segment.x as usize] = true;
+            }
+        }
+    }
+
+    for dy in 0..side {
+        for dx in 0..side {
+            let mut x = head.x as i32 + dx as i32 - radius as i32;
+            let mut y = head.y as i32 + dy as i32 - radius as i32;
+            let cell = dy * side + dx;
+
+            if !(0..width).contains(&x) || !(0..height).contains(&y) {
+                if border {
+                    data[2 * plane + cell] = 1.0;
+                    continue;
+                }
+                x = x.rem_euclid(width);
+                y = y.rem_euclid(height);
+            }
+
+            let point = Point {
+                x: x as u16,
+                y: y as u16,
+            };
+            if taken[y as usize * width as usize + x as usize] {
+                data[cell] = 1.0;
+            }
+            if game.fruits().contains(&point) {
+                data[plane + cell] = 1.0;
+            }
+        }
+    }
+
+    Observation {
+        shape: vec![3, side, side],
+        data,
+    }
+}
+
+pub fn features(game: &SnakeGame, border: bool) -> Observation {
+    features_of(game, border, 0)
+}
+
+// the same features from the point of view of any player
+pub fn features_of(game: &SnakeGame, border: bool, player: usize) -> Observation {
+    let head = game.body(player)[0];
+    let heading = game.direction_of(player);
+    let (width, height) = (game.width() as f32, game.height() as f32);
+
+    let danger = Direction::ALL.map(|direction| {
+        match game.next_point(head, direction, border) {
+            None => 1.0,
+            Some(next) => {
+                let hit = (0..game.players())
+                    .any(|player| game.body(player).iter().any(|segment| *segment == next));
+                if hit {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    });
+    let heading = Direction::ALL.map(|direction| if direction == heading { 1.0 } else { 0.0 });
+
+    let fruit = game
+        .fruits()
+        .iter()
+        .min_by_key(|fruit| fruit.x.abs_diff(head.x) + fruit.y.abs_diff(head.y))
+        .copied()
+        .unwrap_or(head);
+    let dx = fruit.x as f32 - head.x as f32;
+    let dy = fruit.y as f32 - head.y as f32;
+    let flag = |yes: bool| if yes { 1.0 } else { 0.0 };
+
+    let mut data = Vec::with_capacity(15);
+    data.extend(danger);
+    data.extend(heading);
+    data.extend([flag(dy < 0.0), flag(dy > 0.0), flag(dx < 0.0), flag(dx > 0.0)]);
+    data.extend([
+        dx / width,
+        dy / height,
+        game.body(player).len() as f32 / (width * height),
+    ]);
+
+    Observation {
+        shape: vec![data.len()],
+        data,
+    }
+}
@@ -0,0 +1,210 @@
+use super::arena::Rules;
+use super::bots::Bot;
+use super::controller::Controller;
+use super::neural::{Genome, NeuralBot};
+use super::snake::{Multiplayer, SnakeGame};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// plain genetic algorithm over NeuralBot genomes, played on the headless engine
+// every generation all genomes play the same seeds so their fitness is comparable,
+// the best few carry over untouched and the rest are bred from tournament picks
+pub struct Training {
+    pub population: usize,
+    pub generations: u32,
+    pub hidden: Vec<usize>,
+    pub rules: Rules,
+    pub games: usize,          // games per genome per generation
+    pub opponent: Option<Bot>, // None trains on single player games
+    pub elite: usize,
+    pub mutation_rate: f32, // chance of each weight being nudged
+    pub mutation_size: f32, // standard deviation of a nudge
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for Training {
+    fn default() -> Self {
+        Training {
+            population: 100,
+            generations: 50,
+            hidden: vec![16],
+            rules: Rules {
+                width: 20,
+                height: 20,
+                border: true,
+                max_ticks: 2000,
+            },
+            games: 4,
+            opponent: None,
+            elite: 4,
+            mutation_rate: 0.1,
+            mutation_size: 0.3,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+// what a genome earned in one game: 100 per fruit, a little for staying alive
+// and 500 for beating the opponent. a snake that goes a whole board's worth of
+// ticks without eating is stopped so circling forever doesnt pay
+fn fitness(genome: &Genome, rules: &Rules, opponent: Option<&Bot>, seed: u64) -> io::Result<f32> {
+    let mut bot = NeuralBot::new(genome.clone(), rules.border);
+    let mut opponent = match opponent {
+        Some(bot) => Some(bot.build(rules.border, Duration::from_millis(200))?),
+        None => None,
+    };
+    let mut game = if opponent.is_some() {
+        SnakeGame::multiplayer_new_seeded(rules.width, rules.height, seed)
+    } else {
+        SnakeGame::new_seeded(rules.width, rules.height, seed)
+    };
+
+    let patience = rules.width as u64 * rules.height as u64;
+    let mut last_meal = 0;
+    let mut score = 0;
+    while !game.game_over && game.ticks() < rules.max_ticks && game.ticks() - last_meal < patience {
+        let direction = bot.next_direction(&game, 0, &[]);
+        game.steer(0, direction);
+        if let Some(opponent) = &mut opponent {
+            let direction = opponent.next_direction(&game, 1, &[]);
+            game.steer(1, direction);
+        }
+        game.tick(rules.border);
+
+        if game.score(0) > score {
+            score = game.score(0);
+            last_meal = game.ticks();
+        }
+    }
+
+    let mut fitness = score as f32 * 100.0 + game.ticks() as f32 * 0.1;
+    if game.game_over && game.winner == 1 {
+        fitness += 500.0;
+    }
+    Ok(fitness)
+}
+
+impl Training {
+    // `generation` is told after every generation with its best genome and the mean fitness
+    // gives back the best genome seen over the whole run
+    pub fn run(&self, mut generation: impl FnMut(&Genome, f32)) -> io::Result<Genome> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let size = self.population.max(2);
+        let mut population: Vec<Genome> = (0..size)
+            .map(|_| Genome::random(&self.hidden, &mut rng))
+            .collect();
+        let mut best: Option<Genome> = None;
+
+        for number in 0..self.generations {
+            let seed = self
+                .seed
+                .wrapping_add(number as u64 * self.games as u64)
+                .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            let scores = self.evaluate(&population, seed)?;
+            for (genome, score) in population.iter_mut().zip(&scores) {
+                genome.fitness = *score;
+                genome.generation = number + 1;
+            }
+            population.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+
+            let mean = scores.iter().sum::<f32>() / scores.len() as f32;
+            generation(&population[0], mean);
+            if best
+                .as_ref()
+                .is_none_or(|best| population[0].fitness > best.fitness)
+            {
+                best = Some(population[0].clone());
+            }
+
+            let mut next: Vec<Genome> = population.iter().take(self.elite).cloned().collect();
+            while next.len() < size {
+                let mother = tournament(&population, &mut rng);
+                let father = tournament(&population, &mut rng);
+                next.push(self.breed(mother, father, &mut rng));
+            }
+            population = next;
+        }
+
+        Ok(best.unwrap_or_else(|| population.swap_remove(0)))
+    }
+
+    // average fitness of every genome over `games` games, spread over all threads
+    fn evaluate(&self, population: &[Genome], seed: u64) -> io::Result<Vec<f32>> {
+        let next = AtomicUsize::new(0);
+        let scores: Mutex<Vec<io::Result<f32>>> =
+            Mutex::new((0..population.len()).map(|_| Ok(0.0)).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(genome) = population.get(index) else {
+                        break;
+                    };
+
+                    let total = (0..self.games.max(1) as u64)
+                        .map(|game| {
+                            fitness(
+                                genome,
+                                &self.rules,
+                                self.opponent.as_ref(),
+                                seed.wrapping_add(game),
+                            )
+                        })
+                        .sum::<io::Result<f32>>();
+                    scores.lock().unwrap()[index] =
+                        total.map(|total| total / self.games.max(1) as f32);
+                });
+            }
+        });
+
+        scores.into_inner().unwrap().into_iter().collect()
+    }
+
+    // uniform crossover, then gaussian nudges
+    fn breed(&self, mother: &Genome, father: &Genome, rng: &mut StdRng) -> Genome {
+        let weights = mother
+            .weights
+            .iter()
+            .zip(&father.weights)
+            .map(|(m, f)| {
+                let weight = if rng.gen_bool(0.5) { *m } else { *f };
+                if rng.gen::<f32>() < self.mutation_rate {
+                    weight + gaussian(rng) * self.mutation_size
+                } else {
+                    weight
+                }
+            })
+            .collect();
+
+        Genome {
+            layers: mother.layers.clone(),
+            weights,
+            fitness: 0.0,
+            generation: 0,
+        }
+    }
+}
+
+// the fittest of three random picks, population is sorted best first
+fn tournament<'a>(population: &'a [Genome], rng: &mut StdRng) -> &'a Genome {
+    let pick = (0..3)
+        .map(|_| rng.gen_range(0..population.len()))
+        .min()
+        .unwrap_or(0);
+    &population[pick]
+}
+
+// box muller, rand alone has no normal distribution
+fn gaussian(rng: &mut StdRng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let v: f32 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
@@ -0,0 +1,138 @@
+use super::board::Board;
+use super::controller::Controller;
+use super::snake::{Direction, SnakeGame};
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+// a snake driven by some other program, written in whatever language
+// every tick it gets one line of json on stdin (see board::Board) and has to answer
+// with one line on stdout: up, down, left or right, either bare, as a json string
+// or as {"move": "up"}. no answer in time means the snake keeps going straight
+pub struct ProcessBot {
+    child: Child,
+    boards: SyncSender<String>,
+    replies: Receiver<String>,
+    border: bool,
+    timeout: Duration,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(rename = "move")]
+    direction: Direction,
+}
+
+impl ProcessBot {
+    // the command goes through the shell so "python3 bot.py --fast" works as is
+    // stderr is thrown away, anything printed there would end up all over the game
+    pub fn spawn(command: &str, border: bool, timeout: Duration) -> std::io::Result<Self> {
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // writes on its own thread too, a bot that stops reading fills the pipe and a write
+        // to it would block the game. one board waits while another is being written
+        let (boards, waiting) = mpsc::sync_channel::<String>(1);
+        thread::spawn(move || {
+            for board in waiting {
+                if writeln!(stdin, "{}", board)
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        // reads on its own thread so a slow bot can be timed out
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ProcessBot {
+            child,
+            boards,
+            replies,
+            border,
+            timeout,
+        })
+    }
+}
+
+impl Controller for ProcessBot {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        let current = game.direction_of(player);
+
+        // anything still queued is a late answer to an earlier tick
+        while self.replies.try_recv().is_ok() {}
+
+        let Ok(board) = serde_json::to_string(&Board::of(game, self.border, player)) else {
+            return current;
+        };
+        // still stuck on an earlier board, or gone
+        if self.boards.try_send(board).is_err() {
+            return current;
+        }
+
+        match self.replies.recv_timeout(self.timeout) {
+            Ok(reply) => parse_move(&reply).unwrap_or(current),
+            Err(_) => current,
+        }
+    }
+}
+
+impl Drop for ProcessBot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn parse_move(reply: &str) -> Option<Direction> {
+    let reply = reply.trim();
+
+    if let Ok(reply) = serde_json::from_str::<Reply>(reply) {
+        return Some(reply.direction);
+    }
+    if let Ok(direction) = serde_json::from_str::<Direction>(reply) {
+        return Some(direction);
+    }
+
+    match reply.to_lowercase().as_str() {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
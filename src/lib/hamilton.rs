@@ -0,0 +1,225 @@
+use super::ai::{GreedyBot, Occupancy};
+use super::controller::Controller;
+use super::snake::{Direction, Point, SnakeGame};
+use crossterm::event::KeyCode;
+
+// a closed path that visits every cell once, snake zigzags along the rows
+// and comes back up the first column, e.g. on a 4x4 board
+//
+//    0 -> 1 -> 2 -> 3
+//    ^              v
+//   15    6 <- 5 <- 4
+//    ^    v
+//   14    7 -> 8 -> 9
+//    ^              v
+//   13 <-12 <-11 <-10
+//
+// needs an even number of rows, with walls an even number of columns works too
+// by going down the columns instead. on the torus an odd last row is spliced in
+// by wrapping round the right edge. an odd by odd board with walls has no cycle at all
+pub fn hamiltonian_cycle(width: u16, height: u16, border: bool) -> Option<Vec<Point>> {
+    if width < 2 || height < 2 {
+        return None;
+    }
+
+    if height.is_multiple_of(2) {
+        return Some(zigzag(width, height));
+    }
+
+    if !border && height >= 3 {
+        let mut cycle = zigzag(width, height - 1);
+        // zigzag always ends (1, h - 2) -> (0, h - 2) -> back up the first column
+        let at = cycle
+            .iter()
+            .position(|point| point.x == 0 && point.y == height - 2)
+            .unwrap();
+        let last_row = (1..width)
+            .chain(std::iter::once(0))
+            .map(|x| Point { x, y: height - 1 });
+        cycle.splice(at..at, last_row);
+        return Some(cycle);
+    }
+
+    if width.is_multiple_of(2) {
+        let cycle = zigzag(height, width);
+        return Some(
+            cycle
+                .into_iter()
+                .map(|point| Point {
+                    x: point.y,
+                    y: point.x,
+                })
+                .collect(),
+        );
+    }
+
+    None
+}
+
+fn zigzag(width: u16, height: u16) -> Vec<Point> {
+    let mut cycle = vec![Point { x: 0, y: 0 }];
+
+    for y in 0..height {
+        if y.is_multiple_of(2) {
+            cycle.extend((1..width).map(|x| Point { x, y }));
+        } else {
+            cycle.extend((1..width).rev().map(|x| Point { x, y }));
+        }
+    }
+    cycle.extend((1..height).rev().map(|y| Point { x: 0, y }));
+
+    cycle
+}
+
+// follows a hamiltonian cycle so it can never trap itself and always fills the board
+// while the snake is short it cuts across the cycle towards the fruit, but only forward
+// along the cycle so the body stays in cycle order behind the head
+// falls back to the greedy bot when there is no cycle or another snake is in the way
+pub struct HamiltonBot {
+    border: bool,
+    cycle: Vec<Point>,
+    order: Vec<usize>, // position of every cell on the cycle
+    width: u16,
+    fallback: GreedyBot,
+}
+
+impl HamiltonBot {
+    pub fn new(border: bool) -> Self {
+        HamiltonBot {
+            border,
+            cycle: Vec::new(),
+            order: Vec::new(),
+            width: 0,
+            fallback: GreedyBot::new(border),
+        }
+    }
+
+    // built on the first tick since thats when we first see the board
+    // runs the cycle whichever way round the starting body already lies
+    fn build(&mut self, game: &SnakeGame, player: usize) {
+        let Some(cycle) = hamiltonian_cycle(game.width(), game.height(), self.border) else {
+            return;
+        };
+
+        self.width = game.width();
+        self.cycle = cycle;
+        self.index();
+
+        let body = game.body(player);
+        let forward = body.iter().zip(body.iter().skip(1)).all(|(head, behind)| {
+            self.at(*behind).map(|i| (i + 1) % self.cycle.len()) == self.at(*head)
+        });
+        if !forward {
+            self.cycle.reverse();
+            self.index();
+        }
+    }
+
+    fn index(&mut self) {
+        self.order = vec![0; self.cycle.len()];
+        for (i, point) in self.cycle.iter().enumerate() {
+            self.order[point.y as usize * self.width as usize + point.x as usize] = i;
+        }
+    }
+
+    fn at(&self, point: Point) -> Option<usize> {
+        if point.x >= self.width || self.cycle.is_empty() {
+            return None;
+        }
+        self.order
+            .get(point.y as usize * self.width as usize + point.x as usize)
+            .copied()
+    }
+
+    // how far along the cycle a cell is, counting from the tail
+    fn past_tail(&self, point: Point, tail: usize) -> Option<usize> {
+        self.at(point)
+            .map(|i| (i + self.cycle.len() - tail) % self.cycle.len())
+    }
+}
+
+impl Controller for HamiltonBot {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, keys: &[KeyCode]) -> Direction {
+        if self.cycle.is_empty() {
+            self.build(game, player);
+        }
+        if self.cycle.is_empty() {
+            return self.fallback.next_direction(game, player, keys);
+        }
+
+        let body = game.body(player);
+        let cells = self.cycle.len();
+        let head = body[0];
+        let Some(tail) = body.back().and_then(|tail| self.at(*tail)) else {
+            return self.fallback.next_direction(game, player, keys);
+        };
+        let distances: Option<Vec<usize>> = body
+            .iter()
+            .map(|point| self.past_tail(*point, tail))
+            .collect();
+        let Some(distances) = distances else {
+            return self.fallback.next_direction(game, player, keys);
+        };
+        let ahead = distances[0];
+
+        // every segment is further round the cycle than the one behind it
+        let in_order = distances.windows(2).all(|pair| pair[0] > pair[1]);
+        let occupancy = Occupancy::of(game);
+        let step = |point: Point| {
+            Direction::ALL
+                .into_iter()
+                .find(|direction| game.next_point(head, *direction, self.border) == Some(point))
+        };
+
+        if in_order {
+            // a shortcut may skip ahead to the fruit but never past it, and only while the
+            // snake and whatever it skips still fit in half the board
+            if body.len() < cells / 2 {
+                let fruit = game
+                    .fruits()
+                    .iter()
+                    .filter_map(|fruit| self.past_tail(*fruit, tail))
+                    .filter(|distance| *distance > ahead)
+                    .min()
+                    .unwrap_or(cells);
+                let limit = fruit.min(cells / 2 - 1);
+
+                let shortcut = Direction::ALL
+                    .into_iter()
+                    .filter(|direction| *direction != game.direction_of(player).opposite())
+                    .filter_map(|direction| {
+                        let next = game.next_point(head, direction, self.border)?;
+                        let distance = self.past_tail(next, tail)?;
+                        (occupancy.is_free(next) && distance > ahead && distance <= limit)
+                            .then_some((direction, distance))
+                    })
+                    .max_by_key(|(_, distance)| *distance);
+
+                if let Some((direction, _)) = shortcut {
+                    return direction;
+                }
+            }
+
+            // the next cell on the cycle is either empty or the tail that is about to move
+            let next = self.cycle[(self.at(head).unwrap() + 1) % cells];
+            let moving_tail = ahead == cells - 1 && game.growing(player) == 0;
+            if occupancy.is_free(next) || moving_tail {
+                if let Some(direction) = step(next) {
+                    return direction;
+                }
+            }
+        } else {
+            // not lined up yet, ride the cycle until the body falls into place
+            let next = self.cycle[(self.at(head).unwrap() + 1) % cells];
+            if occupancy.is_free(next) {
+                if let Some(direction) = step(next) {
+                    if direction != game.direction_of(player).opposite() {
+                        return direction;
+                    }
+                }
+            }
+        }
+
+        self.fallback.next_direction(game, player, keys)
+    }
+}
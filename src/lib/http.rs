@@ -0,0 +1,276 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// just enough http/1.1 to talk json to bot servers and serve it back
+// one request per connection, no keep alive, no tls
+
+const LONGEST_BODY: usize = 32 << 20; // a long game's replay fits easily
+const LONGEST_LINE: u64 = 8 << 10; // a request, status or header line
+const MOST_HEADERS: usize = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5); // to send a whole request in
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>, // names in lower case
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(body: String) -> Self {
+        Response::page("application/json", body)
+    }
+
+    // anything else that is sent back whole, a web page or a script
+    pub fn page(content_type: &'static str, body: String) -> Self {
+        Response {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Response {
+            status: 404,
+            content_type: "application/json",
+            body: String::from("{\"error\":\"not found\"}"),
+        }
+    }
+
+    pub fn bad_request(why: &str) -> Self {
+        Response {
+            status: 400,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": why }).to_string(),
+        }
+    }
+}
+
+// answers every connection on its own thread until the listener dies
+pub fn serve<F>(listener: TcpListener, handler: F)
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let handler = Arc::clone(&handler);
+
+        thread::spawn(move || {
+            let response = match read_request(&mut stream) {
+                Ok(request) => handler(request),
+                Err(e) => Response::bad_request(&e.to_string()),
+            };
+            let _ = write_response(&mut stream, &response);
+        });
+    }
+}
+
+pub fn post(url: &str, body: &str, timeout: Duration) -> io::Result<String> {
+    request("POST", url, body, timeout)
+}
+
+pub fn get(url: &str, timeout: Duration) -> io::Result<String> {
+    request("GET", url, "", timeout)
+}
+
+// sends one request and gives back the body of a 2xx answer
+pub fn request(method: &str, url: &str, body: &str, timeout: Duration) -> io::Result<String> {
+    let (host, path) = split_url(url)?;
+    let address = host
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid(format!("cannot resolve {}", host)))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        host,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let status_line = read_line(&mut reader)?;
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| invalid(format!("bad status line {:?}", status_line.trim())))?;
+
+    let headers = read_headers(&mut reader)?;
+    let body = if header(&headers, "transfer-encoding").is_some_and(|v| v.contains("chunked")) {
+        read_chunked(&mut reader)?
+    } else if let Some(length) = header(&headers, "content-length") {
+        let length = length.parse().map_err(|_| invalid("bad content length"))?;
+        if length > LONGEST_BODY {
+            return Err(invalid("body too long"));
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader
+            .take(LONGEST_BODY as u64 + 1)
+            .read_to_end(&mut body)?;
+        if body.len() > LONGEST_BODY {
+            return Err(invalid("body too long"));
+        }
+        body
+    };
+    let body = String::from_utf8(body).map_err(|_| invalid("body is not utf-8"))?;
+
+    if !(200..300).contains(&status) {
+        return Err(io::Error::other(format!("{} answered {}", url, status)));
+    }
+    Ok(body)
+}
+
+// a client that goes quiet part way through is given up on, a websocket has to take the
+// timeout off again once it is upgraded
+pub fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let request_line = read_line(&mut reader)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+    if method.is_empty() {
+        return Err(invalid("empty request"));
+    }
+
+    let headers = read_headers(&mut reader)?;
+    let length: usize = header(&headers, "content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    if length > LONGEST_BODY {
+        return Err(invalid("body too long"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+pub fn write_response(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+// "http://localhost:8000/snake" -> ("localhost:8000", "/snake")
+fn split_url(url: &str) -> io::Result<(String, String)> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid(format!("only http:// urls are supported, got {}", url)))?;
+    let (host, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    let host = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    Ok((host, path.to_string()))
+}
+
+// one line however it ends, one that goes on past LONGEST_LINE is an error rather than
+// kept however long it gets
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = Vec::new();
+    let read = reader.take(LONGEST_LINE).read_until(b'\n', &mut line)?;
+    if read as u64 == LONGEST_LINE && !line.ends_with(b"\n") {
+        return Err(invalid("line too long"));
+    }
+    String::from_utf8(line).map_err(|_| invalid("line is not utf-8"))
+}
+
+fn read_headers(reader: &mut impl BufRead) -> io::Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.trim().is_empty() {
+            return Ok(headers);
+        }
+        if headers.len() == MOST_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let size = read_line(reader)?;
+        let size = usize::from_str_radix(size.trim().split(';').next().unwrap_or(""), 16)
+            .map_err(|_| invalid("bad chunk size"))?;
+        if size == 0 {
+            return Ok(body);
+        }
+        if body.len() + size > LONGEST_BODY {
+            return Err(invalid("body too long"));
+        }
+        let mut chunk = vec![0; size + 2]; // chunk plus its \r\n
+        reader.read_exact(&mut chunk)?;
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+fn invalid(why: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, why.into())
+}
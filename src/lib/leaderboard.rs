@@ -0,0 +1,133 @@
+use super::http::{self, Request, Response};
+use super::replay::Replay;
+use super::scores::{Checked, HighScore, HighScores};
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// the high score table behind snake-leaderboard, over http so anything can read it
+//
+//   POST /scores               a replay as json, answered with whichever of its scores made it
+//   GET  /scores?mode=..&n=10  the best n, in one mode or all of them
+//   GET  /replays/3            the replay a score came from
+//
+// a submitted replay is played again here before anything goes in, see Replay::verify,
+// so a client can claim what it likes and only a game that really ended that way counts
+
+pub const DEFAULT_URL: &str = "http://127.0.0.1:7879";
+const MOST: usize = 100; // scores in one answer
+const TIMEOUT: Duration = Duration::from_secs(2); // for the game, it waits on these
+
+// answers until the listener dies, every accepted score is saved to `file` straight away
+pub fn serve(listener: TcpListener, file: PathBuf) -> io::Result<()> {
+    let scores = Mutex::new(HighScores::load(&file)?);
+
+    http::serve(listener, move |request: Request| {
+        let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+        let param = |name: &str| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| decode(value))
+        };
+
+        match (request.method.as_str(), path) {
+            ("POST", "/scores") => {
+                let replay: Replay = match serde_json::from_str(&request.body) {
+                    Ok(replay) => replay,
+                    Err(e) => return Response::bad_request(&e.to_string()),
+                };
+                // played again before the table is locked, everyone else can read it meanwhile
+                let replay = match Checked::new(replay) {
+                    Ok(replay) => replay,
+                    Err(reason) => return Response::bad_request(&reason),
+                };
+                let mut scores = scores.lock().unwrap();
+                let made_it = scores.submit(replay);
+                if let Err(e) = scores.save(&file) {
+                    eprintln!("Could not save to {} because: {}", file.display(), e);
+                }
+                for high in &made_it {
+                    println!("{} scored {} in {}", high.name, high.score, high.mode);
+                }
+                Response::json(serde_json::to_string(&made_it).unwrap())
+            }
+            ("GET", "/scores") => {
+                let n = param("n").and_then(|n| n.parse().ok()).unwrap_or(10);
+                let mode = param("mode");
+                let top = scores.lock().unwrap().top(mode.as_deref(), n.min(MOST));
+                Response::json(serde_json::to_string(&top).unwrap())
+            }
+            ("GET", _) => {
+                let id = path
+                    .strip_prefix("/replays/")
+                    .and_then(|id| id.parse().ok());
+                let scores = scores.lock().unwrap();
+                match id.and_then(|id| scores.replay(id)) {
+                    Some(replay) => Response::json(serde_json::to_string(replay).unwrap()),
+                    None => Response::not_found(),
+                }
+            }
+            _ => Response::not_found(),
+        }
+    });
+    Ok(())
+}
+
+// the game's side of it
+
+// whichever of the replay's scores made the table, an error if the leaderboard is not
+// there or did not believe it
+pub fn submit(url: &str, replay: &Replay) -> io::Result<Vec<HighScore>> {
+    let body = serde_json::to_string(replay).map_err(io::Error::other)?;
+    let answer = http::post(&format!("{}/scores", url), &body, TIMEOUT)?;
+    serde_json::from_str(&answer).map_err(io::Error::other)
+}
+
+pub fn top(url: &str, mode: Option<&str>, n: usize) -> io::Result<Vec<HighScore>> {
+    let mode = mode.map_or(String::new(), |mode| format!("&mode={}", encode(mode)));
+    let answer = http::get(&format!("{}/scores?n={}{}", url, n, mode), TIMEOUT)?;
+    serde_json::from_str(&answer).map_err(io::Error::other)
+}
+
+pub fn replay(url: &str, id: u64) -> io::Result<Replay> {
+    let answer = http::get(&format!("{}/replays/{}", url, id), TIMEOUT)?;
+    serde_json::from_str(&answer).map_err(io::Error::other)
+}
+
+// modes have spaces and commas in them, everything but letters and digits goes as %xx
+pub fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => String::from(byte as char),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// %xx and + back, a % without two hex digits after it is left as it is
+pub fn decode(text: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = text.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let hex = after.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (byte, hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &after[2..];
+            }
+            (b'+', _) => {
+                bytes.push(b' ');
+                rest = after;
+            }
+            _ => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,369 @@
+use super::chat::Chat;
+use super::net::{Connection, Message, Room, RoomRules, DELTAS, VERSION};
+use super::netplay::{self, Ending};
+use super::netsim::Conditions;
+use super::scores::HighScore;
+use super::snake::SnakeGame;
+use super::spectate;
+use super::tm_logic::{self, Demo, GameMode};
+use std::collections::HashMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+// the terminal side of snake-server: a list of rooms to pick from or make, then a room
+// to get ready in. when the server starts the room the game is played like any joined one
+// and afterwards it is back to the room for another go. rooms that are full or playing
+// already can be watched instead
+//
+// losing the server mid game isnt the end of it, the server keeps the seat for a while and
+// the session it handed out gets it back. that is tried straight away, and the session is
+// kept in a file too so starting the game again after a crash picks up where it was
+
+const RECONNECT_FOR: Duration = Duration::from_secs(30); // how long the server keeps a seat
+const SHOWN_SCORES: usize = 3; // best on the server, under the rooms
+const PING_EVERY: Duration = Duration::from_secs(2); // servers that answer pings
+
+// the terminal should already be set up, it is cleaned up here
+pub fn play(
+    mut connection: Connection,
+    address: &str,
+    name: &str,
+    network: &Conditions,
+    width: u16,
+    height: u16,
+) {
+    let _ = connection.send(&Message::Hello {
+        version: VERSION,
+        width,
+        height,
+        name: String::from(name),
+    });
+    // a welcome back if the seat is still there, otherwise a refusal and on to the lobby
+    let key = format!("{}@{}", name, address);
+    if let Some(session) = saved(&key) {
+        save(&key, None);
+        let _ = connection.send(&Message::Rejoin { session });
+    }
+    let _ = connection.send(&Message::ListScores { mode: None });
+
+    let mut demo = Demo::new(width, height);
+    let mut rooms: Vec<Room> = Vec::new();
+    let mut scores: Vec<HighScore> = Vec::new();
+    let mut room: Option<Room> = None;
+    let mut ready = false;
+    let mut notice = String::new();
+    let mut server_version = 0; // older servers never say
+    let mut pinged: Option<(u64, Instant)> = None;
+    let mut ping: Option<Duration> = None;
+
+    loop {
+        loop {
+            match connection.inbox.try_recv() {
+                Ok(Message::Rooms { rooms: list }) => {
+                    rooms = list;
+                    room = None;
+                    ready = false;
+                }
+                Ok(Message::Room { room: now }) => {
+                    if room.as_ref().is_some_and(|before| before.id != now.id) {
+                        ready = false;
+                    }
+                    room = Some(now);
+                }
+                Ok(Message::HighScores { scores: best }) => scores = best,
+                Ok(Message::Version { version }) => server_version = version,
+                Ok(Message::Refused { reason }) => notice = reason,
+                Ok(Message::Welcome {
+                    mut you, session, ..
+                }) => {
+                    if let Some(session) = &session {
+                        save(&key, Some(session));
+                    }
+                    let mut chat = Chat::new(name);
+                    let (game, ending) = loop {
+                        let (game, ending) = netplay::follow(&mut connection, you, &mut chat);
+                        let Some(session) = session.as_deref().filter(|_| ending == Ending::Gone)
+                        else {
+                            break (game, ending);
+                        };
+                        match reconnect(address, name, network, width, height, session) {
+                            Some((back, seat)) => (connection, you) = (back, seat),
+                            None => break (game, ending),
+                        }
+                    };
+                    if ending != Ending::Gone {
+                        save(&key, None);
+                    }
+                    match ending {
+                        Ending::Gone => {
+                            tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                            eprintln!(
+                                "the server went away, start again within {}s to get back in the game",
+                                RECONNECT_FOR.as_secs()
+                            );
+                            return;
+                        }
+                        Ending::Quit => {
+                            let _ = connection.send(&Message::LeaveRoom);
+                        }
+                        Ending::Over => {}
+                    }
+                    // the game just played may have made the table
+                    let _ = connection.send(&Message::ListScores { mode: None });
+                    tm_logic::clear_screan();
+                    notice = game.map_or(String::new(), |game| result(&game, you));
+                    ready = false;
+                }
+                Ok(Message::Bye) | Err(TryRecvError::Disconnected) => {
+                    tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                    eprintln!("the server went away");
+                    return;
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        if let (Some((sent, when)), Some((answered, back))) = (pinged, connection.pong()) {
+            if sent == answered {
+                ping = Some(back.saturating_duration_since(when));
+            }
+        }
+        let due = pinged.is_none_or(|(_, when)| when.elapsed() >= PING_EVERY);
+        if server_version >= DELTAS && due {
+            let id = pinged.map_or(0, |(id, _)| id + 1);
+            let _ = connection.send(&Message::Ping { id });
+            pinged = Some((id, Instant::now()));
+        }
+
+        let lines = match &room {
+            Some(room) => room_lines(room, ready),
+            None => lobby_lines(&rooms, &scores, ping),
+        };
+        let mut lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        if !notice.is_empty() {
+            lines.push(&notice);
+        }
+        lines.push("Press q to quit");
+
+        match (demo.poll(&lines), &room) {
+            (Some('q' | 'Q'), _) => break,
+            (Some('r'), Some(_)) => {
+                ready = !ready;
+                let _ = connection.send(&Message::Ready { ready });
+            }
+            (Some('l'), Some(_)) => {
+                notice.clear();
+                let _ = connection.send(&Message::LeaveRoom);
+            }
+            (Some('c'), None) => {
+                let mut rules = GameMode::new();
+                rules.room_screen(&mut demo);
+                if rules.players != 0 {
+                    notice.clear();
+                    let _ = connection.send(&Message::CreateRoom {
+                        name: format!("{}'s room", name),
+                        rules: RoomRules {
+                            players: rules.players,
+                            border: rules.borders,
+                            speed_ms: rules.speed_ms,
+                        },
+                    });
+                }
+            }
+            (Some(c @ '1'..='9'), None) => {
+                let Some(picked) = rooms.get(c as usize - '1' as usize) else {
+                    continue;
+                };
+                notice.clear();
+                // a room with no seat left can still be watched
+                if picked.playing || picked.seats.len() >= picked.rules.players as usize {
+                    let _ = connection.send(&Message::Watch {
+                        room: Some(picked.id),
+                    });
+                    if spectate::watch(&mut connection, width, height, &mut Chat::watching(name))
+                        == Ending::Gone
+                    {
+                        tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                        eprintln!("the server went away");
+                        return;
+                    }
+                    let _ = connection.send(&Message::LeaveRoom);
+                    tm_logic::clear_screan();
+                } else {
+                    let _ = connection.send(&Message::JoinRoom { room: picked.id });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let _ = connection.send(&Message::Bye);
+    tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+}
+
+// keeps trying to get back into the game on a new connection until the server answers
+// either way or it is too late. the connection and which player we are on success
+fn reconnect(
+    address: &str,
+    name: &str,
+    network: &Conditions,
+    width: u16,
+    height: u16,
+    session: &str,
+) -> Option<(Connection, usize)> {
+    let deadline = Instant::now() + RECONNECT_FOR;
+    let mut next_try = Instant::now();
+    let lines = [
+        "Lost the server, trying to get back in",
+        "Press q to give up",
+    ];
+
+    let back = tm_logic::waiting_screen(width, height, &lines, || {
+        if Instant::now() > deadline {
+            return Some(None);
+        }
+        if Instant::now() < next_try {
+            return None;
+        }
+        next_try = Instant::now() + Duration::from_secs(1);
+
+        let target = address.to_socket_addrs().ok()?.next()?;
+        let stream = TcpStream::connect_timeout(&target, Duration::from_secs(1)).ok()?;
+        let connection = Connection::new(stream).and_then(|c| c.simulated(network));
+        let mut connection = connection.ok()?;
+        let _ = connection.send(&Message::Hello {
+            version: VERSION,
+            width,
+            height,
+            name: String::from(name),
+        });
+        let _ = connection.send(&Message::Rejoin {
+            session: String::from(session),
+        });
+        loop {
+            match connection.inbox.recv_timeout(Duration::from_secs(2)) {
+                Ok(Message::Welcome { you, .. }) => return Some(Some((connection, you))),
+                Ok(Message::Refused { .. }) => return Some(None),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    });
+    back.flatten()
+}
+
+// every session we might get back into, by who played it where, several games on one
+// computer each keep their own. a session is as good as the seat, so it is kept where only
+// the player who got it can read it
+fn session_file() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    let state = var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .or_else(|| var("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(state.join("snake").join("sessions.json"))
+}
+
+fn sessions() -> HashMap<String, String> {
+    session_file()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn saved(key: &str) -> Option<String> {
+    sessions().remove(key)
+}
+
+fn save(key: &str, session: Option<&str>) {
+    let mut sessions = sessions();
+    match session {
+        Some(session) => sessions.insert(String::from(key), String::from(session)),
+        None => sessions.remove(key),
+    };
+    let (Some(path), Ok(json)) = (session_file(), serde_json::to_string(&sessions)) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = write_private(&path, json.as_bytes());
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+fn lobby_lines(rooms: &[Room], scores: &[HighScore], ping: Option<Duration>) -> Vec<String> {
+    let mut lines = vec![match ping {
+        Some(ping) => format!("Rooms on the server, {}ms away", ping.as_millis()),
+        None => String::from("Rooms on the server"),
+    }];
+    if rooms.is_empty() {
+        lines.push(String::from("None yet"));
+    }
+    for (i, room) in rooms.iter().take(9).enumerate() {
+        let full = room.playing || room.seats.len() >= room.rules.players as usize;
+        let verb = if full { "watch" } else { "join" };
+        lines.push(format!("Press {} to {} {}", i + 1, verb, room.describe()));
+    }
+    lines.push(String::from("Press c to make a room"));
+    if !scores.is_empty() {
+        lines.push(String::from("High scores"));
+    }
+    for high in scores.iter().take(SHOWN_SCORES) {
+        lines.push(format!("{} {}  {}", high.name, high.score, high.mode));
+    }
+    lines
+}
+
+fn room_lines(room: &Room, ready: bool) -> Vec<String> {
+    let mut lines = vec![room.describe()];
+    for seat in &room.seats {
+        let state = match (seat.away, seat.ready) {
+            (true, _) => "away",
+            (false, true) => "ready",
+            (false, false) => "not ready",
+        };
+        lines.push(format!("{}  {}", seat.name, state));
+    }
+    for _ in room.seats.len()..room.rules.players as usize {
+        lines.push(String::from("waiting for someone..."));
+    }
+    lines.push(String::from(if ready {
+        "Press r if you are not ready after all"
+    } else {
+        "Press r when you are ready"
+    }));
+    lines.push(String::from("Press l to leave the room"));
+    lines
+}
+
+fn result(game: &SnakeGame, you: usize) -> String {
+    if game.players() == 1 {
+        return format!("Game over, you scored {}", game.score(0));
+    }
+    match game.winner {
+        3 => String::from("Game over, it was a draw"),
+        winner if winner as usize == you + 1 => String::from("Game over, you won!"),
+        _ => String::from("Game over, you lost"),
+    }
+}
@@ -0,0 +1,327 @@
+use super::chat::Chat;
+use super::controller::KeyboardController;
+use super::net::{Connection, Lockstep, Message, Snapshot};
+use super::snake::{Direction, Multiplayer, SnakeGame};
+use super::spectate::Broadcast;
+use super::tm_logic;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+// lockstep two player games: both sides run the same seeded SnakeGame and only ever
+// swap moves, so a message is a few bytes however long the snakes get
+// a key pressed on tick t is played on tick t + input_delay on both sides, which gives it
+// that long to get across. the confirmed game only moves on once both moves for a tick
+// are in, so a slow peer slows the game down rather than letting the two drift apart
+// every confirmed tick both send a hash of their game, if those ever differ both dump a report
+//
+// with rollback the game on screen doesnt wait: it runs up to `rollback` ticks past the
+// confirmed one guessing the other snake keeps going the way it last went. when a move
+// turns up that was guessed wrong the screen goes back to the confirmed game and plays
+// the ticks since again with the real move. with rollback 0 this is plain lockstep
+
+const HISTORY: usize = 256; // snapshots kept around for a desync report
+
+#[derive(Serialize)]
+struct DesyncReport {
+    tick: u64,
+    player: usize,
+    seed: u64,
+    input_delay: u64,
+    moves: Vec<[Direction; 2]>, // every tick from the start, enough to replay both games
+    local: Option<Snapshot>,
+    remote: Option<Snapshot>,
+}
+
+pub struct Settings {
+    pub me: usize,
+    pub width: u16,
+    pub height: u16,
+    pub border: bool,
+    pub speed_ms: u64,
+    pub lockstep: Lockstep,
+    pub broadcast: Option<Broadcast>, // watchers get the confirmed game
+    pub name: String,                 // who we are in the chat
+}
+
+// the terminal should already be set up, it is cleaned up here
+pub fn play(mut connection: Connection, settings: Settings) {
+    let Settings {
+        me,
+        width,
+        height,
+        border,
+        speed_ms,
+        lockstep,
+        mut broadcast,
+        name,
+    } = settings;
+    let other = 1 - me;
+    let frame_duration = Duration::from_millis(speed_ms);
+
+    let mut confirmed = SnakeGame::multiplayer_new_seeded(width, height, lockstep.seed);
+    let mut game = confirmed.clone(); // what is on screen, never behind confirmed
+    let keyboard = KeyboardController::arrows();
+    let mut planned = confirmed.direction_of(me);
+    let mut last_theirs = confirmed.direction_of(other);
+
+    // nobody could have pressed anything for the first ticks, so they go straight
+    let mut inputs: [BTreeMap<u64, Direction>; 2] = Default::default();
+    for (player, queue) in inputs.iter_mut().enumerate() {
+        for tick in 1..=lockstep.input_delay {
+            queue.insert(tick, confirmed.direction_of(player));
+        }
+    }
+    let mut scheduled = lockstep.input_delay; // last tick we sent our move for
+    let mut guessed: BTreeMap<u64, Direction> = BTreeMap::new(); // their moves we made up
+
+    let mut moves = Vec::new();
+    let mut snapshots: VecDeque<(u64, Snapshot)> = VecDeque::new();
+    let mut local_hashes: HashMap<u64, u64> = HashMap::new();
+    let mut remote_hashes: HashMap<u64, u64> = HashMap::new();
+    let mut remote_snapshot = None;
+    let mut desync = None;
+    let mut left = false;
+    let mut rollbacks = 0;
+    let mut chat = Chat::new(&name);
+
+    while !confirmed.game_over && desync.is_none() {
+        let Some((keys, said)) = chat.poll() else {
+            break;
+        };
+        let frame_start = Instant::now();
+
+        // chat goes outside the frames, a message arriving late changes nothing in the game
+        for text in said {
+            let said = Message::Chat {
+                from: name.clone(),
+                text,
+            };
+            let _ = connection.send(&said);
+            if let Some(broadcast) = &mut broadcast {
+                broadcast.tell(&said);
+            }
+        }
+        // watchers can talk too, the other watchers already have it so only the peer is told
+        if let Some(broadcast) = &mut broadcast {
+            for said in broadcast.heard() {
+                if let Message::Chat { from, text } = &said {
+                    chat.heard(from, text);
+                }
+                let _ = connection.send(&said);
+            }
+        }
+
+        // turns are worked out from the last move we queued, not the one on screen yet
+        planned = keyboard.turn(planned, &keys);
+        // the screen can jump more than one tick when it catches up, so fill in every one
+        while scheduled < game.ticks() + 1 + lockstep.input_delay {
+            scheduled += 1;
+            inputs[me].insert(scheduled, planned);
+            let _ = connection.send(&Message::Frame {
+                tick: scheduled,
+                direction: planned,
+            });
+        }
+
+        let mut mispredicted = false;
+        loop {
+            match connection.inbox.try_recv() {
+                Ok(Message::Frame { tick, direction }) => {
+                    inputs[other].insert(tick, direction);
+                    mispredicted |= guessed.get(&tick).is_some_and(|guess| *guess != direction);
+                }
+                Ok(Message::Hash { tick, hash }) => {
+                    remote_hashes.insert(tick, hash);
+                }
+                Ok(Message::Desync { tick, snapshot }) => {
+                    remote_snapshot = Some(snapshot);
+                    desync.get_or_insert(tick);
+                }
+                Ok(Message::Chat { from, text }) => {
+                    chat.heard(&from, &text);
+                    if let Some(broadcast) = &mut broadcast {
+                        broadcast.tell(&Message::Chat { from, text });
+                    }
+                }
+                Ok(Message::Bye) | Err(TryRecvError::Disconnected) => {
+                    left = true;
+                    break;
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        // the confirmed game goes as far as both moves are known
+        let before = confirmed.ticks();
+        while !confirmed.game_over {
+            let next = confirmed.ticks() + 1;
+            if !inputs.iter().all(|queue| queue.contains_key(&next)) {
+                break;
+            }
+
+            let both = [0, 1].map(|player| inputs[player].remove(&next).unwrap_or(planned));
+            for (player, direction) in both.iter().enumerate() {
+                confirmed.steer(player, *direction);
+            }
+            confirmed.tick(border);
+            moves.push(both);
+            guessed.remove(&next);
+            last_theirs = both[other];
+
+            let snapshot = Snapshot::of(&confirmed, border);
+            local_hashes.insert(next, snapshot.hash);
+            let _ = connection.send(&Message::Hash {
+                tick: next,
+                hash: snapshot.hash,
+            });
+            snapshots.push_back((next, snapshot));
+            if snapshots.len() > HISTORY {
+                snapshots.pop_front();
+            }
+        }
+
+        // watchers only ever see what is certain
+        if let Some(broadcast) = broadcast.as_mut().filter(|_| confirmed.ticks() > before) {
+            broadcast.send(&confirmed, border);
+        }
+
+        // ticks both sides have a hash for are done with one way or the other
+        let checked: Vec<u64> = remote_hashes
+            .keys()
+            .filter(|tick| local_hashes.contains_key(tick))
+            .copied()
+            .collect();
+        for tick in checked {
+            if remote_hashes.remove(&tick) != local_hashes.remove(&tick) {
+                desync.get_or_insert(tick);
+            }
+        }
+
+        // the moves they sent before leaving have all been played
+        if left && !inputs[other].contains_key(&(confirmed.ticks() + 1)) {
+            confirmed.game_over = true;
+            confirmed.winner = me as u16 + 1;
+            break;
+        }
+
+        // back to the confirmed game and forward again to where the screen was
+        let mut redraw = false;
+        let shown = game.ticks();
+        if mispredicted || confirmed.ticks() >= shown {
+            if mispredicted {
+                rollbacks += 1;
+            }
+            game = confirmed.clone();
+            guessed.clear();
+            while game.ticks() < shown && !game.game_over {
+                let tick = game.ticks() + 1;
+                let guess = guess(&inputs[other], tick, last_theirs);
+                if !inputs[other].contains_key(&tick) {
+                    guessed.insert(tick, guess);
+                }
+                game.steer(me, inputs[me].get(&tick).copied().unwrap_or(planned));
+                game.steer(other, guess);
+                game.tick(border);
+            }
+            redraw = true;
+        }
+
+        // and one new tick on top, as long as it isnt too far past what is confirmed
+        if !game.game_over && game.ticks() < confirmed.ticks() + lockstep.rollback {
+            let tick = game.ticks() + 1;
+            let guess = guess(&inputs[other], tick, last_theirs);
+            if !inputs[other].contains_key(&tick) {
+                guessed.insert(tick, guess);
+            }
+            game.steer(me, inputs[me].get(&tick).copied().unwrap_or(planned));
+            game.steer(other, guess);
+            game.tick(border);
+            redraw = true;
+        }
+
+        if redraw {
+            tm_logic::clear_screan();
+            game.render();
+        }
+        chat.draw();
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_duration {
+            std::thread::sleep(frame_duration - elapsed);
+        }
+    }
+
+    if let Some(broadcast) = &mut broadcast {
+        broadcast.finish(&confirmed, border);
+    }
+
+    let Some(tick) = desync else {
+        let _ = connection.send(&Message::Bye);
+        if confirmed.game_over {
+            confirmed.render();
+            confirmed.cleanup(2);
+        } else {
+            tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+        }
+        if lockstep.rollback > 0 {
+            eprintln!("rolled back {} times", rollbacks);
+        }
+        return;
+    };
+
+    // swap snapshots of the tick it went wrong on, the other side may already have sent its
+    let local = snapshots
+        .iter()
+        .find(|(at, _)| *at == tick)
+        .map(|(_, snapshot)| snapshot.clone());
+    if let Some(snapshot) = &local {
+        let _ = connection.send(&Message::Desync {
+            tick,
+            snapshot: snapshot.clone(),
+        });
+    }
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while remote_snapshot.is_none() {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match connection.inbox.recv_timeout(wait) {
+            Ok(Message::Desync { snapshot, .. }) => remote_snapshot = Some(snapshot),
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let _ = connection.send(&Message::Bye);
+
+    let report = DesyncReport {
+        tick,
+        player: me + 1,
+        seed: lockstep.seed,
+        input_delay: lockstep.input_delay,
+        moves,
+        local,
+        remote: remote_snapshot,
+    };
+    let path = format!("desync-tick{}-player{}.json", tick, me + 1);
+    let written = serde_json::to_string_pretty(&report)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(&path, json));
+
+    tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+    match written {
+        Ok(()) => eprintln!("the games went out of sync on tick {}, see {}", tick, path),
+        Err(e) => eprintln!(
+            "the games went out of sync on tick {} and the report could not be saved: {}",
+            tick, e
+        ),
+    }
+}
+
+// their move for `tick` if it is in, otherwise the last one they made before it
+fn guess(theirs: &BTreeMap<u64, Direction>, tick: u64, last: Direction) -> Direction {
+    theirs
+        .range(..=tick)
+        .next_back()
+        .map_or(last, |(_, direction)| *direction)
+}
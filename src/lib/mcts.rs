@@ -0,0 +1,178 @@
+use super::controller::Controller;
+use super::snake::{Direction, SnakeGame};
+use crossterm::event::KeyCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+// monte carlo tree search over the real engine, both snakes move at once so every node
+// keeps separate statistics per player and each picks its own move by ucb1 (decoupled uct)
+// the tree is open loop: it only stores moves, every iteration replays them on a fresh
+// fork of the game so fruit can land somewhere else each time, just like it might for real
+const EXPLORATION: f32 = 1.4;
+const ROLLOUT_TICKS: u64 = 30;
+
+#[derive(Clone, Copy, Default)]
+struct Stat {
+    visits: u32,
+    value: f32, // summed from the point of view of the player this stat belongs to
+}
+
+#[derive(Default)]
+struct Node {
+    visits: u32,
+    stats: [[Stat; 4]; 2], // per player, per Direction::ALL
+    children: Vec<([usize; 2], usize)>,
+}
+
+pub struct MctsBot {
+    border: bool,
+    budget: Duration, // thinking time per move
+    rng: StdRng,
+}
+
+impl MctsBot {
+    pub fn new(border: bool, budget: Duration) -> Self {
+        MctsBot {
+            border,
+            budget,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // one iteration: walk down the tree, add one node, play it out at random, back it up
+    fn iterate(&mut self, game: &SnakeGame, nodes: &mut Vec<Node>) {
+        let mut game = game.fork(self.rng.gen());
+        let players = game.players();
+        let scores = [game.score(0), game.score(1)];
+        let mut path = Vec::new();
+        let mut node = 0;
+
+        while !game.game_over {
+            let mut joint = [0; 2];
+            for (player, choice) in joint.iter_mut().enumerate().take(players) {
+                *choice = select(&nodes[node], &game, player);
+                game.steer(player, Direction::ALL[*choice]);
+            }
+            game.tick(self.border);
+            path.push((node, joint));
+
+            match nodes[node]
+                .children
+                .iter()
+                .find(|(moves, _)| *moves == joint)
+            {
+                Some((_, child)) => node = *child,
+                None => {
+                    nodes.push(Node::default());
+                    let child = nodes.len() - 1;
+                    nodes[node].children.push((joint, child));
+                    break;
+                }
+            }
+        }
+
+        let value = self.rollout(&mut game, scores);
+        for (node, joint) in path {
+            let node = &mut nodes[node];
+            node.visits += 1;
+            for (player, choice) in joint.iter().enumerate().take(players) {
+                let stat = &mut node.stats[player][*choice];
+                stat.visits += 1;
+                stat.value += if player == 0 { value } else { 1.0 - value };
+            }
+        }
+    }
+
+    // random moves that dont run straight into something, then a guess at who is ahead
+    // gives how good it went for player1 between 0 and 1
+    fn rollout(&mut self, game: &mut SnakeGame, scores: [u16; 2]) -> f32 {
+        let end = game.ticks() + ROLLOUT_TICKS;
+        while !game.game_over && game.ticks() < end {
+            for player in 0..game.players() {
+                let direction = self.safe_move(game, player);
+                game.steer(player, direction);
+            }
+            game.tick(self.border);
+        }
+
+        if game.players() == 1 {
+            return match (game.game_over, game.winner) {
+                (true, 1) => 1.0,
+                (true, _) => 0.0,
+                _ => 0.5 + 0.5 * ((game.score(0) - scores[0]) as f32 / 2.0).tanh(),
+            };
+        }
+
+        match (game.game_over, game.winner) {
+            (true, 1) => 1.0,
+            (true, 2) => 0.0,
+            (true, _) => 0.5,
+            _ => {
+                let lead = game.body(0).len() as f32 - game.body(1).len() as f32;
+                0.5 + 0.5 * (lead / 5.0).tanh()
+            }
+        }
+    }
+
+    fn safe_move(&mut self, game: &SnakeGame, player: usize) -> Direction {
+        let current = game.direction_of(player);
+        let head = game.body(player)[0];
+        let safe: Vec<Direction> = Direction::ALL
+            .into_iter()
+            .filter(|direction| *direction != current.opposite())
+            .filter(|direction| {
+                game.next_point(head, *direction, self.border)
+                    .is_some_and(|next| {
+                        (0..game.players()).all(|p| !game.body(p).iter().any(|s| *s == next))
+                    })
+            })
+            .collect();
+
+        if safe.is_empty() {
+            current
+        } else {
+            safe[self.rng.gen_range(0..safe.len())]
+        }
+    }
+}
+
+// ucb1 over the moves that arent a u-turn, anything not tried yet goes first
+fn select(node: &Node, game: &SnakeGame, player: usize) -> usize {
+    let back = game.direction_of(player).opposite();
+    let log = (node.visits.max(1) as f32).ln();
+
+    (0..4)
+        .filter(|choice| Direction::ALL[*choice] != back)
+        .max_by(|a, b| {
+            let ucb = |choice: usize| {
+                let stat = node.stats[player][choice];
+                if stat.visits == 0 {
+                    return f32::INFINITY;
+                }
+                stat.value / stat.visits as f32 + EXPLORATION * (log / stat.visits as f32).sqrt()
+            };
+            ucb(*a).total_cmp(&ucb(*b))
+        })
+        .unwrap_or(0)
+}
+
+impl Controller for MctsBot {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        let deadline = Instant::now() + self.budget;
+        let mut nodes = vec![Node::default()];
+
+        // always at least a handful, so a tiny budget still looks before it leaps
+        let mut iterations = 0;
+        while iterations < 16 || Instant::now() < deadline {
+            self.iterate(game, &mut nodes);
+            iterations += 1;
+        }
+
+        let back = game.direction_of(player).opposite();
+        (0..4)
+            .filter(|choice| Direction::ALL[*choice] != back)
+            .max_by_key(|choice| nodes[0].stats[player][*choice].visits)
+            .map_or(game.direction_of(player), |choice| Direction::ALL[choice])
+    }
+}
@@ -1,2 +1,35 @@
+pub mod ai;
+pub mod arena;
+pub mod args;
+pub mod battlesnake;
+pub mod board;
+pub mod bots;
+pub mod chat;
+pub mod control;
+pub mod controller;
+pub mod discovery;
+pub mod env;
+pub mod evolve;
+pub mod external;
+pub mod hamilton;
+pub mod http;
+pub mod leaderboard;
+pub mod lobby;
+pub mod lockstep;
+pub mod mcts;
+pub mod net;
+pub mod netplay;
+pub mod netsim;
+pub mod neural;
+pub mod referee;
+pub mod replay;
+pub mod scores;
+pub mod server;
 pub mod snake;
+pub mod spectate;
+pub mod telnet;
 pub mod tm_logic;
+pub mod vec_env;
+pub mod web;
+pub mod websocket;
+pub mod wire;
@@ -0,0 +1,438 @@
+use super::board::Board;
+use super::netsim::Conditions;
+use super::replay::Replay;
+use super::scores::HighScore;
+use super::snake::{Direction, SnakeGame};
+use super::websocket;
+use super::wire::Delta;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+// what host and client say to each other, one json object per line, or per frame for
+// browsers on a websocket. the messages are the same either way and VERSION goes up
+// whenever they change in a way an older client or server would trip over
+//
+//   client -> host   {"type":"hello","version":1,"width":80,"height":24,"name":"ann"}
+//   host -> client   {"type":"welcome","you":1,"width":80,"height":24,"border":false,"speed_ms":50}
+//   client -> host   {"type":"input","direction":"up"}
+//   host -> client   {"type":"state","board":{...}}      every tick, see Board
+//   either way       {"type":"bye"}
+//
+// lockstep games (the welcome carries a seed, an input delay and how far to roll back) send no state at all,
+// both sides run the same seeded game and only swap moves and hashes of where they got to
+//
+//   either way       {"type":"frame","tick":12,"direction":"up"}   my move for tick 12
+//   either way       {"type":"hash","tick":9,"hash":1234}          my game after tick 9
+//   either way       {"type":"desync","tick":9,"snapshot":{...}}   hashes differed, here is mine
+//
+// snake-server speaks the same hello, welcome, input and state once a room starts, before that
+// clients move around its lobby. whenever a room changes everyone hears about it again
+//
+//   client -> server {"type":"list_rooms"}
+//   server -> client {"type":"rooms","rooms":[...]}                  to everyone not in a room
+//   client -> server {"type":"create_room","name":"den","rules":{...}}
+//   client -> server {"type":"join_room","room":3}
+//   server -> client {"type":"room","room":{...}}                    to everyone in that room
+//   client -> server {"type":"ready","ready":true}                   the game starts once all are
+//   client -> server {"type":"leave_room"}
+//   server -> client {"type":"refused","reason":"room 3 is full"}
+//
+// the welcome for a game on the server carries a session, a player who loses the connection
+// has a while to connect again and send it to get their snake back. a bot steers it meanwhile
+//
+//   client -> server {"type":"rejoin","session":"9f3c..."}         answered with a welcome
+//
+// anyone can watch instead of play, a host, a server room or a broadcast single player game
+// they get told what they are watching, then a state every tick however late they came
+//
+//   watcher -> game  {"type":"watch","room":3}                       no room for whatever is on
+//   game -> watcher  {"type":"room","room":{...}}                    who is playing, again on changes
+//   game -> watcher  {"type":"state","board":{...}}
+//
+// players and watchers can talk while a game is on, whatever is in the middle passes it on to
+// everyone else in the game. a server puts in the name it knows rather than trusting `from`
+//
+//   anyone           {"type":"chat","from":"nik","text":"gg"}
+//
+// a server keeps high scores, only ever from games it has played through again itself
+//
+//   client -> server {"type":"list_scores","mode":"1 player, borders, 50ms"}   no mode for all
+//   server -> client {"type":"high_scores","scores":[...]}
+//   client -> server {"type":"submit","replay":{...}}     answered with its mode's scores or refused
+//
+// version 2 added a version back for the hello, pings, events and deltas. a client that says
+// hello with an older version never gets any of them and is sent the whole state every tick.
+// one that speaks a newer version than the server is refused
+//
+//   server -> client {"type":"version","version":2}                 answers the hello
+//   either way       {"type":"ping","id":7}                         answered with a pong, same id
+//   either way       {"type":"pong","id":7}
+//   server -> client {"type":"delta","delta":{...}}                 instead of state, see wire.rs
+//   server -> client {"type":"event","tick":120,"event":{"kind":"away","player":1}}
+//
+// tests/golden has messages as each version wrote them, every one of them still has to be read
+pub const VERSION: u32 = 2;
+pub const DELTAS: u32 = 2; // the version the messages above came in with
+const LONGEST: u64 = 32 << 20; // one line, a replay fits easily
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello {
+        #[serde(default)]
+        version: u32, // VERSION of whoever sent it, 0 from before there were versions
+        width: u16, // the client's terminal, the board is the smaller of the two
+        height: u16,
+        #[serde(default)]
+        name: String,
+    },
+    Welcome {
+        you: usize,
+        width: u16,
+        height: u16,
+        border: bool,
+        speed_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lockstep: Option<Lockstep>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<String>, // snake-server only, for getting back in after a drop
+    },
+    Input {
+        direction: Direction,
+    },
+    State {
+        board: Board,
+    },
+    Frame {
+        tick: u64,
+        direction: Direction,
+    },
+    Hash {
+        tick: u64,
+        hash: u64,
+    },
+    Desync {
+        tick: u64,
+        snapshot: Snapshot,
+    },
+    ListRooms,
+    Rooms {
+        rooms: Vec<Room>,
+    },
+    CreateRoom {
+        name: String,
+        rules: RoomRules,
+    },
+    JoinRoom {
+        room: u32,
+    },
+    LeaveRoom,
+    Ready {
+        ready: bool,
+    },
+    Room {
+        room: Room,
+    },
+    Refused {
+        reason: String,
+    },
+    Watch {
+        #[serde(default)]
+        room: Option<u32>,
+    },
+    Rejoin {
+        session: String,
+    },
+    Chat {
+        #[serde(default)]
+        from: String,
+        text: String,
+    },
+    ListScores {
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    HighScores {
+        scores: Vec<HighScore>,
+    },
+    Submit {
+        replay: Replay,
+    },
+    Version {
+        version: u32,
+    },
+    Ping {
+        id: u64,
+    },
+    Pong {
+        id: u64,
+    },
+    Delta {
+        delta: Delta,
+    },
+    Event {
+        tick: u64,
+        event: GameEvent,
+    },
+    Bye,
+}
+
+// something that happened in a game that the board doesnt show
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GameEvent {
+    Away { player: usize }, // dropped, a bot steers until they are back
+    Back { player: usize },
+}
+
+impl GameEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            GameEvent::Away { player } => {
+                format!("player {} dropped out, a bot has their snake", player + 1)
+            }
+            GameEvent::Back { player } => format!("player {} is back", player + 1),
+        }
+    }
+}
+
+// what a room on snake-server plays
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoomRules {
+    pub players: u8, // 1 or 2, the game starts once there are this many and all are ready
+    pub border: bool,
+    pub speed_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Room {
+    pub id: u32,
+    pub name: String,
+    pub rules: RoomRules,
+    pub seats: Vec<Seat>, // in player order
+    pub playing: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Seat {
+    pub name: String,
+    pub ready: bool,
+    #[serde(default)]
+    pub away: bool, // dropped out of the game and may still come back
+}
+
+impl Room {
+    // one line for a list of rooms
+    pub fn describe(&self) -> String {
+        format!(
+            "{}  {}/{} players  {}  {}ms{}",
+            self.name,
+            self.seats.len(),
+            self.rules.players,
+            if self.rules.border {
+                "borders"
+            } else {
+                "wrapping"
+            },
+            self.rules.speed_ms,
+            if self.playing { "  playing" } else { "" }
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Lockstep {
+    pub seed: u64,
+    pub input_delay: u64, // ticks between pressing a key and the move happening
+    #[serde(default)]
+    pub rollback: u64, // how many ticks the screen may run ahead on guessed moves
+}
+
+// a game in enough detail to see where two of them went different ways
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub hash: u64,
+    pub board: Board,
+    pub growing: Vec<u16>,
+}
+
+impl Snapshot {
+    pub fn of(game: &SnakeGame, border: bool) -> Self {
+        Snapshot {
+            hash: game.state_hash(),
+            board: Board::of(game, border, 0),
+            growing: (0..game.players()).map(|p| game.growing(p)).collect(),
+        }
+    }
+}
+
+// one end of a tcp connection, lines are read on their own thread so the game loop
+// never blocks on the network. inbox disconnects once the other side is gone
+pub struct Connection {
+    stream: TcpStream,
+    websocket: bool, // the same messages, one per websocket frame rather than per line
+    pub inbox: Receiver<Message>,
+    pong: Arc<Mutex<Option<(u64, Instant)>>>, // the last one and when it came in
+    outgoing: Option<Sender<Message>>,        // through a pretend network, see simulated
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (outbox, inbox) = mpsc::channel();
+        let pong = Arc::default();
+
+        let heard = Arc::clone(&pong);
+        thread::spawn(move || {
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                // a line that goes on and on is hung up on rather than kept however long it gets
+                match (&mut reader).take(LONGEST).read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) if read as u64 == LONGEST && !line.ends_with(b"\n") => break,
+                    Ok(_) => {}
+                }
+                // anything we dont understand is skipped rather than ending the game
+                if let Ok(message) = serde_json::from_slice(&line) {
+                    stamp(&heard, &message);
+                    if outbox.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Connection {
+            stream,
+            websocket: false,
+            inbox,
+            pong,
+            outgoing: None,
+        })
+    }
+
+    // a browser, the handshake should already be done
+    pub fn websocket(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (outbox, inbox) = mpsc::channel();
+        let pong = Arc::default();
+
+        let heard = Arc::clone(&pong);
+        thread::spawn(move || {
+            while let Ok(Some(frame)) = websocket::read_message(&mut reader) {
+                if let Ok(message) = serde_json::from_slice(&frame) {
+                    stamp(&heard, &message);
+                    if outbox.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Connection {
+            stream,
+            websocket: true,
+            inbox,
+            pong,
+            outgoing: None,
+        })
+    }
+
+    // the same connection over a worse network, both ways, see netsim. the other end
+    // doesnt need to know
+    pub fn simulated(mut self, conditions: &Conditions) -> io::Result<Self> {
+        if conditions.is_perfect() {
+            return Ok(self);
+        }
+
+        let (outbox, inbox) = mpsc::channel();
+        let arriving = std::mem::replace(&mut self.inbox, inbox);
+        // a pong is only back once it is through, not when the reader first saw it
+        self.pong = Arc::default();
+        let heard = Arc::clone(&self.pong);
+        conditions.carry(arriving, move |message| {
+            stamp(&heard, &message);
+            outbox.send(message).is_ok()
+        });
+
+        let (outgoing, leaving) = mpsc::channel();
+        let (mut stream, websocket) = (self.stream.try_clone()?, self.websocket);
+        conditions.carry(leaving, move |message| {
+            write(&mut stream, websocket, &message).is_ok()
+        });
+        self.outgoing = Some(outgoing);
+        Ok(self)
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        match &self.outgoing {
+            Some(outgoing) => queue(outgoing, message),
+            None => write(&mut self.stream, self.websocket, message),
+        }
+    }
+
+    // the id of the last pong and when it arrived, rather than when the inbox got looked at
+    pub fn pong(&self) -> Option<(u64, Instant)> {
+        *self.pong.lock().unwrap()
+    }
+
+    // somewhere else to send from, the server writes to a client from its room's thread
+    pub fn outbox(&self) -> io::Result<Outbox> {
+        Ok(Outbox {
+            stream: self.stream.try_clone()?,
+            websocket: self.websocket,
+            outgoing: self.outgoing.clone(),
+        })
+    }
+}
+
+// the sending half of a Connection on its own
+pub struct Outbox {
+    stream: TcpStream,
+    websocket: bool,
+    outgoing: Option<Sender<Message>>,
+}
+
+impl Outbox {
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        match &self.outgoing {
+            Some(outgoing) => queue(outgoing, message),
+            None => write(&mut self.stream, self.websocket, message),
+        }
+    }
+
+    // hangs up both ways, the reading side finds its inbox disconnected
+    pub fn close(&self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+// onto a pretend network, which is gone once the connection is
+fn queue(outgoing: &Sender<Message>, message: &Message) -> io::Result<()> {
+    outgoing
+        .send(message.clone())
+        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+}
+
+fn stamp(pong: &Mutex<Option<(u64, Instant)>>, message: &Message) {
+    if let Message::Pong { id } = message {
+        *pong.lock().unwrap() = Some((*id, Instant::now()));
+    }
+}
+
+fn write(stream: &mut TcpStream, websocket: bool, message: &Message) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    if websocket {
+        return websocket::write_text(stream, &line);
+    }
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
@@ -0,0 +1,352 @@
+use super::board::Board;
+use super::chat::Chat;
+use super::controller::{Controller, KeyboardController, RemoteController};
+use super::discovery::{Announcement, Announcer};
+use super::lockstep::{self, Settings};
+use super::net::{Connection, Lockstep, Message, RoomRules, VERSION};
+use super::netsim::Conditions;
+use super::snake::{Multiplayer, SnakeGame};
+use super::spectate::Broadcast;
+use super::tm_logic::{self, GameMode};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+// two player games over tcp. the host runs the only real SnakeGame and plays player1,
+// the client plays player2: it sends its direction and draws whatever state comes back
+
+pub const DEFAULT_PORT: u16 = 7777; // hosting from the welcome screen
+
+pub struct Options {
+    pub name: String,   // what the game is called on the network
+    pub lockstep: bool, // host only, the joining side does whatever the host says
+    pub input_delay: u64,
+    pub rollback: u64,
+    pub network: Conditions, // pretend the network is this bad, see netsim
+}
+
+// `listener` should already be bound so a taken port is reported before the terminal is grabbed
+// the terminal should already be set up with the rules picked, it is cleaned up here
+pub fn host(
+    listener: TcpListener,
+    options: &Options,
+    game_mode: &GameMode,
+    width: u16,
+    height: u16,
+) {
+    let port = listener.local_addr().map_or(0, |address| address.port());
+
+    // only worth announcing until someone turns up, a lan without broadcast still has --join
+    let mode = match (options.lockstep, options.rollback) {
+        (false, _) => "hosted",
+        (true, 0) => "lockstep",
+        (true, _) => "rollback",
+    };
+    let announcer = Announcer::start(Announcement {
+        name: options.name.clone(),
+        port,
+        players: 1,
+        max_players: 2,
+        width,
+        height,
+        border: game_mode.borders,
+        speed_ms: game_mode.speed_ms,
+        mode: String::from(mode),
+    });
+
+    // anyone connecting to watch is taken in from here on
+    let rules = RoomRules {
+        players: 2,
+        border: game_mode.borders,
+        speed_ms: game_mode.speed_ms,
+    };
+    let mut broadcast = Broadcast::new(
+        listener,
+        format!("{}'s game", options.name),
+        rules,
+        &[&options.name],
+    );
+
+    let waiting = format!("Waiting for player 2 on port {}", port);
+    let player = tm_logic::waiting_screen(width, height, &[&waiting, "Press q to give up"], || {
+        broadcast.accept()
+    });
+    drop(announcer);
+    let Some(player) = player else {
+        tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+        return;
+    };
+    broadcast.seat(if player.name.is_empty() {
+        "player 2"
+    } else {
+        &player.name
+    });
+    let Ok(mut connection) = player.connection.simulated(&options.network) else {
+        tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+        eprintln!("Could not use the connection to player 2");
+        return;
+    };
+
+    // the board has to fit on both screens
+    let (width, height) = (width.min(player.width), height.min(player.height));
+    let lockstep = options.lockstep.then(|| Lockstep {
+        seed: rand::random(),
+        input_delay: options.input_delay,
+        rollback: options.rollback,
+    });
+    let _ = connection.send(&Message::Welcome {
+        you: 1,
+        width,
+        height,
+        border: game_mode.borders,
+        speed_ms: game_mode.speed_ms,
+        lockstep,
+        session: None,
+    });
+
+    if let Some(lockstep) = lockstep {
+        let settings = Settings {
+            me: 0,
+            width,
+            height,
+            border: game_mode.borders,
+            speed_ms: game_mode.speed_ms,
+            lockstep,
+            broadcast: Some(broadcast),
+            name: options.name.clone(),
+        };
+        lockstep::play(connection, settings);
+        return;
+    }
+
+    let (directions, inbox) = mpsc::channel();
+    let mut controllers: [Box<dyn Controller>; 2] = [
+        Box::new(KeyboardController::arrows()),
+        Box::new(RemoteController::new(inbox)),
+    ];
+    let mut game = SnakeGame::multiplayer_new(width, height);
+    let frame_duration = Duration::from_millis(game_mode.speed_ms);
+
+    let mut chat = Chat::new(&options.name);
+
+    while !game.game_over {
+        let Some((keys, said)) = chat.poll() else {
+            break;
+        };
+        let frame_start = Instant::now();
+        for text in said {
+            let said = Message::Chat {
+                from: chat.name.clone(),
+                text,
+            };
+            let _ = connection.send(&said);
+            broadcast.tell(&said);
+        }
+        // watchers can talk too, they are passed on to the client
+        for said in broadcast.heard() {
+            if let Message::Chat { from, text } = &said {
+                chat.heard(from, text);
+            }
+            let _ = connection.send(&said);
+        }
+
+        // hand over whatever the client sent since the last tick, a bye or a
+        // dropped connection leaves the game to the host
+        let mut left = false;
+        loop {
+            match connection.inbox.try_recv() {
+                Ok(Message::Input { direction }) => {
+                    let _ = directions.send(direction);
+                }
+                Ok(Message::Chat { from, text }) => {
+                    chat.heard(&from, &text);
+                    broadcast.tell(&Message::Chat { from, text });
+                }
+                Ok(Message::Bye) | Err(TryRecvError::Disconnected) => {
+                    left = true;
+                    break;
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        if left {
+            game.game_over = true;
+            game.winner = 1;
+            break;
+        }
+
+        for (player, controller) in controllers.iter_mut().enumerate() {
+            let direction = controller.next_direction(&game, player, &keys);
+            game.steer(player, direction);
+        }
+
+        tm_logic::clear_screan();
+        game.tick(game_mode.borders);
+        game.render();
+        chat.draw();
+        let _ = connection.send(&Message::State {
+            board: Board::of(&game, game_mode.borders, 1),
+        });
+        broadcast.send(&game, game_mode.borders);
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_duration {
+            std::thread::sleep(frame_duration - elapsed);
+        }
+    }
+
+    let _ = connection.send(&Message::State {
+        board: Board::of(&game, game_mode.borders, 1),
+    });
+    let _ = connection.send(&Message::Bye);
+    broadcast.finish(&game, game_mode.borders);
+    game.cleanup(2);
+}
+
+// `stream` should already be connected, for the same reason as the host's listener
+// the terminal should already be set up, it is cleaned up here
+pub fn join(stream: TcpStream, options: &Options, width: u16, height: u16) {
+    let connection = Connection::new(stream).and_then(|c| c.simulated(&options.network));
+    let Ok(mut connection) = connection else {
+        tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+        eprintln!("Could not use the connection to the host");
+        return;
+    };
+    let _ = connection.send(&Message::Hello {
+        version: VERSION,
+        width,
+        height,
+        name: options.name.clone(),
+    });
+
+    let you = loop {
+        match connection.inbox.recv_timeout(Duration::from_secs(60)) {
+            Ok(Message::Welcome {
+                you,
+                width,
+                height,
+                border,
+                speed_ms,
+                lockstep: Some(lockstep),
+                ..
+            }) => {
+                let settings = Settings {
+                    me: you,
+                    width,
+                    height,
+                    border,
+                    speed_ms,
+                    lockstep,
+                    broadcast: None,
+                    name: options.name.clone(),
+                };
+                lockstep::play(connection, settings);
+                return;
+            }
+            Ok(Message::Welcome { you, .. }) => break you,
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {
+                tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                eprintln!("the host never started the game");
+                return;
+            }
+        }
+    };
+
+    let (game, ending) = follow(&mut connection, you, &mut Chat::new(&options.name));
+    if ending == Ending::Quit {
+        let _ = connection.send(&Message::Bye);
+    }
+    match game {
+        Some(game) if game.game_over => game.cleanup(2),
+        _ => tm_logic::cleanup_terminal(0, 0, width, height, 0, 0),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ending {
+    Over, // the game finished
+    Quit, // the player pressed q
+    Gone, // the other end went away
+}
+
+// the client side of a game someone else runs: sends turns, draws the states that come
+// back. hands back the last game drawn, if there was one, and how it ended
+pub fn follow(
+    connection: &mut Connection,
+    you: usize,
+    chat: &mut Chat,
+) -> (Option<SnakeGame>, Ending) {
+    let mut keyboard = KeyboardController::arrows();
+    let mut game: Option<SnakeGame> = None;
+    let mut board: Option<Board> = None; // what deltas build on
+
+    loop {
+        let Some((keys, said)) = chat.poll() else {
+            return (game, Ending::Quit);
+        };
+        for text in said {
+            let _ = connection.send(&Message::Chat {
+                from: chat.name.clone(),
+                text,
+            });
+        }
+
+        // only turns are worth sending, going straight is what the host does anyway
+        if let Some(game) = &game {
+            let direction = keyboard.next_direction(game, you, &keys);
+            if direction != game.direction_of(you) {
+                let _ = connection.send(&Message::Input { direction });
+            }
+        }
+
+        // only the newest state is worth drawing, anything after the last one is left
+        // for whoever called, snake-server carries on with the lobby
+        let mut latest = None;
+        let mut gone = false;
+        loop {
+            let next = match connection.inbox.try_recv() {
+                Ok(Message::State { board }) => Some(board),
+                Ok(Message::Delta { delta }) => delta.apply(latest.as_ref().or(board.as_ref())),
+                Ok(Message::Chat { from, text }) => {
+                    chat.heard(&from, &text);
+                    None
+                }
+                Ok(Message::Event { event, .. }) => {
+                    chat.heard("server", &event.describe());
+                    None
+                }
+                Ok(Message::Bye) | Err(TryRecvError::Disconnected) => {
+                    gone = true;
+                    break;
+                }
+                Ok(_) => None,
+                Err(TryRecvError::Empty) => break,
+            };
+            if let Some(next) = next {
+                let over = next.game_over;
+                latest = Some(next);
+                if over {
+                    break;
+                }
+            }
+        }
+
+        if let Some(mut next) = latest.as_ref().and_then(SnakeGame::from_board) {
+            tm_logic::clear_screan();
+            next.render();
+            game = Some(next);
+        }
+        if latest.is_some() {
+            board = latest;
+        }
+        chat.draw();
+        if game.as_ref().is_some_and(|game| game.game_over) {
+            return (game, Ending::Over);
+        }
+        if gone {
+            return (game, Ending::Gone);
+        }
+    }
+}
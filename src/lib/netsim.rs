@@ -0,0 +1,179 @@
+use super::net::Message;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// a pretend network, to try online play over a bad connection on one machine. messages are
+// held back by `latency` plus up to `jitter`, wait their turn for `bandwidth`, go missing with
+// `loss` and turn up after ones sent later with `reorder`
+//
+//   --netsim latency=120ms,jitter=40ms,loss=2%,reorder=1%,bandwidth=8kB,seed=3
+//
+// it sits on top of tcp, which never loses or reorders anything itself. so those two are
+// worse than any real connection would be: lost messages are gone for good rather than late
+
+const HELD: Duration = Duration::from_millis(50); // on top of the jitter for a reordered message
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Conditions {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub loss: f64,              // of messages, 0 to 1
+    pub reorder: f64,           // of messages, held back for later ones to overtake
+    pub bandwidth: Option<u64>, // bytes a second, messages queue up behind each other
+    pub seed: Option<u64>,      // the same seed loses and reorders the same messages
+}
+
+impl Conditions {
+    // comma separated name=value, anything left out is as good as it gets
+    pub fn parse(settings: &str) -> Result<Self, String> {
+        let mut conditions = Conditions::default();
+        for setting in settings.split(',').filter(|s| !s.trim().is_empty()) {
+            let Some((name, value)) = setting.split_once('=') else {
+                return Err(format!("{} should be name=value", setting));
+            };
+            let value = value.trim();
+            match name.trim() {
+                "latency" => conditions.latency = duration(value)?,
+                "jitter" => conditions.jitter = duration(value)?,
+                "loss" => conditions.loss = fraction(value)?,
+                "reorder" => conditions.reorder = fraction(value)?,
+                "bandwidth" => conditions.bandwidth = Some(rate(value)?),
+                "seed" => {
+                    let seed = value.parse().map_err(|_| format!("{} is no seed", value))?;
+                    conditions.seed = Some(seed);
+                }
+                other => {
+                    return Err(format!(
+                        "there is no {}, only latency, jitter, loss, reorder, bandwidth and seed",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(conditions)
+    }
+
+    // a perfect network needs nothing in the way
+    pub fn is_perfect(&self) -> bool {
+        self.latency.is_zero()
+            && self.jitter.is_zero()
+            && self.loss == 0.0
+            && self.reorder == 0.0
+            && self.bandwidth.is_none()
+    }
+
+    // hands everything from `arriving` to `deliver` as this network would have, on a thread
+    // of its own. stops once `deliver` says false, or `arriving` is done and all of it is through
+    pub fn carry(
+        &self,
+        arriving: Receiver<Message>,
+        mut deliver: impl FnMut(Message) -> bool + Send + 'static,
+    ) {
+        let conditions = *self;
+        thread::spawn(move || {
+            let mut rng = StdRng::seed_from_u64(conditions.seed.unwrap_or_else(rand::random));
+            let mut queue: Vec<(Instant, Message)> = Vec::new();
+            let mut free = Instant::now(); // when the last message is through the bandwidth
+            let mut last = Instant::now(); // when the last one not reordered is due
+            let mut open = true;
+
+            loop {
+                let now = Instant::now();
+                while let Some(i) = earliest(&queue).filter(|i| queue[*i].0 <= now) {
+                    if !deliver(queue.remove(i).1) {
+                        return;
+                    }
+                }
+                let wait = earliest(&queue).map(|i| queue[i].0.saturating_duration_since(now));
+
+                let message = match (open, wait) {
+                    (true, Some(wait)) => arriving.recv_timeout(wait),
+                    (true, None) => arriving.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    (false, Some(wait)) => {
+                        thread::sleep(wait);
+                        continue;
+                    }
+                    (false, None) => return,
+                };
+                let message = match message {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        open = false;
+                        continue;
+                    }
+                };
+
+                if rng.gen_bool(conditions.loss) {
+                    continue;
+                }
+                let now = Instant::now();
+                free = free.max(now);
+                if let Some(bandwidth) = conditions.bandwidth {
+                    let size = serde_json::to_string(&message).map_or(0, |json| json.len() + 1);
+                    free += Duration::from_secs_f64(size as f64 / bandwidth as f64);
+                }
+                let mut due = free + conditions.latency + conditions.jitter.mul_f64(rng.gen());
+                if rng.gen_bool(conditions.reorder) {
+                    due += conditions.jitter + HELD;
+                } else {
+                    // jitter alone doesnt reorder anything, tcp would still have it in order
+                    due = due.max(last);
+                    last = due;
+                }
+                queue.push((due, message));
+            }
+        });
+    }
+}
+
+// the first of those due soonest, so ones due together keep their order
+fn earliest(queue: &[(Instant, Message)]) -> Option<usize> {
+    (0..queue.len()).min_by_key(|i| queue[*i].0)
+}
+
+// 120ms, 1.5s, or just a number of milliseconds
+fn duration(value: &str) -> Result<Duration, String> {
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = value.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        (value, 0.001)
+    };
+    let seconds = number.trim().parse::<f64>().map(|number| number * scale);
+    seconds
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or(format!("{} is not a time like 120ms", value))
+}
+
+// 2% or 0.02
+fn fraction(value: &str) -> Result<f64, String> {
+    let parsed = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => value.parse::<f64>(),
+    };
+    match parsed {
+        Ok(fraction) if (0.0..=1.0).contains(&fraction) => Ok(fraction),
+        _ => Err(format!("{} is not a chance like 2%", value)),
+    }
+}
+
+// bytes a second, 8kB, 1MB or just a number
+fn rate(value: &str) -> Result<u64, String> {
+    let (number, scale) = if let Some(kb) = value.strip_suffix("kB") {
+        (kb, 1_000.0)
+    } else if let Some(mb) = value.strip_suffix("MB") {
+        (mb, 1_000_000.0)
+    } else {
+        (value.strip_suffix('B').unwrap_or(value), 1.0)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(number) if number * scale >= 1.0 && number.is_finite() => Ok((number * scale) as u64),
+        _ => Err(format!("{} is not a bandwidth like 8kB", value)),
+    }
+}
@@ -0,0 +1,126 @@
+use super::controller::Controller;
+use super::env::features_of;
+use super::snake::{Direction, SnakeGame};
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+// a small fully connected network that looks at the same 15 features as env::features
+// and scores the four directions, tanh on the hidden layers and argmax on the output
+//
+// a genome is just the layer sizes and every weight in one flat list,
+// per layer the weights row by row (one row per neuron) followed by the biases
+pub const INPUTS: usize = 15;
+pub const OUTPUTS: usize = 4;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Genome {
+    pub layers: Vec<usize>, // INPUTS, the hidden sizes, OUTPUTS
+    pub weights: Vec<f32>,
+    #[serde(default)]
+    pub fitness: f32,
+    #[serde(default)]
+    pub generation: u32,
+}
+
+impl Genome {
+    pub fn weight_count(layers: &[usize]) -> usize {
+        layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+    }
+
+    // weights uniform in -1..1
+    pub fn random(hidden: &[usize], rng: &mut impl rand::Rng) -> Self {
+        let mut layers = vec![INPUTS];
+        layers.extend_from_slice(hidden);
+        layers.push(OUTPUTS);
+        let weights = (0..Self::weight_count(&layers))
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+
+        Genome {
+            layers,
+            weights,
+            fitness: 0.0,
+            generation: 0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let genome: Genome = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+        // an empty layer would have nothing to pass on, forward cant run through one
+        let shape_ok = genome.layers.len() >= 2
+            && !genome.layers.contains(&0)
+            && genome.layers[0] == INPUTS
+            && genome.layers[genome.layers.len() - 1] == OUTPUTS;
+        if !shape_ok || genome.weights.len() != Self::weight_count(&genome.layers) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "genome does not fit a network of its layer sizes",
+            ));
+        }
+        Ok(genome)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    // one value per Direction::ALL
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut values = inputs.to_vec();
+        let mut at = 0;
+
+        for (layer, pair) in self.layers.windows(2).enumerate() {
+            let (from, to) = (pair[0], pair[1]);
+            let last = layer == self.layers.len() - 2;
+            let weights = &self.weights[at..at + from * to];
+            let biases = &self.weights[at + from * to..at + (from + 1) * to];
+            at += (from + 1) * to;
+
+            values = weights
+                .chunks(from)
+                .zip(biases)
+                .map(|(row, bias)| {
+                    let sum = row.iter().zip(&values).map(|(w, v)| w * v).sum::<f32>() + bias;
+                    if last {
+                        sum
+                    } else {
+                        sum.tanh()
+                    }
+                })
+                .collect();
+        }
+
+        values
+    }
+}
+
+// plays whatever a genome says, a u-turn is never picked since the game would ignore it
+pub struct NeuralBot {
+    genome: Genome,
+    border: bool,
+}
+
+impl NeuralBot {
+    pub fn new(genome: Genome, border: bool) -> Self {
+        NeuralBot { genome, border }
+    }
+}
+
+impl Controller for NeuralBot {
+    fn next_direction(&mut self, game: &SnakeGame, player: usize, _keys: &[KeyCode]) -> Direction {
+        let current = game.direction_of(player);
+        let scores = self
+            .genome
+            .forward(&features_of(game, self.border, player).data);
+
+        Direction::ALL
+            .into_iter()
+            .zip(scores)
+            .filter(|(direction, _)| *direction != current.opposite())
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(current, |(direction, _)| direction)
+    }
+}
@@ -0,0 +1,90 @@
+use super::snake::{Direction, SnakeGame};
+use std::time::{Duration, Instant};
+
+// what a server lets through from the players in a game it runs. the game already ignores a
+// turn straight back, but two turns in one tick get round that (up then left while going
+// right) and nobody presses keys hundreds of times a second
+
+const MOST_PER_SECOND: u32 = 30; // moves from one player, a few times what fingers manage
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Foul {
+    Reversal,   // straight back into its own neck
+    SecondTurn, // already turned this tick
+    Flooding,   // too many moves too quickly
+}
+
+impl Foul {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Foul::Reversal => "turned back on itself",
+            Foul::SecondTurn => "turned twice in one tick",
+            Foul::Flooding => "sent too many moves",
+        }
+    }
+}
+
+pub struct Referee {
+    turned: Vec<bool>,         // this tick
+    sent: Vec<(Instant, u32)>, // moves since the start of the current second
+    pub fouls: Vec<Vec<Foul>>, // every move thrown out, per player
+}
+
+impl Referee {
+    pub fn new(players: usize) -> Self {
+        Referee {
+            turned: vec![false; players],
+            sent: vec![(Instant::now(), 0); players],
+            fouls: vec![Vec::new(); players],
+        }
+    }
+
+    // whether the move can be played, the game should be steered straight away if it can so
+    // the next move from the same player is judged against it
+    pub fn judge(
+        &mut self,
+        game: &SnakeGame,
+        player: usize,
+        direction: Direction,
+    ) -> Result<(), Foul> {
+        let verdict = self.verdict(game, player, direction);
+        if let Err(foul) = verdict {
+            self.fouls[player].push(foul);
+        }
+        verdict
+    }
+
+    fn verdict(
+        &mut self,
+        game: &SnakeGame,
+        player: usize,
+        direction: Direction,
+    ) -> Result<(), Foul> {
+        let (since, sent) = &mut self.sent[player];
+        if since.elapsed() >= Duration::from_secs(1) {
+            (*since, *sent) = (Instant::now(), 0);
+        }
+        *sent += 1;
+        if *sent > MOST_PER_SECOND {
+            return Err(Foul::Flooding);
+        }
+
+        let going = game.direction_of(player);
+        if direction == going {
+            return Ok(()); // not a turn at all
+        }
+        if direction == going.opposite() {
+            return Err(Foul::Reversal);
+        }
+        if self.turned[player] {
+            return Err(Foul::SecondTurn);
+        }
+        self.turned[player] = true;
+        Ok(())
+    }
+
+    // the game moved on, everyone can turn again
+    pub fn tick(&mut self) {
+        self.turned.fill(false);
+    }
+}
@@ -0,0 +1,151 @@
+use super::snake::{Direction, Multiplayer, SnakeGame};
+use super::tm_logic::{self, read_keys};
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// a whole game as its seed and every move, enough for anyone to play it again and end up
+// with the same scores. high scores come with one so they can be checked rather than believed
+
+pub const SIDES: std::ops::RangeInclusive<u16> = 10..=1000; // boards the game can be played on
+const LONGEST: usize = 1_000_000; // ticks, anything longer is not worth checking
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub border: bool,
+    pub speed_ms: u64, // changes nothing when played back, only which table it goes in
+    pub names: Vec<String>, // one per snake
+    pub moves: Vec<Vec<Direction>>, // every tick, one per snake
+    pub scores: Vec<u16>, // what the game ended on, only claimed until verify says so
+}
+
+impl Replay {
+    // for a game that has not been ticked yet
+    pub fn start(game: &SnakeGame, border: bool, speed_ms: u64, names: &[&str]) -> Self {
+        Replay {
+            seed: game.seed(),
+            width: game.width(),
+            height: game.height(),
+            border,
+            speed_ms,
+            names: names.iter().map(|name| String::from(*name)).collect(),
+            moves: Vec::new(),
+            scores: Vec::new(),
+        }
+    }
+
+    // everyone's direction once they have been steered, just before the tick
+    pub fn record(&mut self, game: &SnakeGame) {
+        let moves = (0..game.players()).map(|player| game.direction_of(player));
+        self.moves.push(moves.collect());
+    }
+
+    pub fn finish(&mut self, game: &SnakeGame) {
+        self.scores = (0..game.players())
+            .map(|player| game.score(player))
+            .collect();
+    }
+
+    // which table its scores go in, games are only compared with ones played the same way
+    pub fn mode(&self) -> String {
+        format!(
+            "{} player{}, {}, {}ms",
+            self.names.len(),
+            if self.names.len() == 1 { "" } else { "s" },
+            if self.border { "borders" } else { "no borders" },
+            self.speed_ms
+        )
+    }
+
+    // plays it again from the seed. the moves have to be ones a player could have made,
+    // the game has to end on the last of them and the scores have to be the ones claimed
+    pub fn verify(&self) -> Result<SnakeGame, String> {
+        if !SIDES.contains(&self.width) || !SIDES.contains(&self.height) {
+            return Err(format!(
+                "a {}x{} board is not playable",
+                self.width, self.height
+            ));
+        }
+        if self.moves.len() > LONGEST {
+            return Err(String::from("the game is too long to check"));
+        }
+        let mut game = match self.names.len() {
+            1 => SnakeGame::new_seeded(self.width, self.height, self.seed),
+            2 => SnakeGame::multiplayer_new_seeded(self.width, self.height, self.seed),
+            _ => return Err(String::from("games are for one or two players")),
+        };
+
+        for (tick, moves) in self.moves.iter().enumerate() {
+            if game.game_over {
+                return Err(format!(
+                    "there are moves after the game ended on tick {}",
+                    tick
+                ));
+            }
+            if moves.len() != game.players() {
+                return Err(format!(
+                    "tick {} has moves for the wrong number of snakes",
+                    tick + 1
+                ));
+            }
+            for (player, direction) in moves.iter().enumerate() {
+                if *direction == game.direction_of(player).opposite() {
+                    return Err(format!(
+                        "snake {} turned back on tick {}",
+                        player + 1,
+                        tick + 1
+                    ));
+                }
+                game.steer(player, *direction);
+            }
+            game.tick(self.border);
+        }
+
+        if !game.game_over {
+            return Err(String::from("the game never ended"));
+        }
+        let scores: Vec<u16> = (0..game.players())
+            .map(|player| game.score(player))
+            .collect();
+        if scores != self.scores {
+            return Err(format!(
+                "the game ends on {:?}, not the {:?} claimed",
+                scores, self.scores
+            ));
+        }
+        Ok(game)
+    }
+
+    // plays it on screen at the speed it was played, q stops it early. the terminal
+    // should already be set up and at least as big as the board
+    pub fn show(&self) {
+        let mut game = match self.names.len() {
+            2 => SnakeGame::multiplayer_new_seeded(self.width, self.height, self.seed),
+            _ => SnakeGame::new_seeded(self.width, self.height, self.seed),
+        };
+        let frame_duration = Duration::from_millis(self.speed_ms);
+
+        for moves in &self.moves {
+            if read_keys().contains(&KeyCode::Char('q')) {
+                return;
+            }
+            let frame_start = Instant::now();
+
+            for (player, direction) in moves.iter().enumerate() {
+                game.steer(player, *direction);
+            }
+            tm_logic::clear_screan();
+            game.tick(self.border);
+            game.render();
+
+            let elapsed = frame_start.elapsed();
+            if elapsed < frame_duration {
+                std::thread::sleep(frame_duration - elapsed);
+            }
+        }
+        std::thread::sleep(Duration::from_secs(1)); // a moment to see how it ended
+    }
+}
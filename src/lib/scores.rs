@@ -0,0 +1,109 @@
+use super::replay::Replay;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+// high scores that were earned: every one comes with the replay it was scored in, and only
+// goes in once that replay has been played again and ended on the same score
+
+const KEPT: usize = 10; // per mode
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub id: u64, // of the replay it came from
+    pub name: String,
+    pub score: u16,
+    pub mode: String, // see Replay::mode
+}
+
+// a replay that has been played again and ended on the scores it claims, the only kind the
+// table takes. checking one can take a while, so it is done before the table is locked
+pub struct Checked(Replay);
+
+impl Checked {
+    pub fn new(replay: Replay) -> Result<Self, String> {
+        replay.verify()?;
+        Ok(Checked(replay))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    next_id: u64,
+    scores: Vec<HighScore>, // best first
+    replays: Vec<(u64, Replay)>,
+}
+
+impl HighScores {
+    // a file that isnt there yet is an empty table
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_string(self).map_err(io::Error::other)?)
+    }
+
+    // puts in a score for every snake in the replay that made the table, handing those
+    // back. nothing that scored nothing goes in
+    pub fn submit(&mut self, replay: Checked) -> Vec<HighScore> {
+        let Checked(replay) = replay;
+        let id = self.next_id;
+        self.next_id += 1;
+        let mode = replay.mode();
+        for (name, score) in replay.names.iter().zip(&replay.scores) {
+            if *score > 0 {
+                self.scores.push(HighScore {
+                    id,
+                    name: name.clone(),
+                    score: *score,
+                    mode: mode.clone(),
+                });
+            }
+        }
+
+        // best first, the older of two equal scores stays ahead
+        self.scores
+            .sort_by_key(|high| (std::cmp::Reverse(high.score), high.id));
+        let mut counted = 0;
+        self.scores.retain(|high| {
+            counted += usize::from(high.mode == mode);
+            high.mode != mode || counted <= KEPT
+        });
+
+        let made_it: Vec<HighScore> = self
+            .scores
+            .iter()
+            .filter(|high| high.id == id)
+            .cloned()
+            .collect();
+        if !made_it.is_empty() {
+            self.replays.push((id, replay));
+        }
+        let scores = &self.scores;
+        self.replays
+            .retain(|(id, _)| scores.iter().any(|high| high.id == *id));
+        made_it
+    }
+
+    // the best n, in one mode or all of them
+    pub fn top(&self, mode: Option<&str>, n: usize) -> Vec<HighScore> {
+        self.scores
+            .iter()
+            .filter(|high| mode.is_none_or(|mode| high.mode == mode))
+            .take(n)
+            .cloned()
+            .collect()
+    }
+
+    pub fn replay(&self, id: u64) -> Option<&Replay> {
+        self.replays
+            .iter()
+            .find(|(kept, _)| *kept == id)
+            .map(|(_, replay)| replay)
+    }
+}
@@ -0,0 +1,824 @@
+use super::ai::GreedyBot;
+use super::board::Board;
+use super::chat;
+use super::controller::Controller;
+use super::net::{Connection, GameEvent, Message, Outbox, Room, RoomRules, Seat, DELTAS, VERSION};
+use super::referee::Referee;
+use super::replay::{Replay, SIDES};
+use super::scores::{Checked, HighScore, HighScores};
+use super::snake::{Direction, Multiplayer, SnakeGame};
+use super::web;
+use super::wire::Updates;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// the dedicated server behind snake-server: a lobby of rooms, each room plays its own
+// SnakeGame on its own thread at its own speed. every client gets a thread reading what
+// it sends and one writing to it, everything they share lives in one Lobby behind a mutex.
+// messages for a client queue up in the order they were sent with that held, so lines from
+// different threads never get mixed up and a client that stops reading only holds up itself
+//
+// a player whose connection drops mid game keeps their seat for a while, GRACE unless told
+// otherwise. a greedy bot steers their snake meanwhile (or the game waits, if nobody else is
+// playing) and the session from their welcome gets them back in. leaving on purpose gives
+// the game up straight away
+//
+// the server's game is the only one that counts. moves go past a Referee first and the scores
+// are whatever the game here comes to, each finished game is recorded and its replay checked
+// before the scores go in the high score table. players can send in replays of games played
+// elsewhere too, those go through the same check
+
+const MAX_ROOMS: usize = 64;
+pub const GRACE: Duration = Duration::from_secs(30); // unless snake-server is told otherwise
+const SHOWN_SCORES: usize = 10; // high scores sent when asked
+const QUEUED: usize = 256; // messages a client can fall behind by before it is cut off
+
+struct Client {
+    name: String,
+    width: u16,
+    height: u16,
+    version: u32,                        // from their hello, older clients get told less
+    outbox: Option<SyncSender<Message>>, // to its writer, None once it has been cut off
+    updates: Updates,                    // what their game looked like last time they were sent it
+    room: Option<u32>,
+}
+
+struct RoomState {
+    name: String,
+    rules: RoomRules,
+    seats: Vec<SeatState>,
+    game: Option<Sender<(usize, Direction)>>, // moves for the running game, by seat
+    size: (u16, u16),                         // of the running game's board
+    sessions: Vec<String>,                    // per seat, while the game runs
+    watchers: Vec<u64>,
+}
+
+struct SeatState {
+    client: Option<u64>, // nobody while they are away from a running game
+    name: String,
+    ready: bool,
+    dropped: Option<Instant>, // when the connection went, None if they left on purpose
+}
+
+// how a seat is doing in a running game
+enum Presence {
+    Here(u64),
+    Away, // dropped, can still come back
+    Gone,
+}
+
+impl SeatState {
+    fn presence(&self, grace: Duration) -> Presence {
+        match (self.client, self.dropped) {
+            (Some(client), _) => Presence::Here(client),
+            (None, Some(when)) if when.elapsed() < grace => Presence::Away,
+            _ => Presence::Gone,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    next_client: u64,
+    next_room: u32,
+    clients: HashMap<u64, Client>,
+    rooms: BTreeMap<u32, RoomState>,
+    scores: Arc<Mutex<HighScores>>, // locked on its own, saving them takes a moment
+    scores_file: Option<PathBuf>,   // kept in memory only without one
+    grace: Duration,                // a dropped player's seat is kept this long
+}
+
+pub fn serve(listener: TcpListener, scores_file: Option<PathBuf>, grace: Duration) {
+    let scores = match &scores_file {
+        Some(path) => match HighScores::load(path) {
+            Ok(scores) => scores,
+            Err(e) => {
+                eprintln!(
+                    "Could not read the high scores in {} because: {}",
+                    path.display(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        },
+        None => HighScores::default(),
+    };
+    let lobby = Arc::new(Mutex::new(Lobby {
+        scores: Arc::new(Mutex::new(scores)),
+        scores_file,
+        grace,
+        ..Lobby::default()
+    }));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || client(lobby, stream));
+    }
+}
+
+fn client(shared: Arc<Mutex<Lobby>>, stream: TcpStream) {
+    let peer = stream
+        .peer_addr()
+        .map_or(String::from("someone"), |address| address.to_string());
+    // a client that stops reading gives its writer up rather than keeping it forever
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+    // browsers come in over http, either for the web client or its websocket
+    let connection = if web::is_http(&stream) {
+        web::accept(stream)
+    } else {
+        Connection::new(stream).ok()
+    };
+    let Some(connection) = connection else {
+        return;
+    };
+    let Ok(outbox) = connection.outbox() else {
+        return;
+    };
+
+    let id = {
+        let mut lobby = shared.lock().unwrap();
+        let id = lobby.next_client;
+        lobby.next_client += 1;
+        lobby.clients.insert(
+            id,
+            Client {
+                name: peer.clone(),
+                width: 80,
+                height: 24,
+                version: 0,
+                outbox: Some(writer(outbox)),
+                updates: Updates::new(0),
+                room: None,
+            },
+        );
+        id
+    };
+    println!("{} connected", peer);
+
+    for message in connection.inbox.iter() {
+        // a game played somewhere else, checked without holding the lobby up. only single
+        // player ones, and under the name we know them by, anyone could claim a versus win
+        if let Message::Submit { mut replay } = message {
+            let answer = if replay.names.len() == 1 {
+                replay.names = vec![shared.lock().unwrap().clients[&id].name.clone()];
+                let mode = replay.mode();
+                match submit(&shared, replay) {
+                    Ok(_) => Message::HighScores {
+                        scores: top(&shared, Some(&mode)),
+                    },
+                    Err(reason) => Message::Refused {
+                        reason: format!("that score does not check out, {}", reason),
+                    },
+                }
+            } else {
+                Message::Refused {
+                    reason: String::from("only single player games can be sent in"),
+                }
+            };
+            shared.lock().unwrap().send(id, &answer);
+            continue;
+        }
+
+        let mut lobby = shared.lock().unwrap();
+        match message {
+            Message::Hello { version, .. } if version > VERSION => {
+                let reason = format!(
+                    "the server is older than your game, it speaks version {} and you {}",
+                    VERSION, version
+                );
+                lobby.refuse(id, &reason);
+                break;
+            }
+            // every board is as small as the smallest terminal in the room, so one that
+            // couldnt be played would spoil the game for everyone
+            Message::Hello { width, height, .. }
+                if width < *SIDES.start() || height < *SIDES.start() =>
+            {
+                let reason = format!(
+                    "a {}x{} window is too small to play in, it needs to be at least {}x{}",
+                    width,
+                    height,
+                    SIDES.start(),
+                    SIDES.start()
+                );
+                lobby.refuse(id, &reason);
+                break;
+            }
+            Message::Hello {
+                version,
+                width,
+                height,
+                name,
+            } => {
+                if let Some(client) = lobby.clients.get_mut(&id) {
+                    client.width = width.min(*SIDES.end());
+                    client.height = height.min(*SIDES.end());
+                    client.version = version;
+                    client.updates = Updates::new(version);
+                    if !name.is_empty() {
+                        client.name = name;
+                    }
+                }
+                if version >= DELTAS {
+                    lobby.send(id, &Message::Version { version: VERSION });
+                }
+                let rooms = lobby.rooms_message();
+                lobby.send(id, &rooms);
+            }
+            Message::Ping { id: ping } => lobby.send(id, &Message::Pong { id: ping }),
+            Message::ListRooms => {
+                let rooms = lobby.rooms_message();
+                lobby.send(id, &rooms);
+            }
+            Message::CreateRoom { name, rules } => {
+                if !(1..=2).contains(&rules.players) || !(10..=1000).contains(&rules.speed_ms) {
+                    lobby.refuse(id, "rooms are for 1 or 2 players at 10 to 1000ms a tick");
+                } else if lobby.rooms.len() >= MAX_ROOMS {
+                    lobby.refuse(id, "the server has no room for more rooms");
+                } else {
+                    lobby.leave(id);
+                    let room = lobby.next_room;
+                    lobby.next_room += 1;
+                    let name = if name.trim().is_empty() {
+                        format!("room {}", room)
+                    } else {
+                        name
+                    };
+                    println!("{} made room {} ({})", peer, room, name);
+                    lobby.rooms.insert(
+                        room,
+                        RoomState {
+                            name,
+                            rules,
+                            seats: Vec::new(),
+                            game: None,
+                            size: (0, 0),
+                            sessions: Vec::new(),
+                            watchers: Vec::new(),
+                        },
+                    );
+                    lobby.sit(id, room);
+                    lobby.changed();
+                }
+            }
+            Message::JoinRoom { room } => match lobby.rooms.get(&room) {
+                None => lobby.refuse(id, &format!("there is no room {}", room)),
+                Some(state) if state.game.is_some() => {
+                    lobby.refuse(id, &format!("room {} is playing already", room))
+                }
+                Some(state) if state.seats.len() >= state.rules.players as usize => {
+                    lobby.refuse(id, &format!("room {} is full", room))
+                }
+                Some(_) => {
+                    lobby.leave(id);
+                    lobby.sit(id, room);
+                    lobby.changed();
+                }
+            },
+            Message::Watch { room } => {
+                // without a room whatever is playing, or failing that anything at all
+                let room = room
+                    .or_else(|| {
+                        let mut rooms = lobby.rooms.iter();
+                        rooms
+                            .find(|(_, state)| state.game.is_some())
+                            .map(|(id, _)| *id)
+                    })
+                    .or_else(|| lobby.rooms.keys().next().copied());
+                match room.filter(|room| lobby.rooms.contains_key(room)) {
+                    Some(room) => {
+                        lobby.leave(id);
+                        if let Some(state) = lobby.rooms.get_mut(&room) {
+                            state.watchers.push(id);
+                        }
+                        if let Some(client) = lobby.clients.get_mut(&id) {
+                            client.room = Some(room);
+                        }
+                        lobby.changed();
+                    }
+                    None => lobby.refuse(id, "there is nothing to watch"),
+                }
+            }
+            Message::Rejoin { session } => {
+                if lobby.rejoin(id, &session) {
+                    println!("{} is back in", peer);
+                    lobby.changed();
+                } else {
+                    lobby.refuse(id, "that game is over or nobody is missing from it");
+                }
+            }
+            Message::LeaveRoom => {
+                lobby.leave(id);
+                lobby.changed();
+            }
+            Message::Ready { ready } => {
+                let room = lobby.clients.get(&id).and_then(|client| client.room);
+                if let Some(room) = room {
+                    if let Some(state) = lobby.rooms.get_mut(&room) {
+                        if state.game.is_none() {
+                            let mine = state.seats.iter_mut().filter(|s| s.client == Some(id));
+                            for seat in mine {
+                                seat.ready = ready;
+                            }
+                        }
+                    }
+                    lobby.start_if_ready(room, &shared);
+                    lobby.changed();
+                }
+            }
+            Message::Input { direction } => {
+                let room = lobby.clients.get(&id).and_then(|client| client.room);
+                if let Some(state) = room.and_then(|room| lobby.rooms.get(&room)) {
+                    let seat = state.seats.iter().position(|s| s.client == Some(id));
+                    if let (Some(game), Some(seat)) = (&state.game, seat) {
+                        let _ = game.send((seat, direction));
+                    }
+                }
+            }
+            Message::Chat { text, .. } => {
+                let room = lobby.clients.get(&id).and_then(|client| client.room);
+                if let Some(room) = room {
+                    lobby.chat(id, room, &text);
+                }
+            }
+            Message::ListScores { mode } => {
+                drop(lobby);
+                let scores = top(&shared, mode.as_deref());
+                shared
+                    .lock()
+                    .unwrap()
+                    .send(id, &Message::HighScores { scores });
+            }
+            Message::Bye => break,
+            _ => {}
+        }
+    }
+
+    let mut lobby = shared.lock().unwrap();
+    if lobby.lost(id) {
+        println!("{} dropped out of a game", peer);
+    }
+    lobby.leave(id);
+    lobby.clients.remove(&id);
+    lobby.changed();
+    println!("{} left", peer);
+}
+
+// sends whatever is queued for a client in order, until the queue is dropped or the
+// client stops taking it. either way it is hung up on once the queue is done
+fn writer(mut outbox: Outbox) -> SyncSender<Message> {
+    let (queue, queued) = mpsc::sync_channel(QUEUED);
+    thread::spawn(move || {
+        for message in queued.iter() {
+            if outbox.send(&message).is_err() {
+                break;
+            }
+        }
+        outbox.close();
+    });
+    queue
+}
+
+// checks the replay and puts whatever made it into the high scores, saving them. nothing
+// is locked while it is checked
+fn submit(lobby: &Arc<Mutex<Lobby>>, replay: Replay) -> Result<Vec<HighScore>, String> {
+    let replay = Checked::new(replay)?;
+    let (scores, file) = {
+        let lobby = lobby.lock().unwrap();
+        (Arc::clone(&lobby.scores), lobby.scores_file.clone())
+    };
+    let mut scores = scores.lock().unwrap();
+    let made_it = scores.submit(replay);
+    if let (false, Some(file)) = (made_it.is_empty(), file) {
+        if let Err(e) = scores.save(&file) {
+            eprintln!(
+                "Could not save the high scores to {} because: {}",
+                file.display(),
+                e
+            );
+        }
+    }
+    Ok(made_it)
+}
+
+fn top(lobby: &Arc<Mutex<Lobby>>, mode: Option<&str>) -> Vec<HighScore> {
+    let scores = Arc::clone(&lobby.lock().unwrap().scores);
+    let top = scores.lock().unwrap().top(mode, SHOWN_SCORES);
+    top
+}
+
+impl Lobby {
+    // queued for the client's writer. one too far behind to catch up is cut off, its
+    // writer finishes what is queued and hangs up
+    fn send(&mut self, id: u64, message: &Message) {
+        let Some(client) = self.clients.get_mut(&id) else {
+            return;
+        };
+        let full = client
+            .outbox
+            .as_ref()
+            .is_some_and(|outbox| outbox.try_send(message.clone()).is_err());
+        if full {
+            client.outbox = None;
+        }
+    }
+
+    fn refuse(&mut self, id: u64, reason: &str) {
+        self.send(
+            id,
+            &Message::Refused {
+                reason: String::from(reason),
+            },
+        );
+    }
+
+    // to everyone else in the room, seated or watching, under the name we know them by
+    fn chat(&mut self, id: u64, room: u32, text: &str) {
+        let (Some(client), Some(state)) = (self.clients.get(&id), self.rooms.get(&room)) else {
+            return;
+        };
+        let said = Message::Chat {
+            from: client.name.clone(),
+            text: text.chars().take(chat::LONGEST).collect(),
+        };
+        let seated = state.seats.iter().filter_map(|seat| seat.client);
+        let everyone: Vec<u64> = seated.chain(state.watchers.iter().copied()).collect();
+        for other in everyone.into_iter().filter(|other| *other != id) {
+            self.send(other, &said);
+        }
+    }
+
+    fn sit(&mut self, id: u64, room: u32) {
+        let Some(client) = self.clients.get_mut(&id) else {
+            return;
+        };
+        client.room = Some(room);
+        if let Some(state) = self.rooms.get_mut(&room) {
+            state.seats.push(SeatState {
+                client: Some(id),
+                name: client.name.clone(),
+                ready: false,
+                dropped: None,
+            });
+        }
+    }
+
+    // out of whatever room they are in, a room nobody is left in is closed
+    // leaving a running game gives it up, the room's thread notices the empty seat
+    fn leave(&mut self, id: u64) {
+        let Some(room) = self.clients.get_mut(&id).and_then(|client| {
+            client.updates.reset();
+            client.room.take()
+        }) else {
+            return;
+        };
+        if let Some(state) = self.rooms.get_mut(&room) {
+            state.watchers.retain(|who| *who != id);
+            if state.game.is_some() {
+                for seat in state.seats.iter_mut().filter(|s| s.client == Some(id)) {
+                    seat.client = None;
+                }
+            } else {
+                state.seats.retain(|seat| seat.client != Some(id));
+            }
+            if state.seats.is_empty() && state.game.is_none() {
+                self.close(room);
+            }
+        }
+    }
+
+    // the connection went, a seat in a running game is kept for them to come back to
+    fn lost(&mut self, id: u64) -> bool {
+        let Some(room) = self.clients.get(&id).and_then(|client| client.room) else {
+            return false;
+        };
+        let Some(state) = self
+            .rooms
+            .get_mut(&room)
+            .filter(|state| state.game.is_some())
+        else {
+            return false;
+        };
+        let Some(seat) = state.seats.iter_mut().find(|s| s.client == Some(id)) else {
+            return false;
+        };
+        seat.client = None;
+        seat.dropped = Some(Instant::now());
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.room = None;
+        }
+        true
+    }
+
+    // back into the seat the session was handed out for, as long as it is still waiting
+    fn rejoin(&mut self, id: u64, session: &str) -> bool {
+        let found = self.rooms.iter().find_map(|(room, state)| {
+            let seat = state.sessions.iter().position(|s| s == session)?;
+            let waiting = matches!(state.seats.get(seat)?.presence(self.grace), Presence::Away);
+            (state.game.is_some() && waiting).then_some((*room, seat))
+        });
+        let Some((room, you)) = found else {
+            return false;
+        };
+
+        self.leave(id);
+        let Some(state) = self.rooms.get_mut(&room) else {
+            return false;
+        };
+        state.seats[you].client = Some(id);
+        state.seats[you].dropped = None;
+        let ((width, height), rules) = (state.size, state.rules);
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.room = Some(room);
+        }
+        self.send(
+            id,
+            &Message::Welcome {
+                you,
+                width,
+                height,
+                border: rules.border,
+                speed_ms: rules.speed_ms,
+                lockstep: None,
+                session: Some(String::from(session)),
+            },
+        );
+        true
+    }
+
+    // anyone still watching is back in the lobby
+    fn close(&mut self, room: u32) {
+        let Some(state) = self.rooms.remove(&room) else {
+            return;
+        };
+        for watcher in state.watchers {
+            if let Some(client) = self.clients.get_mut(&watcher) {
+                client.room = None;
+            }
+        }
+    }
+
+    fn room(&self, id: u32) -> Option<Room> {
+        let state = self.rooms.get(&id)?;
+        Some(Room {
+            id,
+            name: state.name.clone(),
+            rules: state.rules,
+            seats: state
+                .seats
+                .iter()
+                .map(|seat| Seat {
+                    name: seat.name.clone(),
+                    ready: seat.ready,
+                    away: matches!(seat.presence(self.grace), Presence::Away),
+                })
+                .collect(),
+            playing: state.game.is_some(),
+        })
+    }
+
+    // the next tick of a game, as a delta to those who take them
+    fn update(&mut self, id: u64, board: Board) {
+        if let Some(client) = self.clients.get_mut(&id) {
+            let update = client.updates.next(board);
+            self.send(id, &update);
+        }
+    }
+
+    // only for those who know what an event is
+    fn event(&mut self, to: &[u64], tick: u64, event: GameEvent) {
+        for who in to {
+            if self.clients.get(who).is_some_and(|c| c.version >= DELTAS) {
+                self.send(*who, &Message::Event { tick, event });
+            }
+        }
+    }
+
+    fn rooms_message(&self) -> Message {
+        Message::Rooms {
+            rooms: self.rooms.keys().filter_map(|id| self.room(*id)).collect(),
+        }
+    }
+
+    // tells everyone how things are now, the lobby the list of rooms and each room about itself
+    fn changed(&mut self) {
+        let rooms = self.rooms_message();
+        let messages: Vec<(u64, Message)> = self
+            .clients
+            .iter()
+            .map(
+                |(id, client)| match client.room.and_then(|room| self.room(room)) {
+                    Some(room) => (*id, Message::Room { room }),
+                    None => (*id, rooms.clone()),
+                },
+            )
+            .collect();
+        for (id, message) in messages {
+            self.send(id, &message);
+        }
+    }
+
+    fn start_if_ready(&mut self, room: u32, lobby: &Arc<Mutex<Lobby>>) {
+        let Some(state) = self.rooms.get_mut(&room) else {
+            return;
+        };
+        let full = state.seats.len() == state.rules.players as usize;
+        if state.game.is_some() || !full || !state.seats.iter().all(|seat| seat.ready) {
+            return;
+        }
+
+        // the board has to fit every player's terminal
+        let seats: Vec<u64> = state.seats.iter().filter_map(|seat| seat.client).collect();
+        let (width, height) = seats
+            .iter()
+            .filter_map(|who| self.clients.get(who))
+            .fold((u16::MAX, u16::MAX), |(w, h), client| {
+                (w.min(client.width), h.min(client.height))
+            });
+        let (moves, inbox) = mpsc::channel();
+        state.game = Some(moves);
+        state.size = (width, height);
+        state.sessions = seats
+            .iter()
+            .map(|_| format!("{:016x}", rand::random::<u64>()))
+            .collect();
+        let (rules, sessions) = (state.rules, state.sessions.clone());
+        let names = state.seats.iter().map(|seat| seat.name.clone()).collect();
+        println!("room {} started, {}x{}", room, width, height);
+
+        for (you, (who, session)) in seats.iter().zip(sessions).enumerate() {
+            if let Some(client) = self.clients.get_mut(who) {
+                client.updates.reset();
+            }
+            self.send(
+                *who,
+                &Message::Welcome {
+                    you,
+                    width,
+                    height,
+                    border: rules.border,
+                    speed_ms: rules.speed_ms,
+                    lockstep: None,
+                    session: Some(session),
+                },
+            );
+        }
+
+        let lobby = Arc::clone(lobby);
+        thread::spawn(move || play(lobby, room, names, rules, width, height, inbox));
+    }
+}
+
+// one room's game, sends every seat the state each tick until it is over or everyone left
+fn play(
+    shared: Arc<Mutex<Lobby>>,
+    room: u32,
+    names: Vec<String>,
+    rules: RoomRules,
+    width: u16,
+    height: u16,
+    moves: Receiver<(usize, Direction)>,
+) {
+    let players = names.len();
+    let mut game = if players == 2 {
+        SnakeGame::multiplayer_new(width, height)
+    } else {
+        SnakeGame::new(width, height)
+    };
+    // the scores that count are the ones this game comes to, the replay shows how
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut replay = Replay::start(&game, rules.border, rules.speed_ms, &names);
+    let mut referee = Referee::new(players);
+    let mut autopilot: Vec<GreedyBot> =
+        (0..players).map(|_| GreedyBot::new(rules.border)).collect();
+    let frame_duration = Duration::from_millis(rules.speed_ms);
+    let mut away = vec![false; players];
+
+    loop {
+        let frame_start = Instant::now();
+
+        // every move sent since the previous tick that a player could really make
+        for (seat, direction) in moves.try_iter() {
+            if referee.judge(&game, seat, direction).is_ok() {
+                game.steer(seat, direction);
+            }
+        }
+
+        let mut lobby = shared.lock().unwrap();
+        let Some(state) = lobby.rooms.get(&room) else {
+            return;
+        };
+        let presence: Vec<Presence> = state
+            .seats
+            .iter()
+            .map(|seat| seat.presence(lobby.grace))
+            .collect();
+        let watchers = state.watchers.clone();
+        let gone: Vec<bool> = presence
+            .iter()
+            .map(|p| matches!(p, Presence::Gone))
+            .collect();
+
+        // whoever is still here wins against whoever left, with nobody here it waits
+        match gone.as_slice() {
+            [false, true] => {
+                game.game_over = true;
+                game.winner = 1;
+            }
+            [true, false] => {
+                game.game_over = true;
+                game.winner = 2;
+            }
+            _ if gone.contains(&true) => game.game_over = true,
+            _ if !presence.iter().any(|p| matches!(p, Presence::Here(_))) => {}
+            _ => {
+                for (seat, bot) in autopilot.iter_mut().enumerate() {
+                    if matches!(presence[seat], Presence::Away) {
+                        let direction = bot.next_direction(&game, seat, &[]);
+                        game.steer(seat, direction);
+                    }
+                }
+                replay.record(&game);
+                game.tick(rules.border);
+                referee.tick();
+            }
+        }
+
+        let mut everyone = watchers.clone();
+        for (seat, here) in presence.iter().enumerate() {
+            if let Presence::Here(who) = here {
+                lobby.update(*who, Board::of(&game, rules.border, seat));
+                everyone.push(*who);
+            }
+        }
+        for watcher in watchers {
+            lobby.update(watcher, Board::of(&game, rules.border, 0));
+        }
+        for (player, p) in presence.iter().enumerate() {
+            let now = matches!(p, Presence::Away);
+            if now != away[player] {
+                let event = if now {
+                    GameEvent::Away { player }
+                } else {
+                    GameEvent::Back { player }
+                };
+                lobby.event(&everyone, game.ticks(), event);
+                away[player] = now;
+            }
+        }
+
+        if game.game_over {
+            println!("room {} finished after {} ticks", room, game.ticks());
+            for (name, fouls) in names.iter().zip(&referee.fouls) {
+                if let Some(foul) = fouls.last() {
+                    let thrown = fouls.len();
+                    println!(
+                        "room {}: {} had {} moves thrown out, last {}",
+                        room,
+                        name,
+                        thrown,
+                        foul.describe()
+                    );
+                }
+            }
+            if let Some(state) = lobby.rooms.get_mut(&room) {
+                state.game = None;
+                state.sessions.clear();
+                state.seats.retain(|seat| seat.client.is_some());
+                for seat in &mut state.seats {
+                    seat.ready = false;
+                }
+                if state.seats.is_empty() {
+                    lobby.close(room);
+                }
+            }
+            lobby.changed();
+            drop(lobby);
+
+            // a game someone left before the end has no replay that ends, so isnt scored
+            replay.finish(&game);
+            match submit(&shared, replay) {
+                Ok(made_it) if !made_it.is_empty() => {
+                    println!("room {} made the high scores", room)
+                }
+                Ok(_) => {}
+                Err(reason) => println!("room {} not scored: {}", room, reason),
+            }
+            return;
+        }
+        drop(lobby);
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_duration {
+            thread::sleep(frame_duration - elapsed);
+        }
+    }
+}
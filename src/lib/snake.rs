@@ -1,14 +1,17 @@
+use super::board::Board;
 use super::tm_logic;
 use crossterm::{
     cursor, execute,
     style::Print,
     terminal::{Clear, ClearType},
 };
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::io::stdout;
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     Up,
     Down,
@@ -16,7 +19,25 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Clone, Copy)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Point {
     pub x: u16,
     pub y: u16,
@@ -29,13 +50,14 @@ impl PartialEq for Point {
     }
 }
 
+#[derive(Clone)]
 pub struct SnakeGame {
     player1: VecDeque<Point>,
     player2: Option<VecDeque<Point>>,
     pub direction: Direction,
     pub direction2: Direction,
-    growing: bool,
-    growing2: bool,
+    growing: u16, // segments left to grow, each fruit is worth two
+    growing2: u16,
     fruits: [Point; 5], // this is for multiplayer, single player only has one fruit
     height: u16,
     width: u16,
@@ -43,11 +65,19 @@ pub struct SnakeGame {
     score2: u16,
     pub winner: u16,
     pub game_over: bool,
+    ticks: u64,
+    seed: u64,
+    rng: StdRng, // every fruit comes from here so a seed replays the same game
 }
 
 // single player
 impl SnakeGame {
     pub fn new(width: u16, height: u16) -> Self {
+        Self::new_seeded(width, height, rand::random())
+    }
+
+    pub fn new_seeded(width: u16, height: u16, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut snake = VecDeque::new();
         snake.push_back(Point {
             x: width / 2,
@@ -63,8 +93,8 @@ impl SnakeGame {
         });
 
         let fruit: Point = Point {
-            x: rand::thread_rng().gen_range(1..width - 1),
-            y: rand::thread_rng().gen_range(1..height - 1),
+            x: rng.gen_range(1..width - 1),
+            y: rng.gen_range(1..height - 1),
         };
 
         let fruits: [Point; 5] = [fruit; 5];
@@ -74,42 +104,83 @@ impl SnakeGame {
             player2: None,
             direction: Direction::Left,
             direction2: Direction::Left,
-            fruits: fruits,
+            fruits,
             score: 0,
             score2: 0,
             game_over: false,
-            growing: false,
-            growing2: false,
+            growing: 0,
+            growing2: 0,
             winner: 0,
-            height: height,
-            width: width,
+            height,
+            width,
+            ticks: 0,
+            seed,
+            rng,
+        }
+    }
+
+    // rebuilds a game from the outside view of one, for bots that only get told the board
+    // only the first two snakes fit, it is a two player game after all
+    pub fn from_board(board: &Board) -> Option<Self> {
+        let body = |index: usize| -> Option<VecDeque<Point>> {
+            let snake = board.snakes.get(index)?;
+            (!snake.body.is_empty()).then(|| snake.body.iter().copied().collect())
+        };
+        let player1 = body(0)?;
+        let player2 = body(1);
+
+        // pad the fruits out with ones nobody can reach
+        let mut fruits = [Point {
+            x: board.width,
+            y: board.height,
+        }; 5];
+        for (fruit, seen) in fruits.iter_mut().zip(&board.fruits) {
+            *fruit = *seen;
         }
+
+        let view = |index: usize| board.snakes.get(index);
+        Some(Self {
+            player1,
+            player2,
+            direction: view(0).map_or(Direction::Left, |snake| snake.direction),
+            direction2: view(1).map_or(Direction::Left, |snake| snake.direction),
+            growing: 0,
+            growing2: 0,
+            fruits,
+            height: board.height,
+            width: board.width,
+            score: view(0).map_or(0, |snake| snake.score),
+            score2: view(1).map_or(0, |snake| snake.score),
+            winner: board.winner,
+            game_over: board.game_over,
+            ticks: board.tick,
+            seed: 0,
+            rng: StdRng::seed_from_u64(board.tick),
+        })
     }
 
     // updates the location
     // checks for collision and if the snake ate food
     pub fn update(&mut self, border: bool) {
+        self.ticks += 1;
         self.move_snake(border);
         let head = self.player1[0];
 
         // check for collision
         self.game_over = self.player1.iter().skip(1).any(|segment| *segment == head);
 
+        // the snake covers every cell, nothing left to do but win
+        if !self.game_over && self.player1.len() >= self.cells(border) {
+            self.game_over = true;
+            self.winner = 1;
+            return;
+        }
+
         // ate the apple
         if self.fruits[0] == head {
             self.score += 1;
-            let last = self.player1.back().unwrap(); // this should never be None
-            self.player1.push_back(Point {
-                x: last.x + 1,
-                y: last.y,
-            });
-
-            self.growing = true;
-            self.gen_fruit();
-        }
-
-        if self.score == self.width * self.height {
-            self.game_over = true;
+            self.growing += 2;
+            self.gen_fruit(border);
         }
     }
 
@@ -167,7 +238,7 @@ impl SnakeGame {
                 Direction::Up => {
                     if border && head.y == 0 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: head.x,
@@ -182,7 +253,7 @@ impl SnakeGame {
                 Direction::Down => {
                     if border && head.y == self.height - 1 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: head.x,
@@ -197,7 +268,7 @@ impl SnakeGame {
                 Direction::Left => {
                     if border && head.x == 0 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: if border {
@@ -212,7 +283,7 @@ impl SnakeGame {
                 Direction::Right => {
                     if border && head.x == self.width - 1 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: if border {
@@ -230,43 +301,304 @@ impl SnakeGame {
         // prepend the new head
         self.player1.push_front(new_head);
 
-        if !self.growing {
+        if self.growing == 0 {
             self.player1.pop_back();
         } else {
-            self.growing = false;
+            self.growing -= 1;
         }
     }
 
     // random x y for the fruit
-    // recursivley called if the food is spawned in the snake thats not the head
-    fn gen_fruit(&mut self) {
-        self.fruits[0] = Point {
-            x: rand::thread_rng().gen_range(1..self.width - 1),
-            y: rand::thread_rng().gen_range(1..self.height - 1),
+    // if there is no room left for one the board is full and the player has won
+    fn gen_fruit(&mut self, border: bool) {
+        match self.free_cell(border) {
+            Some(fruit) => self.fruits[0] = fruit,
+            None => {
+                self.game_over = true;
+                self.winner = 1;
+            }
+        }
+    }
+
+    // puts a fruit at `at`, or anywhere free without one, for the control socket. there are
+    // only so many fruits, so it is the one furthest from every head that moves there
+    // with borders the edge cells are the wall, a fruit there could never be eaten safely
+    pub fn place_fruit(&mut self, at: Option<Point>, border: bool) -> Result<Point, String> {
+        let (width, height) = (self.width, self.height);
+        let in_wall = |at: Point| {
+            border && (at.x == 0 || at.y == 0 || at.x == width - 1 || at.y == height - 1)
         };
+        let fruit = match at {
+            Some(at) if at.x >= self.width || at.y >= self.height => {
+                return Err(format!(
+                    "{},{} is off the {}x{} board",
+                    at.x, at.y, self.width, self.height
+                ))
+            }
+            Some(at) if in_wall(at) => return Err(format!("{},{} is in the wall", at.x, at.y)),
+            Some(at)
+                if self
+                    .player1
+                    .iter()
+                    .chain(self.player2.iter().flatten())
+                    .any(|s| *s == at) =>
+            {
+                return Err(format!("there is a snake on {},{}", at.x, at.y))
+            }
+            Some(at) => at,
+            None => self
+                .free_cell(border)
+                .ok_or("there is no room for a fruit")?,
+        };
+        if self.fruits().contains(&fruit) {
+            return Ok(fruit);
+        }
 
-        if self
-            .player1
+        let heads: Vec<Point> = (0..self.players()).map(|p| self.body(p)[0]).collect();
+        let away = |fruit: &Point| {
+            heads
+                .iter()
+                .map(|head| head.x.abs_diff(fruit.x) + head.y.abs_diff(fruit.y))
+                .min()
+                .unwrap_or(0)
+        };
+        let index = (0..self.fruits().len())
+            .max_by_key(|i| away(&self.fruits[*i]))
+            .unwrap_or(0);
+        self.fruits[index] = fruit;
+        Ok(fruit)
+    }
+
+    // cells there are to fill, with borders the edges are the wall and dont count
+    fn cells(&self, border: bool) -> usize {
+        if border {
+            self.width.saturating_sub(2) as usize * self.height.saturating_sub(2) as usize
+        } else {
+            self.width as usize * self.height as usize
+        }
+    }
+
+    // picks a random cell no snake is on, away from the edges if there is any room there
+    // wrapping round, the edges are only used once the inside is full so a perfect game can
+    // still finish. with borders they are the wall, so a full inside is a full board
+    fn free_cell(&mut self, border: bool) -> Option<Point> {
+        let width = self.width as usize;
+        let mut taken = vec![false; self.width as usize * self.height as usize];
+        for segment in self.player1.iter().chain(self.player2.iter().flatten()) {
+            if segment.x < self.width && segment.y < self.height {
+                taken[segment.y as usize * width + segment.x as usize] = true;
+            }
+        }
+
+        let free: Vec<Point> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .filter(|point| !taken[point.y as usize * width + point.x as usize])
+            .collect();
+
+        let inside: Vec<Point> = free
             .iter()
-            .skip(1)
-            .any(|segment| *segment == self.fruits[0])
-        {
-            self.gen_fruit();
+            .copied()
+            .filter(|point| {
+                (1..self.width - 1).contains(&point.x) && (1..self.height - 1).contains(&point.y)
+            })
+            .collect();
+
+        let pool = if inside.is_empty() && !border {
+            free
+        } else {
+            inside
+        };
+        if pool.is_empty() {
+            return None;
+        }
+
+        Some(pool[self.rng.gen_range(0..pool.len())])
+    }
+}
+
+// read only view of the game plus steering, this is all a Controller gets to see
+// players are indexed from 0, so player 0 is player1 and player 1 is player2
+impl SnakeGame {
+    pub fn players(&self) -> usize {
+        if self.player2.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // fnv-1a over everything that decides how the game goes on, two games that hash the
+    // same on the same tick will keep doing the same thing given the same moves
+    pub fn state_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut mix = |value: u64| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+
+        mix(self.ticks);
+        for player in 0..self.players() {
+            mix(self.body(player).len() as u64);
+            for segment in self.body(player) {
+                mix(((segment.x as u64) << 16) | segment.y as u64);
+            }
+            mix(self.direction_of(player) as u64);
+            mix(self.score(player) as u64);
+            mix(self.growing(player) as u64);
+        }
+        for fruit in self.fruits() {
+            mix(((fruit.x as u64) << 16) | fruit.y as u64);
+        }
+        mix(self.game_over as u64);
+        mix(self.winner as u64);
+        hash
+    }
+
+    // a copy that plays on with its own fruit luck, so a bot searching ahead on it
+    // cannot find out where the real game will put the next fruit
+    pub fn fork(&self, seed: u64) -> SnakeGame {
+        let mut game = self.clone();
+        game.rng = StdRng::seed_from_u64(seed);
+        game
+    }
+
+    pub fn body(&self, player: usize) -> &VecDeque<Point> {
+        match player {
+            0 => &self.player1,
+            _ => self.player2.as_ref().unwrap_or(&self.player1),
+        }
+    }
+
+    pub fn score(&self, player: usize) -> u16 {
+        match player {
+            0 => self.score,
+            _ => self.score2,
+        }
+    }
+
+    // segments this snake still has to grow, its tail stays put until this is 0
+    pub fn growing(&self, player: usize) -> u16 {
+        match player {
+            0 => self.growing,
+            _ => self.growing2,
+        }
+    }
+
+    pub fn direction_of(&self, player: usize) -> Direction {
+        match player {
+            0 => self.direction,
+            _ => self.direction2,
+        }
+    }
+
+    // single player only ever uses the first fruit
+    pub fn fruits(&self) -> &[Point] {
+        &self.fruits[..if self.player2.is_some() { 5 } else { 1 }]
+    }
+
+    // where a head at `from` ends up after one step, None if it would run into a wall
+    pub fn next_point(&self, from: Point, direction: Direction, border: bool) -> Option<Point> {
+        let (width, height) = (self.width, self.height);
+        match direction {
+            Direction::Up if border && from.y == 0 => None,
+            Direction::Down if border && from.y >= height - 1 => None,
+            Direction::Left if border && from.x == 0 => None,
+            Direction::Right if border && from.x >= width - 1 => None,
+            Direction::Up => Some(Point {
+                x: from.x,
+                y: (from.y + height - 1) % height,
+            }),
+            Direction::Down => Some(Point {
+                x: from.x,
+                y: (from.y + 1) % height,
+            }),
+            Direction::Left => Some(Point {
+                x: (from.x + width - 1) % width,
+                y: from.y,
+            }),
+            Direction::Right => Some(Point {
+                x: (from.x + 1) % width,
+                y: from.y,
+            }),
+        }
+    }
+
+    // 1 or 2 for whoever has eaten more, 3 for a draw
+    pub fn leader(&self) -> u16 {
+        if self.score > self.score2 {
+            1
+        } else if self.score < self.score2 {
+            2
+        } else {
+            3
+        }
+    }
+
+    // turning straight back into your own neck is ignored, same as the keyboard always did
+    pub fn steer(&mut self, player: usize, direction: Direction) {
+        if direction == self.direction_of(player).opposite() {
+            return;
+        }
+
+        match player {
+            0 => self.direction = direction,
+            _ => self.direction2 = direction,
+        }
+    }
+
+    // one tick of whichever mode this game was created for
+    pub fn tick(&mut self, border: bool) {
+        if self.player2.is_some() {
+            self.multiplayer_update(border);
+        } else {
+            self.update(border);
+        }
+    }
+
+    pub fn render(&mut self) {
+        if self.player2.is_some() {
+            self.multiplayer_draw();
+        } else {
+            self.draw();
         }
     }
 }
 
 pub trait Multiplayer {
     fn multiplayer_new(width: u16, height: u16) -> Self;
+    fn multiplayer_new_seeded(width: u16, height: u16, seed: u64) -> Self;
     fn multiplayer_update(&mut self, border: bool);
     fn multiplayer_draw(&mut self);
     fn multiplayer_move_snake(&mut self, border: bool);
     fn collision(&mut self);
-    fn gen_fruits(&mut self, index: usize);
+    fn gen_fruits(&mut self, index: usize, border: bool);
 }
 
 impl Multiplayer for SnakeGame {
     fn multiplayer_new(width: u16, height: u16) -> Self {
+        Self::multiplayer_new_seeded(width, height, rand::random())
+    }
+
+    fn multiplayer_new_seeded(width: u16, height: u16, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut snake = VecDeque::new();
         snake.push_back(Point {
             x: width - 2,
@@ -284,8 +616,8 @@ impl Multiplayer for SnakeGame {
         let mut fruits = [Point { x: 0, y: 0 }; 5];
         for fruit in &mut fruits {
             *fruit = Point {
-                x: rand::thread_rng().gen_range(1..width - 1),
-                y: rand::thread_rng().gen_range(1..height - 1),
+                x: rng.gen_range(1..width - 1),
+                y: rng.gen_range(1..height - 1),
             };
         }
 
@@ -312,16 +644,20 @@ impl Multiplayer for SnakeGame {
             score: 0,
             score2: 0,
             game_over: false,
-            growing: false,
-            growing2: false,
+            growing: 0,
+            growing2: 0,
             winner: 0,
-            height: height,
-            width: width,
+            height,
+            width,
+            ticks: 0,
+            seed,
+            rng,
         }
     }
 
     // Note: pressing q is considered a draw
     fn multiplayer_update(&mut self, border: bool) {
+        self.ticks += 1;
         self.move_snake(border);
         self.multiplayer_move_snake(border);
         let snake1_head = self.player1[0];
@@ -330,18 +666,21 @@ impl Multiplayer for SnakeGame {
         // check for collision
         self.collision();
 
+        // the two of them cover every cell, whoever ate more wins
+        if !self.game_over
+            && self.player1.len() + self.player2.as_ref().unwrap().len() >= self.cells(border)
+        {
+            self.game_over = true;
+            self.winner = self.leader();
+            return;
+        }
+
         // ate the apple
         for (index, fruit) in self.fruits.iter().enumerate() {
             if snake1_head == *fruit {
                 self.score += 1;
-                let last = self.player1.back().unwrap(); // this should never be None
-                self.player1.push_back(Point {
-                    x: last.x + 1,
-                    y: last.y,
-                });
-
-                self.growing = true;
-                self.gen_fruits(index);
+                self.growing += 2;
+                self.gen_fruits(index, border);
                 break;
             }
         }
@@ -349,22 +688,11 @@ impl Multiplayer for SnakeGame {
         for (index, fruit) in self.fruits.iter().enumerate() {
             if snake2_head == *fruit {
                 self.score2 += 1;
-                let player2 = self.player2.as_mut().unwrap();
-                let last = player2.back().unwrap(); // this should never be None
-                player2.push_back(Point {
-                    x: last.x + 1,
-                    y: last.y,
-                });
-
-                self.growing2 = true;
-                self.gen_fruits(index);
+                self.growing2 += 2;
+                self.gen_fruits(index, border);
                 break;
             }
         }
-
-        if self.score == self.width * self.height || self.score2 == self.width * self.height {
-            self.game_over = true;
-        }
     }
 
     fn multiplayer_draw(&mut self) {
@@ -414,7 +742,7 @@ impl Multiplayer for SnakeGame {
                 Direction::Up => {
                     if border && head.y == 0 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: head.x,
@@ -429,7 +757,7 @@ impl Multiplayer for SnakeGame {
                 Direction::Down => {
                     if border && head.y == self.height - 1 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: head.x,
@@ -444,7 +772,7 @@ impl Multiplayer for SnakeGame {
                 Direction::Left => {
                     if border && head.x == 0 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: if border {
@@ -459,7 +787,7 @@ impl Multiplayer for SnakeGame {
                 Direction::Right => {
                     if border && head.x == self.width - 1 {
                         self.game_over = true;
-                        head.clone()
+                        *head
                     } else {
                         Point {
                             x: if border {
@@ -477,10 +805,10 @@ impl Multiplayer for SnakeGame {
         // prepend the new head
         self.player2.as_mut().unwrap().push_front(new_head);
 
-        if !self.growing2 {
+        if self.growing2 == 0 {
             self.player2.as_mut().unwrap().pop_back();
         } else {
-            self.growing2 = false;
+            self.growing2 -= 1;
         }
     }
 
@@ -542,33 +870,19 @@ impl Multiplayer for SnakeGame {
 
         // they collide head on, so then the person with higher score wins
         if snake1_head == snake2_head {
-            self.winner = if self.score > self.score2 {
-                1
-            } else if self.score > self.score2 {
-                2
-            } else {
-                // draw
-                3
-            }
+            self.winner = self.leader();
         }
     }
 
     // same as gen fruit but make instead pass the index of the fruit that has
     // been eating by p1/p2
-    fn gen_fruits(&mut self, index: usize) {
-        loop {
-            let new_fruit = Point {
-                x: rand::thread_rng().gen_range(1..self.width - 1),
-                y: rand::thread_rng().gen_range(1..self.height - 1),
-            };
-
-            if !self.player1.iter().any(|segment| *segment == new_fruit)
-                && self.player2.as_ref().map_or(true, |player2| {
-                    !player2.iter().any(|segment| *segment == new_fruit)
-                })
-            {
-                self.fruits[index] = new_fruit;
-                break;
+    // a full board ends the game and the higher score wins
+    fn gen_fruits(&mut self, index: usize, border: bool) {
+        match self.free_cell(border) {
+            Some(fruit) => self.fruits[index] = fruit,
+            None => {
+                self.game_over = true;
+                self.winner = self.leader();
             }
         }
     }
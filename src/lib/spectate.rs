@@ -0,0 +1,343 @@
+use super::board::Board;
+use super::chat::{self, Chat};
+use super::net::{Connection, Message, Room, RoomRules, Seat};
+use super::netplay::Ending;
+use super::snake::{Point, SnakeGame};
+use super::tm_logic;
+use crossterm::event::KeyCode;
+use crossterm::{cursor, execute, style::Print};
+use std::io::stdout;
+use std::net::TcpListener;
+use std::sync::mpsc::{RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+// watching games without playing in them. a Broadcast lets anyone connect to a game running
+// here and sends them the state every tick, watch() is the other end: it draws what comes
+// in, following one snake around when the board is bigger than the terminal
+
+const HELLO_WITHIN: Duration = Duration::from_secs(5); // to say whether they play or watch
+
+pub struct Broadcast {
+    listener: TcpListener,
+    pending: Vec<(Connection, Instant)>, // connected but not said what for yet
+    watchers: Vec<Watcher>,
+    watched: usize, // watchers there have been, to name the next one
+    room: Room,
+}
+
+// watchers never say who they are, in the chat they go by the order they turned up in
+// rather than whatever name they put on their messages
+struct Watcher {
+    connection: Connection,
+    name: String,
+}
+
+// someone who connected to a Broadcast wanting to play
+pub struct Player {
+    pub connection: Connection,
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Broadcast {
+    pub fn new(listener: TcpListener, name: String, rules: RoomRules, players: &[&str]) -> Self {
+        let _ = listener.set_nonblocking(true);
+        Broadcast {
+            listener,
+            pending: Vec::new(),
+            watchers: Vec::new(),
+            watched: 0,
+            room: Room {
+                id: 0,
+                name,
+                rules,
+                seats: players
+                    .iter()
+                    .map(|player| Seat {
+                        name: String::from(*player),
+                        ready: true,
+                        away: false,
+                    })
+                    .collect(),
+                playing: true,
+            },
+        }
+    }
+
+    pub fn watchers(&self) -> usize {
+        self.watchers.len()
+    }
+
+    // another player sat down, watchers see their name from now on
+    pub fn seat(&mut self, name: &str) {
+        self.room.seats.push(Seat {
+            name: String::from(name),
+            ready: true,
+            away: false,
+        });
+        let room = Message::Room {
+            room: self.room.clone(),
+        };
+        self.watchers
+            .retain_mut(|watcher| watcher.connection.send(&room).is_ok());
+    }
+
+    // takes in whoever connected since last time, watchers are kept and the first
+    // one who said hello wanting to play is handed back
+    pub fn accept(&mut self) -> Option<Player> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            // someone who stops reading shouldnt hold the game up for long
+            let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+            if let Ok(connection) = Connection::new(stream) {
+                self.pending.push((connection, Instant::now()));
+            }
+        }
+
+        let mut player = None;
+        let mut waiting = Vec::new();
+        for (mut connection, since) in self.pending.drain(..) {
+            match connection.inbox.try_recv() {
+                Ok(Message::Watch { .. }) => {
+                    let room = Message::Room {
+                        room: self.room.clone(),
+                    };
+                    if connection.send(&room).is_ok() {
+                        self.watched += 1;
+                        self.watchers.push(Watcher {
+                            connection,
+                            name: format!("watcher {}", self.watched),
+                        });
+                    }
+                }
+                Ok(Message::Hello {
+                    width,
+                    height,
+                    name,
+                    ..
+                }) if player.is_none() => {
+                    player = Some(Player {
+                        connection,
+                        name,
+                        width,
+                        height,
+                    })
+                }
+                Ok(Message::Hello { .. }) => waiting.push((connection, since)), // next time
+                Err(TryRecvError::Empty) if since.elapsed() < HELLO_WITHIN => {
+                    waiting.push((connection, since))
+                }
+                _ => {}
+            }
+        }
+        self.pending = waiting;
+        player
+    }
+
+    // one tick for everyone watching, players turning up now are too late
+    pub fn send(&mut self, game: &SnakeGame, border: bool) {
+        while let Some(mut late) = self.accept() {
+            let _ = late.connection.send(&Message::Refused {
+                reason: String::from("the game has started, you can only watch it"),
+            });
+        }
+
+        let state = Message::State {
+            board: Board::of(game, border, 0),
+        };
+        self.watchers
+            .retain_mut(|watcher| watcher.connection.send(&state).is_ok());
+    }
+
+    // something said in the game, for every watcher
+    pub fn tell(&mut self, said: &Message) {
+        self.watchers
+            .retain_mut(|watcher| watcher.connection.send(said).is_ok());
+    }
+
+    // whatever the watchers said since last time, already passed on to the other watchers
+    // under the name we know them by
+    pub fn heard(&mut self) -> Vec<Message> {
+        let mut heard = Vec::new();
+        for (i, watcher) in self.watchers.iter().enumerate() {
+            while let Ok(message) = watcher.connection.inbox.try_recv() {
+                if let Message::Chat { text, .. } = message {
+                    let said = Message::Chat {
+                        from: watcher.name.clone(),
+                        text: text.chars().take(chat::LONGEST).collect(),
+                    };
+                    heard.push((i, said));
+                }
+            }
+        }
+
+        for (from, said) in &heard {
+            for (i, watcher) in self.watchers.iter_mut().enumerate() {
+                if i != *from {
+                    let _ = watcher.connection.send(said);
+                }
+            }
+        }
+        heard.into_iter().map(|(_, said)| said).collect()
+    }
+
+    pub fn finish(&mut self, game: &SnakeGame, border: bool) {
+        self.send(game, border);
+        for watcher in &mut self.watchers {
+            let _ = watcher.connection.send(&Message::Bye);
+        }
+        self.watchers.clear();
+    }
+}
+
+// the terminal should already be set up, it is left as it is for whoever called
+// tab or a number picks which snake to follow, watchers can chat with t but have no emotes
+pub fn watch(connection: &mut Connection, width: u16, height: u16, chat: &mut Chat) -> Ending {
+    // whatever is being watched says what it is first
+    let mut room = loop {
+        match connection.inbox.recv_timeout(HELLO_WITHIN) {
+            Ok(Message::Room { room }) => break room,
+            Ok(Message::Refused { .. }) => return Ending::Over,
+            Ok(Message::Pong { .. }) => {} // the lobby pinged just before
+            Ok(_) | Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {
+                return Ending::Gone
+            }
+        }
+    };
+    let mut board: Option<Board> = None;
+    let mut following = 0;
+
+    loop {
+        let Some((keys, said)) = chat.poll() else {
+            return Ending::Quit;
+        };
+        for text in said {
+            let _ = connection.send(&Message::Chat {
+                from: chat.name.clone(),
+                text,
+            });
+        }
+        let snakes = board
+            .as_ref()
+            .map_or(room.seats.len(), |board| board.snakes.len());
+        for key in keys {
+            match key {
+                KeyCode::Tab => following = (following + 1) % snakes.max(1),
+                KeyCode::Char(c @ '1'..='9') if (c as usize - '1' as usize) < snakes => {
+                    following = c as usize - '1' as usize
+                }
+                _ => {}
+            }
+        }
+
+        let mut latest = None;
+        loop {
+            match connection.inbox.try_recv() {
+                Ok(Message::State { board }) => latest = Some(board),
+                Ok(Message::Delta { delta }) => {
+                    if let Some(next) = delta.apply(latest.as_ref().or(board.as_ref())) {
+                        latest = Some(next);
+                    }
+                }
+                Ok(Message::Event { event, .. }) => chat.heard("server", &event.describe()),
+                Ok(Message::Room { room: now }) => room = now,
+                Ok(Message::Chat { from, text }) => chat.heard(&from, &text),
+                Ok(Message::Rooms { .. }) => return Ending::Over, // the room closed
+                // a bye is the game finishing however it ended, even someone quitting
+                Ok(Message::Bye) => {
+                    if let Some(last) = latest.as_ref().or(board.as_ref()) {
+                        draw(last, &room, following, width, height);
+                    }
+                    return Ending::Over;
+                }
+                Err(TryRecvError::Disconnected) => return Ending::Gone,
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        if let Some(latest) = latest {
+            draw(&latest, &room, following, width, height);
+            board = Some(latest);
+        }
+        chat.draw();
+    }
+}
+
+// the board, moved so the followed snake's head stays on screen, with everyone's score on top
+fn draw(board: &Board, room: &Room, following: usize, width: u16, height: u16) {
+    let centre = board
+        .snakes
+        .get(following)
+        .and_then(|snake| snake.body.first())
+        .copied()
+        .unwrap_or(Point { x: 0, y: 0 });
+    let offset = |head: u16, size: u16, screen: u16| -> u16 {
+        if size <= screen {
+            0
+        } else {
+            head.saturating_sub(screen / 2).min(size - screen)
+        }
+    };
+    let left = offset(centre.x, board.width, width);
+    let top = offset(centre.y, board.height, height);
+    let on_screen = |point: &Point| -> Option<(u16, u16)> {
+        let (x, y) = (point.x.checked_sub(left)?, point.y.checked_sub(top)?);
+        (x < width && y < height).then_some((x, y))
+    };
+
+    let mut stdout = stdout();
+    tm_logic::clear_screan();
+
+    for fruit in &board.fruits {
+        if let Some((x, y)) = on_screen(fruit) {
+            let _ = execute!(stdout, cursor::MoveTo(x, y), Print("a"));
+        }
+    }
+    // heads like the game draws them, player1 a star and player2 a dollar
+    for (player, snake) in board.snakes.iter().enumerate() {
+        for (i, segment) in snake.body.iter().enumerate() {
+            let glyph = match (i, player) {
+                (0, 0) => "*",
+                (0, _) => "$",
+                _ => "o",
+            };
+            if let Some((x, y)) = on_screen(segment) {
+                let _ = execute!(stdout, cursor::MoveTo(x, y), Print(glyph));
+            }
+        }
+    }
+
+    let name = |player: usize| match room.seats.get(player) {
+        Some(seat) if seat.away => format!("{} (away)", seat.name),
+        Some(seat) => seat.name.clone(),
+        None => format!("P{}", player + 1),
+    };
+    let mut hud: Vec<String> = board
+        .snakes
+        .iter()
+        .enumerate()
+        .map(|(player, snake)| format!("{}: {}", name(player), snake.score))
+        .collect();
+    if board.snakes.len() > 1 {
+        hud.push(format!("following {}", name(following)));
+    }
+    let _ = execute!(stdout, cursor::MoveTo(0, 0), Print(hud.join("   ")));
+
+    if board.game_over {
+        let over = match board.winner {
+            3 => String::from("It's a draw"),
+            0 => String::from("Game over"),
+            winner => format!("{} won", name(winner as usize - 1)),
+        };
+        let _ = execute!(
+            stdout,
+            cursor::MoveTo(
+                (width / 2).saturating_sub(over.len() as u16 / 2),
+                height / 2
+            ),
+            Print(over)
+        );
+    }
+}
@@ -0,0 +1,540 @@
+use super::controller::KeyboardController;
+use super::replay::{Replay, SIDES};
+use super::scores::{Checked, HighScores};
+use super::snake::{Multiplayer, SnakeGame};
+use crossterm::event::KeyCode;
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, queue, style::Print};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// the game over telnet, nothing to install on the other end. every connection gets its own
+// games drawn for the size of its window, which the client tells us (NAWS) and tells us
+// again when it changes. the high scores are shared by everyone on the server and two people
+// can play each other: the first to ask waits, the next one to ask plays them
+//
+// the session's own thread runs its games. a versus game is run by the thread of whoever
+// asked second, the one waiting hands its terminal over and gets it back afterwards
+
+const SPEED: Duration = Duration::from_millis(50);
+const BORDER: bool = false;
+const SIZE_WITHIN: Duration = Duration::from_secs(1); // for the window size, or 80x24
+const SHOWN_SCORES: usize = 5;
+const LONGEST_NAME: usize = 16;
+const SMALLEST: u16 = *SIDES.start(); // window side a game can be played in
+
+// telnet commands and options, rfc 854 and friends
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+enum Input {
+    Key(KeyCode),
+    Size(u16, u16),
+}
+
+// one person's end of it, handed between threads for versus games
+struct Terminal {
+    output: TcpStream,
+    input: Receiver<Input>,
+    width: u16,
+    height: u16,
+    name: String,
+    gone: bool, // the connection dropped, nothing more will come in
+}
+
+// someone who asked for a versus game after another player was already waiting
+struct Opponent {
+    terminal: Terminal,
+    back: Sender<(Terminal, String)>, // their terminal and how it went, once it is over
+}
+
+struct Shared {
+    scores: Mutex<HighScores>,
+    file: Option<PathBuf>,
+    next_session: AtomicU64,
+    waiting: Mutex<Option<(u64, Sender<Opponent>)>>, // the session waiting for a versus game
+}
+
+pub fn serve(listener: TcpListener, file: Option<PathBuf>) -> io::Result<()> {
+    let scores = match &file {
+        Some(file) => HighScores::load(file)?,
+        None => HighScores::default(),
+    };
+    let shared = Arc::new(Shared {
+        scores: Mutex::new(scores),
+        file,
+        next_session: AtomicU64::new(0),
+        waiting: Mutex::new(None),
+    });
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || session(shared, stream));
+    }
+    Ok(())
+}
+
+fn session(shared: Arc<Shared>, stream: TcpStream) {
+    let peer = stream
+        .peer_addr()
+        .map_or(String::from("someone"), |address| address.to_string());
+    let _ = stream.set_nodelay(true);
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let (inputs, input) = mpsc::channel();
+    thread::spawn(move || read(reader, inputs));
+
+    let mut terminal = Terminal {
+        output: stream,
+        input,
+        width: 80,
+        height: 24,
+        name: String::new(),
+        gone: false,
+    };
+    // we echo and go a key at a time, and want to know the window size
+    let hello = [
+        [IAC, WILL, ECHO],
+        [IAC, WILL, SUPPRESS_GO_AHEAD],
+        [IAC, DO, SUPPRESS_GO_AHEAD],
+        [IAC, DO, NAWS],
+    ]
+    .concat();
+    let _ = terminal.output.write_all(&hello);
+    let _ = terminal.output.write_all(b"\x1b[?25l");
+    if let Ok(Input::Size(width, height)) = terminal.input.recv_timeout(SIZE_WITHIN) {
+        terminal.resize(width, height);
+    }
+    println!("{} connected, {}x{}", peer, terminal.width, terminal.height);
+
+    let id = shared.next_session.fetch_add(1, Ordering::Relaxed);
+    if let Some(name) = ask_name(&mut terminal) {
+        terminal.name = name;
+        menu(&shared, id, terminal);
+    } else {
+        let _ = terminal.output.write_all(b"\x1b[2J\x1b[H\x1b[?25h");
+    }
+    println!("{} left", peer);
+}
+
+// bytes from the client into keys and window sizes, telnet's own chatter taken out.
+// the channel closes when the connection does
+fn read(mut stream: TcpStream, inputs: Sender<Input>) {
+    enum State {
+        Data,
+        Return,       // after \r, which comes with a \n or \0 we dont want
+        Escape,       // after esc, an arrow key if [ or O follows
+        Sequence,     // inside an escape sequence
+        Command,      // after IAC
+        Negotiation,  // after IAC WILL and co, the option is next
+        Sub(Vec<u8>), // inside IAC SB .. IAC SE
+        SubIac(Vec<u8>),
+    }
+    let mut state = State::Data;
+    let mut buffer = [0; 512];
+
+    loop {
+        let read = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(read) => read,
+        };
+        for &byte in &buffer[..read] {
+            let mut key = None;
+            state = match (state, byte) {
+                (State::Command, SB) => State::Sub(Vec::new()),
+                (State::Command, WILL | WONT | DO | DONT) => State::Negotiation,
+                (State::Command | State::Negotiation, _) => State::Data,
+                (State::Sub(data), IAC) => State::SubIac(data),
+                (State::Sub(mut data), _) => {
+                    data.push(byte);
+                    State::Sub(data)
+                }
+                (State::SubIac(mut data), IAC) => {
+                    data.push(IAC);
+                    State::Sub(data)
+                }
+                (State::SubIac(data), SE) => {
+                    if let [NAWS, w1, w2, h1, h2, ..] = data[..] {
+                        let size = (u16::from_be_bytes([w1, w2]), u16::from_be_bytes([h1, h2]));
+                        if inputs.send(Input::Size(size.0, size.1)).is_err() {
+                            return;
+                        }
+                    }
+                    State::Data
+                }
+                (State::SubIac(_), _) => State::Data,
+                (State::Escape, b'[' | b'O') => State::Sequence,
+                (State::Sequence, b'0'..=b'9' | b';') => State::Sequence,
+                (State::Sequence, _) => {
+                    key = match byte {
+                        b'A' => Some(KeyCode::Up),
+                        b'B' => Some(KeyCode::Down),
+                        b'C' => Some(KeyCode::Right),
+                        b'D' => Some(KeyCode::Left),
+                        _ => None,
+                    };
+                    State::Data
+                }
+                (State::Return, b'\n' | 0) => State::Data,
+                (State::Escape | State::Return | State::Data, _) => {
+                    key = match byte {
+                        IAC => None,
+                        0x1b => None,
+                        b'\r' | b'\n' => Some(KeyCode::Enter),
+                        0x7f | 0x08 => Some(KeyCode::Backspace),
+                        0x03 => return, // ctrl-c hangs up
+                        0x20..=0x7e => Some(KeyCode::Char(byte as char)),
+                        _ => None,
+                    };
+                    match byte {
+                        IAC => State::Command,
+                        0x1b => State::Escape,
+                        b'\r' => State::Return,
+                        _ => State::Data,
+                    }
+                }
+            };
+            if let Some(key) = key {
+                if inputs.send(Input::Key(key)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Terminal {
+    fn resize(&mut self, width: u16, height: u16) {
+        // some clients say 0 for a size they dont know
+        if width > 0 && height > 0 {
+            (self.width, self.height) = (width, height);
+        }
+    }
+
+    // keys pressed since last time, waiting up to `wait` for the first. a new window size is
+    // taken in on the way and counts as an empty key press so the screen gets drawn again
+    fn keys(&mut self, wait: Duration) -> Vec<KeyCode> {
+        let mut keys = Vec::new();
+        let first = match self.input.recv_timeout(wait) {
+            Ok(input) => Some(input),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                self.gone = true;
+                None
+            }
+        };
+        let rest = std::iter::from_fn(|| match self.input.try_recv() {
+            Ok(input) => Some(input),
+            Err(TryRecvError::Disconnected) => {
+                self.gone = true;
+                None
+            }
+            Err(TryRecvError::Empty) => None,
+        });
+        let inputs: Vec<Input> = first.into_iter().chain(rest).collect();
+        for input in inputs {
+            match input {
+                Input::Key(key) => keys.push(key),
+                Input::Size(width, height) => {
+                    self.resize(width, height);
+                    keys.push(KeyCode::Null);
+                }
+            }
+        }
+        keys
+    }
+
+    fn fits(&self) -> bool {
+        self.width >= SMALLEST && self.height >= SMALLEST
+    }
+
+    fn show(&mut self, frame: &[u8]) {
+        if self.output.write_all(frame).is_err() {
+            self.gone = true;
+        }
+    }
+
+    // lines centred on an empty screen, like the welcome screen without the demo behind
+    fn lines(&mut self, lines: &[&str]) {
+        let mut frame = Vec::new();
+        let _ = queue!(frame, Clear(ClearType::All));
+        let top = (self.height / 2).saturating_sub(lines.len() as u16);
+        for (i, line) in lines.iter().enumerate() {
+            let x = (self.width / 2).saturating_sub(line.len() as u16 / 2);
+            let _ = queue!(frame, cursor::MoveTo(x, top + 2 * i as u16), Print(line));
+        }
+        self.show(&frame);
+    }
+}
+
+// None if they hung up first
+fn ask_name(terminal: &mut Terminal) -> Option<String> {
+    let mut name = String::new();
+    loop {
+        let prompt = format!("Your name: {}_", name);
+        terminal.lines(&["My Scuffed Snake", &prompt, "Press enter when done"]);
+        for key in terminal.keys(Duration::from_secs(1)) {
+            match key {
+                KeyCode::Char(c) if name.len() < LONGEST_NAME => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter if !name.trim().is_empty() => {
+                    return Some(String::from(name.trim()))
+                }
+                _ => {}
+            }
+        }
+        if terminal.gone {
+            return None;
+        }
+    }
+}
+
+fn menu(shared: &Shared, id: u64, mut terminal: Terminal) {
+    let mut notice = String::new();
+
+    while !terminal.gone {
+        let scores = shared.scores.lock().unwrap().top(None, SHOWN_SCORES);
+        let mut lines = vec![
+            format!("Hi {}", terminal.name),
+            String::from("Press 1 to play on your own"),
+            String::from("Press 2 to play against someone else here"),
+            String::from("Press q to quit"),
+        ];
+        if !notice.is_empty() {
+            lines.push(notice.clone());
+        }
+        if !scores.is_empty() {
+            lines.push(String::from("High scores"));
+        }
+        for high in &scores {
+            lines.push(format!("{} {}  {}", high.name, high.score, high.mode));
+        }
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        terminal.lines(&lines);
+
+        for key in terminal.keys(Duration::from_secs(1)) {
+            match key {
+                KeyCode::Char('1' | '2') if !terminal.fits() => notice = too_small(),
+                KeyCode::Char('1') => notice = solo(shared, &mut terminal),
+                KeyCode::Char('2') => match versus(shared, id, terminal) {
+                    Some((back, result)) => (terminal, notice) = (back, result),
+                    None => return,
+                },
+                KeyCode::Char('q' | 'Q') => {
+                    terminal.lines(&["Sad to see you go"]);
+                    let _ = terminal.output.write_all(b"\x1b[2J\x1b[H\x1b[?25h");
+                    return;
+                }
+                _ => continue,
+            }
+            break; // whatever else was pressed belonged to the game
+        }
+    }
+}
+
+fn too_small() -> String {
+    format!(
+        "Your window is too small, a game needs at least {}x{}",
+        SMALLEST, SMALLEST
+    )
+}
+
+// one game alone on a board the size of the window, the result for the menu
+fn solo(shared: &Shared, terminal: &mut Terminal) -> String {
+    let mut game = SnakeGame::new(terminal.width, terminal.height);
+    let mut replay = Replay::start(&game, BORDER, SPEED.as_millis() as u64, &[&terminal.name]);
+    let keyboard = KeyboardController::arrows();
+
+    while !game.game_over {
+        let frame_start = Instant::now();
+        let keys = terminal.keys(Duration::ZERO);
+        if terminal.gone || keys.contains(&KeyCode::Char('q')) {
+            return String::new();
+        }
+
+        game.steer(0, keyboard.turn(game.direction_of(0), &keys));
+        replay.record(&game);
+        game.tick(BORDER);
+        terminal.show(&draw(&game, &[&terminal.name], 0));
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < SPEED {
+            thread::sleep(SPEED - elapsed);
+        }
+    }
+
+    replay.finish(&game);
+    thread::sleep(Duration::from_secs(1));
+    let score = game.score(0);
+    match submit(shared, replay) {
+        true => format!("You scored {}, a high score!", score),
+        false => format!("You scored {}", score),
+    }
+}
+
+// waits for someone else to ask for a game, or plays whoever is waiting already. hands the
+// terminal back with the result, None if it was lost on the way
+fn versus(shared: &Shared, id: u64, mut terminal: Terminal) -> Option<(Terminal, String)> {
+    let waiting = shared.waiting.lock().unwrap().take();
+    if let Some((_, host)) = waiting {
+        let (back, returned) = mpsc::channel();
+        return match host.send(Opponent { terminal, back }) {
+            Ok(()) => returned.recv().ok().filter(|(terminal, _)| !terminal.gone),
+            // they gave up waiting just now, this one waits instead
+            Err(mpsc::SendError(opponent)) => versus(shared, id, opponent.terminal),
+        };
+    }
+
+    let (host, opponents) = mpsc::channel();
+    *shared.waiting.lock().unwrap() = Some((id, host));
+    let opponent = loop {
+        terminal.lines(&[
+            "Waiting for someone to play against",
+            "Press q to stop waiting",
+        ]);
+        let keys = terminal.keys(Duration::from_millis(500));
+        if let Ok(opponent) = opponents.try_recv() {
+            break Some(opponent);
+        }
+        if terminal.gone || keys.contains(&KeyCode::Char('q')) {
+            let mut waiting = shared.waiting.lock().unwrap();
+            if waiting.as_ref().is_some_and(|(waiting, _)| *waiting == id) {
+                *waiting = None;
+                break None;
+            }
+            // someone took us up on it just as we gave up, play them anyway
+        }
+    };
+
+    let Some(mut opponent) = opponent else {
+        return (!terminal.gone).then_some((terminal, String::new()));
+    };
+    let [mine, theirs] = play_versus(shared, [&mut terminal, &mut opponent.terminal]);
+    let _ = opponent.back.send((opponent.terminal, theirs));
+    (!terminal.gone).then_some((terminal, mine))
+}
+
+// a two player game on a board that fits both windows, the result for each of them
+fn play_versus(shared: &Shared, mut terminals: [&mut Terminal; 2]) -> [String; 2] {
+    let width = terminals[0].width.min(terminals[1].width);
+    let height = terminals[0].height.min(terminals[1].height);
+    // whoever was waiting could have made their window smaller meanwhile
+    if !terminals.iter().all(|terminal| terminal.fits()) {
+        return [0, 1].map(|player| match terminals[player].fits() {
+            true => String::from("Their window is too small to play in"),
+            false => too_small(),
+        });
+    }
+    let mut game = SnakeGame::multiplayer_new(width, height);
+    let names = [terminals[0].name.clone(), terminals[1].name.clone()];
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut replay = Replay::start(&game, BORDER, SPEED.as_millis() as u64, &names);
+    let keyboard = KeyboardController::arrows();
+
+    while !game.game_over {
+        let frame_start = Instant::now();
+
+        for (player, terminal) in terminals.iter_mut().enumerate() {
+            let keys = terminal.keys(Duration::ZERO);
+            // leaving gives the game to the other one
+            if terminal.gone || keys.contains(&KeyCode::Char('q')) {
+                game.game_over = true;
+                game.winner = 2 - player as u16;
+            }
+            game.steer(player, keyboard.turn(game.direction_of(player), &keys));
+        }
+        if game.game_over {
+            break;
+        }
+        replay.record(&game);
+        game.tick(BORDER);
+        for (player, terminal) in terminals.iter_mut().enumerate() {
+            terminal.show(&draw(&game, &names, player));
+        }
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < SPEED {
+            thread::sleep(SPEED - elapsed);
+        }
+    }
+
+    thread::sleep(Duration::from_secs(1));
+    replay.finish(&game);
+    submit(shared, replay);
+    [0, 1].map(|player| match game.winner {
+        3 => String::from("It was a draw"),
+        winner if winner as usize == player + 1 => String::from("You won!"),
+        _ => String::from("You lost"),
+    })
+}
+
+// into the shared high scores if it checks out and was good enough, saved if so. it is
+// checked before anyone else is kept from the scores
+fn submit(shared: &Shared, replay: Replay) -> bool {
+    let Ok(replay) = Checked::new(replay) else {
+        return false;
+    };
+    let mut scores = shared.scores.lock().unwrap();
+    let made_it = scores.submit(replay);
+    if let (false, Some(file)) = (made_it.is_empty(), &shared.file) {
+        if let Err(e) = scores.save(file) {
+            eprintln!(
+                "Could not save the high scores to {} because: {}",
+                file.display(),
+                e
+            );
+        }
+    }
+    !made_it.is_empty()
+}
+
+// the game the way SnakeGame::render draws it, for a window on the other end of a connection
+fn draw(game: &SnakeGame, names: &[&str], you: usize) -> Vec<u8> {
+    let mut frame = Vec::new();
+    let _ = queue!(frame, Clear(ClearType::All));
+
+    for fruit in game.fruits() {
+        let _ = queue!(frame, cursor::MoveTo(fruit.x, fruit.y), Print("a"));
+    }
+    for player in 0..game.players() {
+        for (i, segment) in game.body(player).iter().enumerate() {
+            let glyph = match (i, player) {
+                (0, 0) => "*",
+                (0, _) => "$",
+                _ => "o",
+            };
+            let _ = queue!(frame, cursor::MoveTo(segment.x, segment.y), Print(glyph));
+        }
+    }
+
+    let mut hud: Vec<String> = names
+        .iter()
+        .enumerate()
+        .map(|(player, name)| format!("{}: {}", name, game.score(player)))
+        .collect();
+    if names.len() > 1 {
+        hud.push(format!("you are {}", if you == 0 { "*" } else { "$" }));
+    }
+    let _ = queue!(frame, cursor::MoveTo(0, 0), Print(hud.join("   ")));
+    frame
+}
@@ -1,4 +1,8 @@
-use super::snake;
+use super::ai::GreedyBot;
+use super::controller::Controller;
+use super::discovery::Browser;
+use super::leaderboard;
+use super::snake::{Multiplayer, SnakeGame};
 use crossterm::event::{self, Event, KeyCode};
 use crossterm::{
     cursor, execute,
@@ -8,11 +12,22 @@ use crossterm::{
 };
 
 use std::io::stdout;
+use std::net::SocketAddr;
 
 pub struct GameMode {
     pub players: u8,
     pub speed_ms: u64,
     pub borders: bool,
+    pub autopilot: bool,          // single player driven by the hamiltonian bot
+    pub trained: bool,            // player2 is the genome saved by snake train
+    pub host: bool,               // wait for someone on the network to be player2
+    pub join: Option<SocketAddr>, // a game on the network picked to join
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameMode {
@@ -21,77 +36,139 @@ impl GameMode {
             players: 1,
             speed_ms: 50,
             borders: false,
+            autopilot: false,
+            trained: false,
+            host: false,
+            join: None,
         }
     }
 
-    pub fn welcome_screen(&mut self, width: u16, height: u16) {
-        let mut stdout = stdout();
+    // `trained` is whether there is a saved genome to offer as an opponent
+    // `leaderboard` is the snake-leaderboard whose scores it can show
+    pub fn welcome_screen(&mut self, width: u16, height: u16, trained: bool, leaderboard: &str) {
+        let mut demo = Demo::new(width, height);
 
         let h1 = "Press 1 for single player";
         let h2 = "Press 2 for multiplayer";
+        let h3 = "Press 3 to watch the perfect game";
+        let h4 = "Press 4 to play against the trained snake";
+        let hh = "Press h to host a network game";
+        let hj = "Press j to join a network game";
+        let hl = "Press l for the online leaderboard";
+
+        let choice = loop {
+            let choice = if trained {
+                demo.menu(
+                    &[h1, h2, h3, h4, hh, hj, hl],
+                    &['1', '2', '3', '4', 'h', 'j', 'l'],
+                )
+            } else {
+                demo.menu(&[h1, h2, h3, hh, hj, hl], &['1', '2', '3', 'h', 'j', 'l'])
+            };
+            if choice != Some('l') {
+                break choice;
+            }
+            leaderboard_screen(&mut demo, leaderboard);
+        };
+        match choice {
+            Some('2') => self.players = 2,
+            Some('3') => self.autopilot = true,
+            Some('4') => {
+                self.players = 2;
+                self.trained = true;
+            }
+            Some('h') => {
+                self.players = 2;
+                self.host = true;
+            }
+            Some('j') => {
+                // the host picks the rules
+                self.players = 2;
+                self.join = self.join_screen(&mut demo);
+                if self.join.is_none() {
+                    self.players = 0;
+                }
+                return;
+            }
+            Some(_) => self.players = 1,
+            None => {
+                self.players = 0;
+                return;
+            }
+        }
 
-        let _ = execute!(
-            stdout,
-            Clear(ClearType::All),
-            cursor::MoveTo((width / 2) - (h1.len() as u16 / 2), (height / 2) - 1),
-            Print(h1),
-            cursor::MoveTo((width / 2) - (h2.len() as u16 / 2), (height / 2) + 1),
-            Print(h2),
-        );
+        self.settings(&mut demo);
+    }
+
+    // hosting a network game is always two players, only the rules get picked
+    pub fn host_screen(&mut self, width: u16, height: u16) {
+        self.players = 2;
+        self.settings(&mut Demo::new(width, height));
+    }
+
+    // lists the games being announced on the network until one is picked, None on q
+    fn join_screen(&mut self, demo: &mut Demo) -> Option<SocketAddr> {
+        let browser = match Browser::start() {
+            Ok(browser) => browser,
+            Err(e) => {
+                let error = format!("Could not look for games because: {}", e);
+                demo.menu(&[&error, "Press q to quit"], &[]);
+                return None;
+            }
+        };
 
         loop {
-            if event::poll(std::time::Duration::from_millis(100)).unwrap() {
-                if let Event::Key(key_event) = event::read().unwrap() {
-                    match key_event.code {
-                        KeyCode::Char('1') => {
-                            self.players = 1;
-                            break;
-                        }
-                        KeyCode::Char('2') => {
-                            self.players = 2;
-                            break;
-                        }
-                        KeyCode::Char('q') | KeyCode::Char('Q') => {
-                            self.players = 0;
-                            return;
-                        }
-                        _ => {}
+            let games = browser.games();
+            let mut lines = vec![String::from("Games on your network")];
+            if games.is_empty() {
+                lines.push(String::from("Looking..."));
+            }
+            for (i, (_, game)) in games.iter().take(9).enumerate() {
+                lines.push(format!("Press {} for {}", i + 1, game.describe()));
+            }
+            lines.push(String::from("Press q to quit"));
+
+            let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+            match demo.poll(&lines) {
+                Some('q' | 'Q') => return None,
+                Some(c @ '1'..='9') => {
+                    let picked = c as usize - '1' as usize;
+                    if let Some((address, _)) = games.get(picked) {
+                        return Some(*address);
                     }
                 }
+                _ => {}
             }
         }
+    }
 
+    // the rules for a new room on snake-server
+    pub fn room_screen(&mut self, demo: &mut Demo) {
+        let one = "Press 1 for a room for one";
+        let two = "Press 2 for a room for two";
+
+        match demo.menu(&[one, two], &['1', '2']) {
+            Some('1') => self.players = 1,
+            Some(_) => self.players = 2,
+            None => {
+                self.players = 0;
+                return;
+            }
+        }
+
+        self.settings(demo);
+    }
+
+    fn settings(&mut self, demo: &mut Demo) {
         let noborders = "Press 1 for no borders";
         let borders = "Press 2 for borders";
 
-        let _ = execute!(
-            stdout,
-            Clear(ClearType::All),
-            cursor::MoveTo((width / 2) - (noborders.len() as u16 / 2), (height / 2) - 1),
-            Print(noborders),
-            cursor::MoveTo((width / 2) - (borders.len() as u16 / 2), (height / 2) + 1),
-            Print(borders),
-        );
-
-        loop {
-            if event::poll(std::time::Duration::from_millis(100)).unwrap() {
-                if let Event::Key(key_event) = event::read().unwrap() {
-                    match key_event.code {
-                        KeyCode::Char('1') => {
-                            self.borders = false;
-                            break;
-                        }
-                        KeyCode::Char('2') => {
-                            self.borders = true;
-                            break;
-                        }
-                        KeyCode::Char('q' | 'Q') => {
-                            self.players = 0;
-                            return;
-                        }
-                        _ => {}
-                    }
-                }
+        match demo.menu(&[noborders, borders], &['1', '2']) {
+            Some('2') => self.borders = true,
+            Some(_) => self.borders = false,
+            None => {
+                self.players = 0;
+                return;
             }
         }
 
@@ -99,39 +176,151 @@ impl GameMode {
         let med = "Press 2 for medium";
         let fast = "Press 3 for fast";
 
-        let _ = execute!(
-            stdout,
-            Clear(ClearType::All),
-            cursor::MoveTo((width / 2) - (slow.len() as u16 / 2), (height / 2) - 1),
-            Print(slow),
-            cursor::MoveTo((width / 2) - (med.len() as u16 / 2), (height / 2) + 1),
-            Print(med),
-            cursor::MoveTo((width / 2) - (fast.len() as u16 / 2), (height / 2) + 3),
-            Print(fast),
-        );
+        match demo.menu(&[slow, med, fast], &['1', '2', '3']) {
+            Some('1') => self.speed_ms = 100,
+            Some('3') => self.speed_ms = 30,
+            Some(_) => self.speed_ms = 50,
+            None => self.players = 0,
+        }
+    }
+}
+
+// the best scores on a snake-leaderboard, picking one plays back the game it was scored in
+fn leaderboard_screen(demo: &mut Demo, url: &str) {
+    let scores = match leaderboard::top(url, None, 9) {
+        Ok(scores) => scores,
+        Err(e) => {
+            let error = format!("Could not reach the leaderboard because: {}", e);
+            demo.menu(&[&error, "Press q to go back"], &[]);
+            return;
+        }
+    };
 
+    let mut lines = vec![String::from("Online leaderboard")];
+    if scores.is_empty() {
+        lines.push(String::from("No scores yet"));
+    }
+    for (i, high) in scores.iter().enumerate() {
+        lines.push(format!(
+            "Press {} to watch {} {}  {}",
+            i + 1,
+            high.name,
+            high.score,
+            high.mode
+        ));
+    }
+    lines.push(String::from("Press q to go back"));
+    let keys: Vec<char> = ('1'..='9').take(scores.len()).collect();
+
+    let mut notice = String::new();
+    loop {
+        let mut shown: Vec<&str> = lines.iter().map(String::as_str).collect();
+        if !notice.is_empty() {
+            shown.push(&notice);
+        }
+        let Some(picked) = demo.menu(&shown, &keys) else {
+            return;
+        };
+        let high = &scores[picked as usize - '1' as usize];
+        notice = match leaderboard::replay(url, high.id) {
+            Ok(replay) if replay.width > demo.width || replay.height > demo.height => {
+                String::from("That game was played on a bigger terminal than this one")
+            }
+            Ok(replay) => {
+                replay.show();
+                String::new()
+            }
+            Err(e) => format!("Could not fetch that game because: {}", e),
+        };
+    }
+}
+
+// attract mode, two bots play a real game behind the menu text
+// a fresh game is started whenever the current one ends
+pub struct Demo {
+    game: SnakeGame,
+    bots: [GreedyBot; 2],
+    width: u16,
+    height: u16,
+}
+
+impl Demo {
+    pub fn new(width: u16, height: u16) -> Self {
+        Demo {
+            game: SnakeGame::multiplayer_new(width, height),
+            bots: [GreedyBot::new(false), GreedyBot::new(false)],
+            width,
+            height,
+        }
+    }
+
+    // one tick of the demo without drawing it
+    pub fn step(&mut self) {
+        if self.game.game_over {
+            self.game = SnakeGame::multiplayer_new(self.width, self.height);
+        }
+
+        for (player, bot) in self.bots.iter_mut().enumerate() {
+            let direction = bot.next_direction(&self.game, player, &[]);
+            self.game.steer(player, direction);
+        }
+
+        self.game.tick(false);
+    }
+
+    pub fn game(&self) -> &SnakeGame {
+        &self.game
+    }
+
+    // one frame of the demo with the lines centred on top
+    fn show(&mut self, lines: &[&str]) {
+        let mut stdout = stdout();
+
+        self.step();
+        self.game.render();
+
+        // long lists start higher up so they still fit
+        let top = (self.height / 2)
+            .saturating_sub(1)
+            .min(self.height.saturating_sub(2 * lines.len() as u16));
+        for (i, line) in lines.iter().enumerate() {
+            let _ = execute!(
+                stdout,
+                cursor::MoveTo(
+                    (self.width / 2).saturating_sub(line.len() as u16 / 2),
+                    top + 2 * i as u16
+                ),
+                Print(line),
+            );
+        }
+    }
+
+    // one frame with the lines on top and up to 100ms for a key, for screens that change
+    // while they are up. whatever was pressed is handed back, q included
+    pub fn poll(&mut self, lines: &[&str]) -> Option<char> {
+        self.show(lines);
+
+        if event::poll(std::time::Duration::from_millis(100)).unwrap() {
+            if let Event::Key(key_event) = event::read().unwrap() {
+                if let KeyCode::Char(c) = key_event.code {
+                    return Some(c);
+                }
+            }
+        }
+        None
+    }
+
+    // keeps the demo running with the lines centred on top until one of the keys is pressed
+    // returns None if the player pressed q
+    fn menu(&mut self, lines: &[&str], keys: &[char]) -> Option<char> {
         loop {
+            self.show(lines);
+
             if event::poll(std::time::Duration::from_millis(100)).unwrap() {
                 if let Event::Key(key_event) = event::read().unwrap() {
                     match key_event.code {
-                        KeyCode::Char('1') => {
-                            self.speed_ms = 100;
-                            break;
-                        }
-                        KeyCode::Char('2') => {
-                            self.speed_ms = 50;
-                            break;
-                        }
-
-                        KeyCode::Char('3') => {
-                            self.speed_ms = 30;
-                            break;
-                        }
-
-                        KeyCode::Char('q' | 'Q') => {
-                            self.players = 0;
-                            return;
-                        }
+                        KeyCode::Char('q' | 'Q') => return None,
+                        KeyCode::Char(c) if keys.contains(&c) => return Some(c),
                         _ => {}
                     }
                 }
@@ -140,6 +329,32 @@ impl GameMode {
     }
 }
 
+// shows the demo and the lines until `ready` comes back with something, for waiting on
+// the network without freezing the screen. None if the player gave up with q
+pub fn waiting_screen<T>(
+    width: u16,
+    height: u16,
+    lines: &[&str],
+    mut ready: impl FnMut() -> Option<T>,
+) -> Option<T> {
+    let mut demo = Demo::new(width, height);
+
+    loop {
+        if let Some(done) = ready() {
+            return Some(done);
+        }
+        demo.show(lines);
+
+        if event::poll(std::time::Duration::from_millis(100)).unwrap() {
+            if let Event::Key(key_event) = event::read().unwrap() {
+                if let KeyCode::Char('q' | 'Q') = key_event.code {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
 // hides cursros and sets temrinal to Raw for better user handling
 pub fn init_terminal() -> (u16, u16) {
     match terminal::enable_raw_mode() {
@@ -193,7 +408,11 @@ pub fn cleanup_terminal(
     let mut stdout = stdout();
 
     if players == 1 {
-        let h1 = "thank you for playing!";
+        let h1 = if winner == 1 {
+            "the whole board is snake, you win!"
+        } else {
+            "thank you for playing!"
+        };
         let h2 = format!("Your score: {}!", score1);
 
         let _ = execute!(
@@ -246,71 +465,31 @@ pub fn cleanup_terminal(
     let _ = terminal::disable_raw_mode();
 }
 
-// non blocking event func that converts arrows to Direction Enum
+// non blocking event func that gathers every key pressed this tick
 // must be none blocking otherwise snake does not move on its own
-// change the from_millis to dictate the speed
-pub fn handle_input(game: &mut snake::SnakeGame) -> bool {
-    if event::poll(std::time::Duration::from_millis(50)).unwrap() {
-        if let Event::Key(key_event) = event::read().unwrap() {
-            match key_event.code {
-                KeyCode::Up if game.direction != snake::Direction::Down => {
-                    game.direction = snake::Direction::Up;
-                }
-                KeyCode::Down if game.direction != snake::Direction::Up => {
-                    game.direction = snake::Direction::Down;
-                }
-                KeyCode::Left if game.direction != snake::Direction::Right => {
-                    game.direction = snake::Direction::Left;
-                }
-                KeyCode::Right if game.direction != snake::Direction::Left => {
-                    game.direction = snake::Direction::Right;
-                }
-                KeyCode::Char('q' | 'Q') => {
-                    println!("Exiting...");
-                    return false;
-                }
-                _ => {}
-            }
-        }
+// waits up to 50ms for the first key so the pace feels the same as before
+// returns None once the player presses q
+pub fn poll_keys() -> Option<Vec<KeyCode>> {
+    let keys = read_keys();
+    if keys.contains(&KeyCode::Char('q')) || keys.contains(&KeyCode::Char('Q')) {
+        println!("Exiting...");
+        return None;
     }
-    true
+
+    Some(keys)
 }
 
-pub fn multiplayer_handle_input(game: &mut snake::SnakeGame) -> bool {
-    if event::poll(std::time::Duration::from_millis(50)).unwrap() {
+// everything pressed since last time, q included, waiting up to 50ms for the first key
+pub fn read_keys() -> Vec<KeyCode> {
+    let mut keys = Vec::new();
+    let mut wait = std::time::Duration::from_millis(50);
+
+    while event::poll(wait).unwrap() {
         if let Event::Key(key_event) = event::read().unwrap() {
-            match key_event.code {
-                KeyCode::Up if game.direction != snake::Direction::Down => {
-                    game.direction = snake::Direction::Up;
-                }
-                KeyCode::Down if game.direction != snake::Direction::Up => {
-                    game.direction = snake::Direction::Down;
-                }
-                KeyCode::Left if game.direction != snake::Direction::Right => {
-                    game.direction = snake::Direction::Left;
-                }
-                KeyCode::Right if game.direction != snake::Direction::Left => {
-                    game.direction = snake::Direction::Right;
-                }
-                KeyCode::Char('w') if game.direction2 != snake::Direction::Down => {
-                    game.direction2 = snake::Direction::Up;
-                }
-                KeyCode::Char('s') if game.direction2 != snake::Direction::Up => {
-                    game.direction2 = snake::Direction::Down;
-                }
-                KeyCode::Char('a') if game.direction2 != snake::Direction::Right => {
-                    game.direction2 = snake::Direction::Left;
-                }
-                KeyCode::Char('d') if game.direction2 != snake::Direction::Left => {
-                    game.direction2 = snake::Direction::Right;
-                }
-                KeyCode::Char('q' | 'Q') => {
-                    println!("Exiting...");
-                    return false;
-                }
-                _ => {}
-            }
+            keys.push(key_event.code);
         }
+        wait = std::time::Duration::ZERO;
     }
-    true
+
+    keys
 }
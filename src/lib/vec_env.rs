@@ -0,0 +1,348 @@
+use super::env::{Encoding, EnvConfig, Rewards};
+use super::snake::{Direction, Point};
+
+// many single player boards stepped together for training throughput
+// same rules as SnakeGame::update (two segments per fruit, fruit inside the edges first,
+// the tail moves out of the way before the head arrives, a full board or with borders a full
+// inside wins) but every board lives in flat arrays instead of a VecDeque per snake, and a
+// board that finishes is reset on the spot so every step returns a fresh observation for all
+// of them
+//
+// cells are numbered y * width + x, each body is a ring buffer of cells with the head at
+// `head` and the tail `length - 1` slots after it
+pub struct VecSnakeEnv {
+    config: EnvConfig, // checked and fixed at new, the arrays are sized for it
+    boards: usize,
+    cells: usize,
+    body: Vec<u32>, // boards * cells ring buffers
+    taken: Vec<u8>, // boards * cells, 1 where a segment is
+    head: Vec<u32>, // slot of the head in each ring
+    length: Vec<u32>,
+    growing: Vec<u32>,
+    heading: Vec<Direction>,
+    fruit: Vec<u32>,
+    score: Vec<u32>,
+    ticks: Vec<u64>,
+    rng: Vec<u64>,
+    pub rewards: Vec<f32>,
+    pub dones: Vec<bool>,
+    pub finished_scores: Vec<u32>, // score of the last episode each board finished
+}
+
+impl VecSnakeEnv {
+    // a local window is left to SnakeEnv, asking for one here is an error
+    pub fn new(config: EnvConfig, boards: usize, seed: u64) -> Result<Self, String> {
+        config.check()?;
+        if let Encoding::Window(_) = config.encoding {
+            return Err(String::from(
+                "VecSnakeEnv only does Grid and Features, use SnakeEnv for a window",
+            ));
+        }
+        let cells = config.width as usize * config.height as usize;
+        let mut env = VecSnakeEnv {
+            config,
+            boards,
+            cells,
+            body: vec![0; boards * cells],
+            taken: vec![0; boards * cells],
+            head: vec![0; boards],
+            length: vec![0; boards],
+            growing: vec![0; boards],
+            heading: vec![Direction::Left; boards],
+            fruit: vec![0; boards],
+            score: vec![0; boards],
+            ticks: vec![0; boards],
+            rng: (0..boards as u64)
+                .map(|board| seed ^ board.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+                .collect(),
+            rewards: vec![0.0; boards],
+            dones: vec![false; boards],
+            finished_scores: vec![0; boards],
+        };
+
+        for board in 0..boards {
+            env.reset(board);
+        }
+        Ok(env)
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.boards
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boards == 0
+    }
+
+    pub fn score(&self, board: usize) -> u32 {
+        self.score[board]
+    }
+
+    pub fn length(&self, board: usize) -> u32 {
+        self.length[board]
+    }
+
+    pub fn head(&self, board: usize) -> Point {
+        self.point(self.body[board * self.cells + self.head[board] as usize])
+    }
+
+    pub fn fruit(&self, board: usize) -> Point {
+        self.point(self.fruit[board])
+    }
+
+    fn point(&self, cell: u32) -> Point {
+        let width = self.config.width as u32;
+        Point {
+            x: (cell % width) as u16,
+            y: (cell / width) as u16,
+        }
+    }
+
+    // one action per board, u-turns are ignored like SnakeGame::steer does
+    // fills in rewards and dones, boards that are done have already been reset
+    pub fn step(&mut self, actions: &[Direction]) {
+        assert_eq!(actions.len(), self.boards, "one action per board");
+        for (board, action) in actions.iter().enumerate() {
+            if *action != self.heading[board].opposite() {
+                self.heading[board] = *action;
+            }
+            let (reward, done) = self.step_board(board);
+            self.rewards[board] = reward;
+            self.dones[board] = done;
+            if done {
+                self.finished_scores[board] = self.score[board];
+                self.reset(board);
+            }
+        }
+    }
+
+    fn step_board(&mut self, board: usize) -> (f32, bool) {
+        let Rewards {
+            fruit,
+            death,
+            step,
+            win,
+        } = self.config.rewards;
+        let (width, height) = (self.config.width as u32, self.config.height as u32);
+        let base = board * self.cells;
+        let cells = self.cells as u32;
+        self.ticks[board] += 1;
+
+        let head = self.body[base + self.head[board] as usize];
+        let (x, y) = (head % width, head / width);
+        let border = self.config.border;
+        let next = match self.heading[board] {
+            Direction::Up if border && y == 0 => None,
+            Direction::Down if border && y == height - 1 => None,
+            Direction::Left if border && x == 0 => None,
+            Direction::Right if border && x == width - 1 => None,
+            Direction::Up => Some((y + height - 1) % height * width + x),
+            Direction::Down => Some((y + 1) % height * width + x),
+            Direction::Left => Some(y * width + (x + width - 1) % width),
+            Direction::Right => Some(y * width + (x + 1) % width),
+        };
+        let Some(next) = next else {
+            return (step + death, true);
+        };
+
+        // tail first, so following your own tail is allowed
+        if self.growing[board] == 0 {
+            let tail = (self.head[board] + self.length[board] - 1) % cells;
+            self.taken[base + self.body[base + tail as usize] as usize] = 0;
+            self.length[board] -= 1;
+        } else {
+            self.growing[board] -= 1;
+        }
+
+        if self.taken[base + next as usize] == 1 {
+            return (step + death, true);
+        }
+        self.head[board] = (self.head[board] + cells - 1) % cells;
+        self.body[base + self.head[board] as usize] = next;
+        self.taken[base + next as usize] = 1;
+        self.length[board] += 1;
+
+        let playable = if border {
+            width.saturating_sub(2) * height.saturating_sub(2)
+        } else {
+            cells
+        };
+        if self.length[board] >= playable {
+            return (step + win, true);
+        }
+
+        let mut reward = step;
+        if next == self.fruit[board] {
+            self.score[board] += 1;
+            self.growing[board] += 2;
+            reward += fruit;
+            match self.free_cell(board) {
+                Some(cell) => self.fruit[board] = cell,
+                None => return (reward + win, true),
+            }
+        }
+
+        let truncated = self.ticks[board] >= self.config.max_ticks;
+        (reward, truncated)
+    }
+
+    fn reset(&mut self, board: usize) {
+        let (width, height) = (self.config.width as u32, self.config.height as u32);
+        let base = board * self.cells;
+        self.taken[base..base + self.cells].fill(0);
+
+        let (x, y) = (width / 2, height / 2);
+        for (slot, cell) in (0..3).map(|i| y * width + x + i).enumerate() {
+            self.body[base + slot] = cell;
+            self.taken[base + cell as usize] = 1;
+        }
+        self.head[board] = 0;
+        self.length[board] = 3;
+        self.growing[board] = 0;
+        self.heading[board] = Direction::Left;
+        self.score[board] = 0;
+        self.ticks[board] = 0;
+        self.fruit[board] = self.free_cell(board).unwrap_or(0);
+    }
+
+    // uniform over the free cells inside the edges, or anywhere once those are gone unless
+    // the edges are the wall
+    // rejection sampling is quick while the board is mostly empty, then it scans
+    fn free_cell(&mut self, board: usize) -> Option<u32> {
+        let (width, height) = (self.config.width as u32, self.config.height as u32);
+        let base = board * self.cells;
+        let inside = |cell: u32| {
+            let (x, y) = (cell % width, cell / width);
+            (1..width - 1).contains(&x) && (1..height - 1).contains(&y)
+        };
+
+        if width > 2 && height > 2 {
+            for _ in 0..16 {
+                let x = 1 + self.random(board, width - 2);
+                let y = 1 + self.random(board, height - 2);
+                let cell = y * width + x;
+                if self.taken[base + cell as usize] == 0 {
+                    return Some(cell);
+                }
+            }
+        }
+
+        let free = |cell: &u32| self.taken[base + *cell as usize] == 0;
+        let mut pool: Vec<u32> = (0..self.cells as u32)
+            .filter(|cell| inside(*cell))
+            .filter(free)
+            .collect();
+        if pool.is_empty() && !self.config.border {
+            pool = (0..self.cells as u32).filter(free).collect();
+        }
+        if pool.is_empty() {
+            return None;
+        }
+
+        let pick = self.random(board, pool.len() as u32);
+        Some(pool[pick as usize])
+    }
+
+    // splitmix64, a u64 of state per board is all it takes
+    fn random(&mut self, board: usize, below: u32) -> u32 {
+        self.rng[board] = self.rng[board].wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng[board];
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (((z >> 32) * below as u64) >> 32) as u32
+    }
+
+    // observation of every board back to back, in the same layout SnakeEnv uses
+    pub fn observe(&self, out: &mut Vec<f32>) {
+        out.clear();
+        for board in 0..self.boards {
+            match self.config.encoding {
+                Encoding::Features => self.features(board, out),
+                Encoding::Grid => self.grid(board, out),
+                Encoding::Window(_) => unreachable!("turned down in new"),
+            }
+        }
+    }
+
+    pub fn observation_size(&self) -> usize {
+        match self.config.encoding {
+            Encoding::Features => 15,
+            Encoding::Window(_) => unreachable!("turned down in new"),
+            Encoding::Grid => {
+                5 * (self.config.width as usize + 2) * (self.config.height as usize + 2)
+            }
+        }
+    }
+
+    fn grid(&self, board: usize, out: &mut Vec<f32>) {
+        let (width, height) = (self.config.width as usize, self.config.height as usize);
+        let (padded, plane) = (width + 2, (width + 2) * (height + 2));
+        let start = out.len();
+        out.resize(start + 5 * plane, 0.0);
+        let base = board * self.cells;
+        let at = |cell: u32| (cell as usize / width + 1) * padded + cell as usize % width + 1;
+
+        for slot in 0..self.length[board] {
+            let cell = self.body[base + ((self.head[board] + slot) % self.cells as u32) as usize];
+            let channel = if slot == 0 { 0 } else { 1 };
+            out[start + channel * plane + at(cell)] = 1.0;
+        }
+        out[start + 3 * plane + at(self.fruit[board])] = 1.0;
+
+        if self.config.border {
+            for y in 0..height + 2 {
+                for x in 0..padded {
+                    if x == 0 || y == 0 || x == padded - 1 || y == height + 1 {
+                        out[start + 4 * plane + y * padded + x] = 1.0;
+                    }
+                }
+            }
+        }
+    }
+
+    fn features(&self, board: usize, out: &mut Vec<f32>) {
+        let (width, height) = (self.config.width as u32, self.config.height as u32);
+        let base = board * self.cells;
+        let head = self.body[base + self.head[board] as usize];
+        let (x, y) = (head % width, head / width);
+        let border = self.config.border;
+        let flag = |yes: bool| if yes { 1.0 } else { 0.0 };
+
+        for direction in Direction::ALL {
+            let next = match direction {
+                Direction::Up if border && y == 0 => None,
+                Direction::Down if border && y == height - 1 => None,
+                Direction::Left if border && x == 0 => None,
+                Direction::Right if border && x == width - 1 => None,
+                Direction::Up => Some((y + height - 1) % height * width + x),
+                Direction::Down => Some((y + 1) % height * width + x),
+                Direction::Left => Some(y * width + (x + width - 1) % width),
+                Direction::Right => Some(y * width + (x + 1) % width),
+            };
+            out.push(next.map_or(1.0, |cell| self.taken[base + cell as usize] as f32));
+        }
+        for direction in Direction::ALL {
+            out.push(flag(direction == self.heading[board]));
+        }
+
+        let fruit = self.fruit[board];
+        let dx = (fruit % width) as f32 - x as f32;
+        let dy = (fruit / width) as f32 - y as f32;
+        out.extend([
+            flag(dy < 0.0),
+            flag(dy > 0.0),
+            flag(dx < 0.0),
+            flag(dx > 0.0),
+        ]);
+        out.extend([
+            dx / width as f32,
+            dy / height as f32,
+            self.length[board] as f32 / self.cells as f32,
+        ]);
+    }
+}
@@ -0,0 +1,38 @@
+use super::http::{self, Response};
+use super::net::Connection;
+use super::websocket;
+use std::net::TcpStream;
+
+// browsers on snake-server's own port. a plain GET gets the web client, which is a page and
+// a script built into the binary, and the page connects back with a websocket that speaks
+// the same messages as the terminal game does over tcp
+
+const PAGE: &str = include_str!("../../web/index.html");
+const SCRIPT: &str = include_str!("../../web/snake.js");
+
+// the terminal game starts with a json object, browsers with a request line
+pub fn is_http(stream: &TcpStream) -> bool {
+    let mut start = [0; 4];
+    matches!(stream.peek(&mut start), Ok(4) if &start == b"GET ")
+}
+
+// a websocket turns into a Connection, anything else is answered and closed
+pub fn accept(mut stream: TcpStream) -> Option<Connection> {
+    let request = http::read_request(&mut stream).ok()?;
+
+    let upgrade = request.header("upgrade");
+    if upgrade.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket")) {
+        let key = request.header("sec-websocket-key")?;
+        websocket::handshake(&mut stream, key).ok()?;
+        stream.set_read_timeout(None).ok()?;
+        return Connection::websocket(stream).ok();
+    }
+
+    let response = match request.path.as_str() {
+        "/" | "/index.html" => Response::page("text/html; charset=utf-8", String::from(PAGE)),
+        "/snake.js" => Response::page("text/javascript", String::from(SCRIPT)),
+        _ => Response::not_found(),
+    };
+    let _ = http::write_response(&mut stream, &response);
+    None
+}
@@ -0,0 +1,153 @@
+use super::wire::base64;
+use std::io::{self, Read, Write};
+
+// just enough websocket (rfc 6455) for browsers to talk json to snake-server: the handshake
+// key, and text messages in frames either way. no extensions, no compression, and pings are
+// ignored since browsers dont send them
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"; // fixed by the rfc
+const LONGEST: u64 = 32 << 20; // one message, a replay fits easily
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+
+// what goes in Sec-WebSocket-Accept for the client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), GUID).as_bytes()))
+}
+
+pub fn handshake(stream: &mut impl Write, key: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    stream.flush()
+}
+
+// the next whole message, put back together if it came in pieces. None once the other
+// end closes
+pub fn read_message(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut message = Vec::new();
+    let mut started = false; // a text or binary frame that wasnt the last has come in
+    loop {
+        let mut head = [0; 2];
+        reader.read_exact(&mut head)?;
+        let last = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0f;
+        let masked = head[1] & 0x80 != 0;
+        let length = match head[1] & 0x7f {
+            126 => {
+                let mut length = [0; 2];
+                reader.read_exact(&mut length)?;
+                u16::from_be_bytes(length) as u64
+            }
+            127 => {
+                let mut length = [0; 8];
+                reader.read_exact(&mut length)?;
+                u64::from_be_bytes(length)
+            }
+            length => length as u64,
+        };
+        if (message.len() as u64).saturating_add(length) > LONGEST {
+            return Err(invalid("message too long"));
+        }
+        let mut mask = [0; 4];
+        if masked {
+            reader.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0; length as usize];
+        reader.read_exact(&mut payload)?;
+        if masked {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+
+        match opcode {
+            CLOSE => return Ok(None),
+            CONTINUATION if !started => return Err(invalid("continuation of nothing")),
+            TEXT | BINARY if started => return Err(invalid("new message in the middle of one")),
+            TEXT | BINARY | CONTINUATION => {
+                started = true;
+                message.extend_from_slice(&payload);
+                if last {
+                    return Ok(Some(message));
+                }
+            }
+            _ => {} // ping and pong
+        }
+    }
+}
+
+// one text frame, servers dont mask
+pub fn write_text(stream: &mut impl Write, text: &str) -> io::Result<()> {
+    let mut frame = vec![0x80 | TEXT];
+    match text.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(text.as_bytes());
+    stream.write_all(&frame)
+}
+
+fn invalid(why: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, why)
+}
+
+// the handshake needs sha-1, and only for this, so here it is rather than a crate
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, next);
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
@@ -0,0 +1,303 @@
+use super::board::{Board, SnakeView};
+use super::net::{Message, DELTAS};
+use super::snake::{Direction, Point};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// the smaller way of sending a game tick by tick, for clients that said hello with version
+// DELTAS or later. a delta is only what changed since a board the client already has: the
+// cells each snake's head moved into, how many fell off its tail and the fruits if they moved.
+// one with no `since` builds on nothing and is the whole board, those go out first and every
+// KEYFRAME ticks after
+//
+// bodies go as bytes in base64 rather than a list of points. the head comes first as two
+// little endian u16s, then a byte for each run of steps towards the tail, the direction in
+// the top two bits and how many in a row in the rest. anything that isnt one step, through a
+// wrapping edge or onto a segment that hasnt moved off yet, is a zero byte and the point in full
+
+const KEYFRAME: u64 = 100;
+const LONGEST_RUN: u8 = 0x3f;
+const JUMP: u8 = 0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Delta {
+    pub tick: u64,
+    pub since: Option<u64>, // the tick of the board this goes on top of
+    pub width: u16,
+    pub height: u16,
+    pub wrap: bool,
+    pub you: usize,
+    pub snakes: Vec<SnakeDelta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fruits: Option<Vec<Point>>, // only when they changed
+    pub game_over: bool,
+    pub winner: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnakeDelta {
+    pub grew: Body, // new cells at the head end, head first
+    pub shed: u16,  // cells gone from the tail
+    pub direction: Direction,
+    pub score: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body(pub Vec<Point>);
+
+impl Delta {
+    // what takes `before` to `after`, or all of `after` without a board to build on
+    pub fn between(before: Option<&Board>, after: &Board) -> Self {
+        let before = before.filter(|before| {
+            before.width == after.width
+                && before.height == after.height
+                && before.snakes.len() == after.snakes.len()
+        });
+
+        let snakes = after.snakes.iter().enumerate().map(|(i, snake)| {
+            let old = before.map_or(&[][..], |before| &before.snakes[i].body[..]);
+            // the fewest new cells that leave the rest of the body where the old one was
+            let new = (0..=snake.body.len())
+                .find(|new| old.starts_with(&snake.body[*new..]))
+                .unwrap_or(snake.body.len());
+            SnakeDelta {
+                grew: Body(snake.body[..new].to_vec()),
+                shed: (old.len() + new - snake.body.len()) as u16,
+                direction: snake.direction,
+                score: snake.score,
+            }
+        });
+
+        let fruits_moved = before.is_none_or(|before| before.fruits != after.fruits);
+        Delta {
+            tick: after.tick,
+            since: before.map(|before| before.tick),
+            width: after.width,
+            height: after.height,
+            wrap: after.wrap,
+            you: after.you,
+            snakes: snakes.collect(),
+            fruits: fruits_moved.then(|| after.fruits.clone()),
+            game_over: after.game_over,
+            winner: after.winner,
+        }
+    }
+
+    // the board it makes, None if it builds on a board other than `onto`. the next
+    // whole one puts that right
+    pub fn apply(&self, onto: Option<&Board>) -> Option<Board> {
+        let base =
+            match self.since {
+                None => None,
+                Some(since) => Some(onto.filter(|board| {
+                    board.tick == since && board.snakes.len() == self.snakes.len()
+                })?),
+            };
+
+        let mut snakes = Vec::new();
+        for (i, delta) in self.snakes.iter().enumerate() {
+            let old = base.map_or(&[][..], |base| &base.snakes[i].body[..]);
+            let kept = old.len().checked_sub(delta.shed as usize)?;
+            let mut body = delta.grew.0.clone();
+            body.extend_from_slice(&old[..kept]);
+            snakes.push(SnakeView {
+                body,
+                direction: delta.direction,
+                score: delta.score,
+            });
+        }
+        let fruits = match (&self.fruits, base) {
+            (Some(fruits), _) => fruits.clone(),
+            (None, Some(base)) => base.fruits.clone(),
+            (None, None) => return None,
+        };
+
+        Some(Board {
+            tick: self.tick,
+            width: self.width,
+            height: self.height,
+            wrap: self.wrap,
+            you: self.you,
+            snakes,
+            fruits,
+            game_over: self.game_over,
+            winner: self.winner,
+        })
+    }
+}
+
+// what one client was sent last, so the next board can go as a delta on top of it. clients
+// from before DELTAS get the whole board every time
+pub struct Updates {
+    version: u32,
+    last: Option<Board>,
+}
+
+impl Updates {
+    pub fn new(version: u32) -> Self {
+        Updates {
+            version,
+            last: None,
+        }
+    }
+
+    pub fn next(&mut self, board: Board) -> Message {
+        if self.version < DELTAS {
+            return Message::State { board };
+        }
+        let since = self
+            .last
+            .as_ref()
+            .filter(|_| !board.tick.is_multiple_of(KEYFRAME));
+        let delta = Delta::between(since, &board);
+        self.last = Some(board);
+        Message::Delta { delta }
+    }
+
+    // they are about to see some other game, the next one goes whole
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+pub fn pack(body: &[Point]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let Some(head) = body.first() else {
+        return bytes;
+    };
+    put_point(&mut bytes, *head);
+
+    let mut run: Option<(u8, u8)> = None; // direction and how many
+    for pair in body.windows(2) {
+        match (step(pair[0], pair[1]), &mut run) {
+            (Some(way), Some((along, count))) if way == *along && *count < LONGEST_RUN => {
+                *count += 1
+            }
+            (Some(way), _) => {
+                put_run(&mut bytes, run);
+                run = Some((way, 1));
+            }
+            (None, _) => {
+                put_run(&mut bytes, run.take());
+                bytes.push(JUMP);
+                put_point(&mut bytes, pair[1]);
+            }
+        }
+    }
+    put_run(&mut bytes, run);
+    bytes
+}
+
+// None if it isnt a body pack made, or walks off the edge of a u16
+pub fn unpack(bytes: &[u8]) -> Option<Vec<Point>> {
+    let mut body = Vec::new();
+    if bytes.is_empty() {
+        return Some(body);
+    }
+    let (mut at, mut rest) = take_point(bytes)?;
+    body.push(at);
+
+    while let Some((&byte, after)) = rest.split_first() {
+        rest = after;
+        let count = byte & LONGEST_RUN;
+        if count == JUMP {
+            (at, rest) = take_point(rest)?;
+            body.push(at);
+            continue;
+        }
+        let way = WAYS[(byte >> 6) as usize];
+        for _ in 0..count {
+            at = Point {
+                x: at.x.checked_add_signed(way.0)?,
+                y: at.y.checked_add_signed(way.1)?,
+            };
+            body.push(at);
+        }
+    }
+    Some(body)
+}
+
+// up, down, left, right as in Direction, the order their two bits count in
+const WAYS: [(i16, i16); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+fn step(from: Point, to: Point) -> Option<u8> {
+    let way = (to.x as i32 - from.x as i32, to.y as i32 - from.y as i32);
+    WAYS.iter()
+        .position(|(x, y)| (*x as i32, *y as i32) == way)
+        .map(|way| way as u8)
+}
+
+fn put_run(bytes: &mut Vec<u8>, run: Option<(u8, u8)>) {
+    if let Some((way, count)) = run {
+        bytes.push(way << 6 | count);
+    }
+}
+
+fn put_point(bytes: &mut Vec<u8>, point: Point) {
+    bytes.extend_from_slice(&point.x.to_le_bytes());
+    bytes.extend_from_slice(&point.y.to_le_bytes());
+}
+
+fn take_point(bytes: &[u8]) -> Option<(Point, &[u8])> {
+    let (point, rest) = bytes.split_first_chunk::<4>()?;
+    let point = Point {
+        x: u16::from_le_bytes([point[0], point[1]]),
+        y: u16::from_le_bytes([point[2], point[3]]),
+    };
+    Some((point, rest))
+}
+
+impl Serialize for Body {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64(&pack(&self.0)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let bytes = unbase64(&text).ok_or_else(|| D::Error::custom("a body is base64"))?;
+        let body = unpack(&bytes).ok_or_else(|| D::Error::custom("not a packed body"))?;
+        Ok(Body(body))
+    }
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64(data: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+pub fn unbase64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut decoded = Vec::new();
+    for chunk in text.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|a| a == c)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+        let bytes = bits.to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(decoded)
+}
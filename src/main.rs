@@ -1,50 +1,393 @@
-mod lib;
-use lib::{
+use snake::{
+    args::{Args, TrainArgs, TRAIN_USAGE, USAGE},
+    battlesnake,
+    chat::Chat,
+    control::{Control, Settings},
+    controller::{Controller, KeyboardController},
+    discovery,
+    hamilton::HamiltonBot,
+    leaderboard, lobby,
+    net::{Connection, Message, RoomRules},
+    netplay::{self, Ending},
+    netsim::Conditions,
+    neural::{Genome, NeuralBot},
+    replay::Replay,
     snake::Multiplayer,
-    tm_logic::{self, handle_input, multiplayer_handle_input},
+    spectate::{self, Broadcast},
+    tm_logic::{self, poll_keys},
     {snake::SnakeGame, tm_logic::GameMode},
 };
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("train") {
+        train();
+        return;
+    }
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) if args.help => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(address) = args.battlesnake {
+        serve_battlesnake(address, &args);
+        return;
+    }
+
+    if let Some(port) = args.host {
+        let listener = listen(port);
+        let (width, height) = tm_logic::init_terminal();
+        let mut game_mode = GameMode::new();
+        game_mode.host_screen(width, height);
+        if game_mode.players == 0 {
+            tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+            return;
+        }
+        netplay::host(listener, &net_options(&args), &game_mode, width, height);
+        return;
+    }
+
+    if let Some(address) = &args.join {
+        let stream = connect(address);
+        let (width, height) = tm_logic::init_terminal();
+        netplay::join(stream, &net_options(&args), width, height);
+        return;
+    }
+
+    if let Some(address) = &args.server {
+        let connection = Connection::new(connect(address)).and_then(|c| c.simulated(&args.netsim));
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Could not use the connection to {} because: {}", address, e);
+                std::process::exit(1);
+            }
+        };
+        let name = net_options(&args).name;
+        let (width, height) = tm_logic::init_terminal();
+        lobby::play(connection, address, &name, &args.netsim, width, height);
+        return;
+    }
+
+    if let Some(address) = &args.watch {
+        watch(address, &net_options(&args).name, &args.netsim);
+        return;
+    }
+
+    // bound first so a taken port is reported before the terminal is grabbed
+    let broadcast = args.broadcast.map(listen);
+    let control = args.control.as_ref().map(|path| {
+        Control::listen(std::path::Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("Could not make a control socket at {} because: {}", path, e);
+            std::process::exit(1);
+        })
+    });
+
     let (width, height) = tm_logic::init_terminal();
     let mut game_mode = GameMode::new();
-    game_mode.welcome_screen(width, height);
-    // for consistent refresh rate
-    let frame_duration = std::time::Duration::from_millis(game_mode.speed_ms);
+    let trained = std::path::Path::new(&args.genome).exists();
+    game_mode.welcome_screen(width, height, trained, &args.leaderboard);
 
-    if game_mode.players == 1 {
-        let mut game = SnakeGame::new(width, height);
-        while handle_input(&mut game) && !game.game_over {
-            let frame_start = std::time::Instant::now();
+    // network games picked on the welcome screen
+    if game_mode.players != 0 && game_mode.host {
+        match std::net::TcpListener::bind(("0.0.0.0", netplay::DEFAULT_PORT)) {
+            Ok(listener) => netplay::host(listener, &net_options(&args), &game_mode, width, height),
+            Err(e) => {
+                tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                eprintln!(
+                    "Could not listen on port {} because: {}",
+                    netplay::DEFAULT_PORT,
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(address) = game_mode.join {
+        let timeout = std::time::Duration::from_secs(5);
+        match std::net::TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => netplay::join(stream, &net_options(&args), width, height),
+            Err(e) => {
+                tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+                eprintln!("Could not connect to {} because: {}", address, e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if game_mode.players == 0 {
+        tm_logic::cleanup_terminal(0, 0, width, height, game_mode.players, 0);
+        return;
+    }
+
+    let mut controllers = match controllers(&game_mode, &args) {
+        Ok(controllers) => controllers,
+        Err(e) => {
+            tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+            eprintln!("Could not start bot because: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut game = if game_mode.players == 2 {
+        SnakeGame::multiplayer_new(width, height)
+    } else {
+        SnakeGame::new(width, height)
+    };
+    let mut broadcast = broadcast.map(|listener| {
+        let name = net_options(&args).name;
+        let rules = RoomRules {
+            players: game_mode.players,
+            border: game_mode.borders,
+            speed_ms: game_mode.speed_ms,
+        };
+        let players = ["player 1", "player 2"];
+        Broadcast::new(
+            listener,
+            format!("{}'s game", name),
+            rules,
+            &players[..game_mode.players as usize],
+        )
+    });
+
+    // single player games someone really played go on the leaderboard, with a replay to prove it
+    let name = net_options(&args).name;
+    let played = game_mode.players == 1 && !game_mode.autopilot && args.bots[0].is_none();
+    let mut replay =
+        played.then(|| Replay::start(&game, game_mode.borders, game_mode.speed_ms, &[&name]));
+
+    // the frame time, for a consistent refresh rate, and pausing are up to the control socket
+    let mut settings = Settings::new(game_mode.speed_ms, game_mode.borders);
+
+    while !game.game_over {
+        let Some(keys) = poll_keys() else {
+            break;
+        };
+        let frame_start = std::time::Instant::now();
+        if let Some(control) = &control {
+            control.answer(&mut game, &mut settings);
+        }
+
+        tm_logic::clear_screan();
+        if settings.paused {
+            // still drawn, a fruit dropped meanwhile shows up
+            game.render();
+        } else {
+            for (player, controller) in controllers.iter_mut().enumerate() {
+                let direction = controller.next_direction(&game, player, &keys);
+                game.steer(player, direction);
+            }
+            if let Some(replay) = &mut replay {
+                replay.record(&game);
+            }
 
-            tm_logic::clear_screan();
+            game.tick(game_mode.borders);
+            game.render();
+            if let Some(broadcast) = &mut broadcast {
+                broadcast.send(&game, game_mode.borders);
+            }
+        }
 
-            game.update(game_mode.borders);
-            game.draw();
+        let elapsed = frame_start.elapsed();
+        if elapsed < settings.speed {
+            std::thread::sleep(settings.speed - elapsed);
+        }
+    }
+    drop(control);
+    if let Some(broadcast) = &mut broadcast {
+        broadcast.finish(&game, game_mode.borders);
+    }
+    // a game the control socket changed wouldnt replay the same, so it isnt sent
+    let replay = replay.filter(|_| game.game_over && !settings.meddled);
+    let replay = replay.map(|mut replay| {
+        replay.finish(&game);
+        replay
+    });
+    game.cleanup(game_mode.players);
 
-            let elapsed = frame_start.elapsed();
-            if elapsed < frame_duration {
-                std::thread::sleep(frame_duration - elapsed);
+    // after the terminal is back, a leaderboard that isnt running is not worth a word
+    if let Some(replay) = replay.filter(|replay| replay.scores[0] > 0) {
+        match leaderboard::submit(&args.leaderboard, &replay) {
+            Ok(made_it) if made_it.is_empty() => {
+                println!("{} didn't make the online leaderboard", replay.scores[0])
             }
+            Ok(_) => println!("{} is on the online leaderboard!", replay.scores[0]),
+            Err(_) => {}
+        }
+    }
+}
+
+// read only, follows a game someone else is playing
+fn watch(address: &str, name: &str, network: &Conditions) {
+    let connection = Connection::new(connect(address)).and_then(|c| c.simulated(network));
+    let mut connection = match connection {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Could not use the connection to {} because: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    let _ = connection.send(&Message::Watch { room: None });
+    let (width, height) = tm_logic::init_terminal();
+
+    let ending = spectate::watch(&mut connection, width, height, &mut Chat::watching(name));
+    if ending == Ending::Over {
+        // long enough to see who won
+        std::thread::sleep(std::time::Duration::from_secs(2));
+    }
+    let _ = connection.send(&Message::Bye);
+    tm_logic::cleanup_terminal(0, 0, width, height, 0, 0);
+    if ending == Ending::Gone {
+        eprintln!("the game being watched went away");
+    }
+}
+
+// one controller per snake, player1 is on the arrows and player2 on wasd
+// unless a bot was asked for on the command line
+fn controllers(game_mode: &GameMode, args: &Args) -> std::io::Result<Vec<Box<dyn Controller>>> {
+    let mut controllers: Vec<Box<dyn Controller>> = Vec::new();
+
+    for player in 0..game_mode.players as usize {
+        let controller: Box<dyn Controller> = match &args.bots[player] {
+            Some(bot) => bot.build(game_mode.borders, args.move_timeout)?,
+            None if game_mode.trained && player == 1 => Box::new(NeuralBot::new(
+                Genome::load(&args.genome)?,
+                game_mode.borders,
+            )),
+            None if game_mode.autopilot => Box::new(HamiltonBot::new(game_mode.borders)),
+            None if player == 0 => Box::new(KeyboardController::arrows()),
+            None => Box::new(KeyboardController::wasd()),
+        };
+        controllers.push(controller);
+    }
+
+    Ok(controllers)
+}
+
+// before the terminal is grabbed so the error can be read
+fn listen(port: u16) -> std::net::TcpListener {
+    match std::net::TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on port {} because: {}", port, e);
+            std::process::exit(1);
         }
-        game.cleanup(game_mode.players);
     }
+}
+
+fn connect(address: &str) -> std::net::TcpStream {
+    match std::net::TcpStream::connect(address) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Could not connect to {} because: {}", address, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn net_options(args: &Args) -> netplay::Options {
+    let rollback = args.rollback > 0;
+    netplay::Options {
+        name: args.name.clone().unwrap_or_else(discovery::host_name),
+        lockstep: args.lockstep,
+        input_delay: args.input_delay.unwrap_or(if rollback { 1 } else { 3 }),
+        rollback: args.rollback,
+        network: args.netsim,
+    }
+}
 
-    if game_mode.players == 2 {
-        let mut game = SnakeGame::multiplayer_new(width, height);
-        while multiplayer_handle_input(&mut game) && !game.game_over {
-            let frame_start = std::time::Instant::now();
+// no terminal game at all, just answer battlesnake requests with one of our bots
+fn serve_battlesnake(address: std::net::SocketAddr, args: &Args) {
+    let listener = match std::net::TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on {} because: {}", address, e);
+            std::process::exit(1);
+        }
+    };
 
-            tm_logic::clear_screan();
+    let ai = args.ai.clone();
+    let timeout = args.move_timeout;
+    println!(
+        "serving the {} bot as a battlesnake on {}",
+        ai.name(),
+        address
+    );
+    battlesnake::serve(listener, &ai.name(), move |border| {
+        ai.build(border, timeout)
+            .expect("built in bots always start")
+    });
+}
 
-            game.multiplayer_update(game_mode.borders);
-            game.multiplayer_draw();
+// snake train, evolves neural network bots headless and saves the best one
+fn train() {
+    let args = match TrainArgs::parse(std::env::args().skip(2)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", TRAIN_USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, TRAIN_USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let training = &args.training;
+    println!(
+        "training {} genomes for {} generations on {}x{} {}{}",
+        training.population,
+        training.generations,
+        training.rules.width,
+        training.rules.height,
+        if training.rules.border {
+            "with borders"
+        } else {
+            "wrapping"
+        },
+        training
+            .opponent
+            .as_ref()
+            .map_or(String::new(), |bot| format!(" against {}", bot.name()))
+    );
 
-            let elapsed = frame_start.elapsed();
-            if elapsed < frame_duration {
-                std::thread::sleep(frame_duration - elapsed);
+    // saved whenever a generation beats the best so far, so stopping early loses nothing
+    let mut best = f32::MIN;
+    let result = training.run(|genome, mean| {
+        let saved = genome.fitness > best;
+        if saved {
+            best = genome.fitness;
+            if let Err(e) = genome.save(&args.out) {
+                eprintln!("Could not save {} because: {}", args.out, e);
+                std::process::exit(1);
             }
         }
-        game.cleanup(game_mode.players);
+        println!(
+            "generation {:>4}  best {:>8.1}  mean {:>8.1}{}",
+            genome.generation,
+            genome.fitness,
+            mean,
+            if saved { "  saved" } else { "" }
+        );
+    });
+
+    match result {
+        Ok(genome) => println!(
+            "best genome from generation {} saved to {}",
+            genome.generation, args.out
+        ),
+        Err(e) => {
+            eprintln!("a training game could not be played: {}", e);
+            std::process::exit(1);
+        }
     }
 }
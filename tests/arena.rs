@@ -0,0 +1,78 @@
+use snake::arena::{self, elo, Rules, Tournament};
+use snake::bots::Bot;
+use std::time::Duration;
+
+fn rules() -> Rules {
+    Rules {
+        width: 14,
+        height: 12,
+        border: true,
+        max_ticks: 400,
+    }
+}
+
+#[test]
+fn elo_moves_by_how_surprising_it_was() {
+    assert_eq!(elo(1500.0, 1500.0, 1.0), 1508.0);
+    assert_eq!(elo(1500.0, 1500.0, 0.0), 1492.0);
+    assert_eq!(elo(1500.0, 1500.0, 0.5), 1500.0);
+
+    // beating someone much better is worth more than beating someone much worse
+    let upset = elo(1400.0, 1600.0, 1.0) - 1400.0;
+    let expected = elo(1600.0, 1400.0, 1.0) - 1600.0;
+    assert!(upset > expected && expected > 0.0);
+    assert!(upset < 16.0);
+
+    // whatever one side gains the other loses
+    for (a, b, score) in [
+        (1500.0, 1700.0, 1.0),
+        (1623.0, 1411.0, 0.5),
+        (1400.0, 1450.0, 0.0),
+    ] {
+        let gained = elo(a, b, score) - a;
+        let lost = elo(b, a, 1.0 - score) - b;
+        assert!((gained + lost).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn a_seed_plays_the_same_match() {
+    let (greedy, hamilton) = (Bot::Greedy, Bot::Hamilton);
+    let timeout = Duration::from_millis(100);
+    let one = arena::play([&greedy, &hamilton], &rules(), 5, timeout).unwrap();
+    let other = arena::play([&greedy, &hamilton], &rules(), 5, timeout).unwrap();
+    assert_eq!(one.winner, other.winner);
+    assert_eq!(one.scores, other.scores);
+    assert_eq!(one.ticks, other.ticks);
+    assert!(one.ticks <= 400);
+}
+
+#[test]
+fn the_table_is_the_same_however_many_threads() {
+    let table = |threads| {
+        let tournament = Tournament {
+            bots: vec![Bot::Greedy, Bot::Hamilton],
+            rules: rules(),
+            games_per_pair: 6,
+            seed: 1,
+            threads,
+            timeout: Duration::from_millis(100),
+        };
+        tournament.run(|_, _| {}).unwrap()
+    };
+    let alone = table(1);
+    let together = table(4);
+
+    for (a, b) in alone.iter().zip(&together) {
+        assert_eq!(a.name, b.name);
+        assert_eq!((a.wins, a.losses, a.draws), (b.wins, b.losses, b.draws));
+        assert_eq!(a.elo, b.elo);
+    }
+    for standing in &alone {
+        assert_eq!(standing.games, 6);
+        assert_eq!(standing.wins + standing.losses + standing.draws, 6);
+    }
+    assert_eq!(alone[0].wins, alone[1].losses);
+    assert!((alone[0].elo + alone[1].elo - 3000.0).abs() < 1e-9);
+    assert!(alone[0].elo >= alone[1].elo, "best first");
+}
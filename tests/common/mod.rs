@@ -0,0 +1,21 @@
+use snake::ai::GreedyBot;
+use snake::controller::Controller;
+use snake::replay::Replay;
+use snake::snake::SnakeGame;
+
+// a single player game for the tests to check, played by the greedy bot since it eats a few
+// fruits and then runs into itself
+pub fn played(width: u16, height: u16, border: bool, seed: u64) -> Replay {
+    let mut game = SnakeGame::new_seeded(width, height, seed);
+    let mut replay = Replay::start(&game, border, 50, &["greedy"]);
+    let mut bot = GreedyBot::new(border);
+    while !game.game_over {
+        assert!(game.ticks() < 100_000, "the greedy bot never ended");
+        let direction = bot.next_direction(&game, 0, &[]);
+        game.steer(0, direction);
+        replay.record(&game);
+        game.tick(border);
+    }
+    replay.finish(&game);
+    replay
+}
@@ -0,0 +1,163 @@
+#![cfg(unix)]
+
+use snake::control::{Control, Settings};
+use snake::snake::SnakeGame;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn socket(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("snake-control-{}-{}", std::process::id(), name))
+}
+
+// asks each of `requests` in turn while the game answers, what came back as json
+fn ask(
+    control: &Control,
+    path: &Path,
+    game: &mut SnakeGame,
+    settings: &mut Settings,
+    requests: &[&str],
+) -> Vec<serde_json::Value> {
+    let requests: Vec<String> = requests.iter().map(|r| r.to_string()).collect();
+    let path = path.to_path_buf();
+    let client = std::thread::spawn(move || {
+        let mut stream = UnixStream::connect(path).unwrap();
+        let mut answers = BufReader::new(stream.try_clone().unwrap()).lines();
+        let mut answered = Vec::new();
+        for request in requests {
+            writeln!(stream, "{}", request).unwrap();
+            let answer = answers.next().unwrap().unwrap();
+            answered.push(serde_json::from_str(&answer).unwrap());
+        }
+        answered
+    });
+
+    let start = Instant::now();
+    while !client.is_finished() {
+        assert!(start.elapsed() < Duration::from_secs(5), "no answer");
+        control.answer(game, settings);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    client.join().unwrap()
+}
+
+#[test]
+fn status_pause_and_speed() {
+    let path = socket("status");
+    let control = Control::listen(&path).unwrap();
+    let mut game = SnakeGame::new_seeded(20, 10, 1);
+    game.tick(false);
+    let mut settings = Settings::new(50, false);
+
+    let answers = ask(
+        &control,
+        &path,
+        &mut game,
+        &mut settings,
+        &[
+            r#"{"type":"status"}"#,
+            r#"{"type":"pause"}"#,
+            r#"{"type":"speed","speed_ms":30}"#,
+            r#"{"type":"speed","speed_ms":0}"#,
+            r#"{"type":"status"}"#,
+            r#"{"type":"jump"}"#,
+        ],
+    );
+    assert_eq!(answers[0]["tick"], 1);
+    assert_eq!(answers[0]["paused"], false);
+    assert_eq!(answers[0]["speed_ms"], 50);
+    assert_eq!(
+        answers[0]["players"],
+        serde_json::json!([{"score": 0, "length": 3, "alive": true}])
+    );
+    assert_eq!(answers[1]["type"], "ok");
+    assert_eq!(answers[2]["type"], "ok");
+    assert_eq!(answers[3]["type"], "error");
+    assert_eq!(answers[4]["paused"], true);
+    assert_eq!(answers[4]["speed_ms"], 30);
+    assert_eq!(answers[5]["type"], "error");
+
+    assert!(settings.paused && settings.meddled);
+    assert_eq!(settings.speed, Duration::from_millis(30));
+}
+
+#[test]
+fn fruit_goes_where_it_is_put() {
+    let path = socket("fruit");
+    let control = Control::listen(&path).unwrap();
+    let mut game = SnakeGame::new_seeded(20, 10, 2);
+    let mut settings = Settings::new(50, true);
+    let head = game.body(0)[0];
+
+    let answers = ask(
+        &control,
+        &path,
+        &mut game,
+        &mut settings,
+        &[
+            r#"{"type":"spawn_fruit","x":3,"y":4}"#,
+            &format!(r#"{{"type":"spawn_fruit","x":{},"y":{}}}"#, head.x, head.y),
+            r#"{"type":"spawn_fruit","x":20,"y":4}"#,
+            r#"{"type":"spawn_fruit","x":3}"#,
+            r#"{"type":"spawn_fruit"}"#,
+            r#"{"type":"spawn_fruit","x":0,"y":4}"#,
+            r#"{"type":"spawn_fruit","x":3,"y":9}"#,
+        ],
+    );
+    assert_eq!(
+        answers[0],
+        serde_json::json!({"type": "ok", "x": 3, "y": 4})
+    );
+    assert_eq!(answers[1]["type"], "error");
+    assert_eq!(answers[2]["type"], "error");
+    assert_eq!(answers[3]["type"], "error");
+    assert_eq!(answers[4]["type"], "ok");
+    assert_eq!(answers[5]["type"], "error", "in the wall");
+    assert_eq!(answers[6]["type"], "error", "in the wall");
+
+    let x = answers[4]["x"].as_u64().unwrap() as u16;
+    let y = answers[4]["y"].as_u64().unwrap() as u16;
+    assert_eq!(game.fruits(), [snake::snake::Point { x, y }]);
+    assert!(!game.body(0).contains(&snake::snake::Point { x, y }));
+    assert!(
+        (1..19).contains(&x) && (1..9).contains(&y),
+        "inside the walls"
+    );
+}
+
+#[test]
+fn without_borders_the_edges_are_fine() {
+    let path = socket("edges");
+    let control = Control::listen(&path).unwrap();
+    let mut game = SnakeGame::new_seeded(20, 10, 3);
+    let mut settings = Settings::new(50, false);
+
+    let answers = ask(
+        &control,
+        &path,
+        &mut game,
+        &mut settings,
+        &[
+            r#"{"type":"spawn_fruit","x":0,"y":0}"#,
+            r#"{"type":"spawn_fruit","x":19,"y":9}"#,
+        ],
+    );
+    assert_eq!(answers[0]["type"], "ok");
+    assert_eq!(answers[1]["type"], "ok");
+}
+
+#[test]
+fn the_socket_goes_with_the_game() {
+    let path = socket("gone");
+    let control = Control::listen(&path).unwrap();
+    assert!(path.exists());
+    assert!(Control::listen(&path).is_err(), "it is still answering");
+    drop(control);
+    assert!(!path.exists());
+
+    // one left behind by a game that crashed is taken over
+    std::fs::write(&path, "").unwrap();
+    let control = Control::listen(&path).unwrap();
+    drop(control);
+}
@@ -0,0 +1,84 @@
+use crossterm::event::KeyCode;
+use snake::ai::GreedyBot;
+use snake::controller::{
+    Controller, KeyboardController, RecordingController, RemoteController, ScriptedController,
+};
+use snake::snake::{Direction, SnakeGame};
+use std::sync::mpsc;
+
+// the game with `controller` driving, every head along the way
+fn drive(controller: &mut impl Controller, seed: u64, ticks: usize) -> Vec<snake::snake::Point> {
+    let mut game = SnakeGame::new_seeded(20, 10, seed);
+    let mut heads = Vec::new();
+    for _ in 0..ticks {
+        if game.game_over {
+            break;
+        }
+        let direction = controller.next_direction(&game, 0, &[]);
+        game.steer(0, direction);
+        game.tick(false);
+        heads.push(game.body(0)[0]);
+    }
+    heads
+}
+
+#[test]
+fn each_cluster_only_hears_its_own_keys() {
+    let arrows = KeyboardController::arrows();
+    let wasd = KeyboardController::wasd();
+    let keys = [KeyCode::Up, KeyCode::Char('s')];
+
+    assert_eq!(arrows.turn(Direction::Left, &keys), Direction::Up);
+    assert_eq!(wasd.turn(Direction::Left, &keys), Direction::Down);
+    assert_eq!(arrows.turn(Direction::Left, &[]), Direction::Left);
+}
+
+#[test]
+fn the_last_key_that_isnt_a_u_turn_wins() {
+    let arrows = KeyboardController::arrows();
+    let keys = [KeyCode::Up, KeyCode::Right];
+    assert_eq!(arrows.turn(Direction::Left, &keys), Direction::Up);
+    let keys = [KeyCode::Up, KeyCode::Down];
+    assert_eq!(arrows.turn(Direction::Left, &keys), Direction::Down);
+}
+
+#[test]
+fn scripts_go_round_and_round() {
+    let game = SnakeGame::new_seeded(20, 10, 1);
+    let mut script = ScriptedController::new(vec![Direction::Up, Direction::Left]);
+    let moves: Vec<Direction> = (0..5)
+        .map(|_| script.next_direction(&game, 0, &[]))
+        .collect();
+    use Direction::*;
+    assert_eq!(moves, [Up, Left, Up, Left, Up]);
+
+    let mut empty = ScriptedController::new(Vec::new());
+    assert_eq!(empty.next_direction(&game, 0, &[]), game.direction_of(0));
+}
+
+#[test]
+fn a_recording_plays_the_game_again() {
+    let mut recording = RecordingController::new(GreedyBot::new(false));
+    let played = drive(&mut recording, 3, 300);
+    assert_eq!(recording.moves().len(), played.len());
+
+    let mut replay = recording.into_replay();
+    assert_eq!(drive(&mut replay, 3, played.len()), played);
+}
+
+#[test]
+fn remote_moves_take_the_latest() {
+    let game = SnakeGame::new_seeded(20, 10, 1);
+    let (moves, inbox) = mpsc::channel();
+    let mut remote = RemoteController::new(inbox);
+
+    assert_eq!(remote.next_direction(&game, 0, &[]), Direction::Left);
+    moves.send(Direction::Up).unwrap();
+    moves.send(Direction::Down).unwrap();
+    assert_eq!(remote.next_direction(&game, 0, &[]), Direction::Down);
+    assert_eq!(
+        remote.next_direction(&game, 0, &[]),
+        Direction::Left,
+        "nothing new"
+    );
+}
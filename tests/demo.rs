@@ -0,0 +1,20 @@
+use snake::tm_logic::Demo;
+
+// it runs behind every menu for as long as they are up, so it must never stop or fall over
+#[test]
+fn the_demo_goes_on_forever() {
+    for (width, height) in [(10, 10), (80, 24), (13, 31)] {
+        let mut demo = Demo::new(width, height);
+        let mut games = 1;
+        for _ in 0..5_000 {
+            let ended = demo.game().game_over;
+            demo.step();
+            if ended {
+                games += 1;
+                assert_eq!(demo.game().ticks(), 1, "a new game starts straight away");
+            }
+            assert_eq!(demo.game().players(), 2);
+        }
+        assert!(games > 1, "{}x{} never ended", width, height);
+    }
+}
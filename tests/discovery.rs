@@ -0,0 +1,34 @@
+use snake::discovery::{Announcement, Announcer, Browser};
+use std::time::{Duration, Instant};
+
+#[test]
+fn two_join_screens_can_listen_at_once() {
+    let first = Browser::start().unwrap();
+    let second = Browser::start().unwrap();
+
+    let announcement = Announcement {
+        name: String::from("two screens"),
+        port: 40404,
+        players: 1,
+        max_players: 2,
+        width: 20,
+        height: 20,
+        border: true,
+        speed_ms: 100,
+        mode: String::from("hosted"),
+    };
+    let _announcer = Announcer::start(announcement.clone()).unwrap();
+
+    // loopback only reaches one of them, the other has to hear it over the network if at all
+    let heard = |browser: &Browser| {
+        browser
+            .games()
+            .iter()
+            .any(|(_, heard)| *heard == announcement)
+    };
+    let start = Instant::now();
+    while !heard(&first) && !heard(&second) {
+        assert!(start.elapsed() < Duration::from_secs(5), "nobody heard it");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
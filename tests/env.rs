@@ -0,0 +1,133 @@
+use snake::ai::GreedyBot;
+use snake::env::{Encoding, EnvConfig, Rewards, SnakeEnv};
+use snake::snake::Direction;
+
+fn env(encoding: Encoding) -> SnakeEnv {
+    SnakeEnv::new(EnvConfig {
+        width: 12,
+        height: 10,
+        encoding,
+        ..EnvConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn every_encoding_has_the_shape_it_says() {
+    let cases = [
+        (Encoding::Grid, vec![5, 12, 14]),
+        (Encoding::Window(3), vec![3, 7, 7]),
+        (Encoding::Features, vec![15]),
+    ];
+    for (encoding, shape) in cases {
+        let mut env = env(encoding);
+        let observation = env.reset(1);
+        assert_eq!(observation.shape, shape);
+        assert_eq!(observation.data.len(), shape.iter().product::<usize>());
+        let (next, ..) = env.step(Direction::Up);
+        assert_eq!(next.shape, shape);
+    }
+}
+
+#[test]
+fn a_seed_resets_to_the_same_game() {
+    let mut env = env(Encoding::Grid);
+    let first = env.reset(7);
+    env.step(Direction::Up);
+    env.step(Direction::Left);
+    assert_eq!(env.reset(7), first);
+    assert_eq!(env.game().ticks(), 0);
+    assert_ne!(
+        env.reset(8),
+        first,
+        "another seed puts the fruit somewhere else"
+    );
+}
+
+#[test]
+fn steps_pay_the_rewards_they_are_set_to() {
+    let rewards = Rewards {
+        fruit: 5.0,
+        death: -3.0,
+        step: -0.5,
+        win: 100.0,
+    };
+    let mut env = SnakeEnv::new(EnvConfig {
+        width: 12,
+        height: 10,
+        rewards,
+        ..EnvConfig::default()
+    })
+    .unwrap();
+    env.reset(2);
+
+    // straight on until the wall, eating whatever is in the way
+    let mut total = 0.0;
+    loop {
+        let (_, reward, done, info) = env.step(Direction::Left);
+        let expected = -0.5 + if info.ate { 5.0 } else { 0.0 } + if done { -3.0 } else { 0.0 };
+        assert_eq!(reward, expected, "tick {}", info.ticks);
+        total += reward;
+        if done {
+            assert!(!info.truncated && !info.won);
+            assert_eq!(info.length, env.game().body(0).len());
+            break;
+        }
+    }
+    assert!(total < 0.0);
+}
+
+#[test]
+fn running_out_of_ticks_is_done_but_truncated() {
+    let mut env = SnakeEnv::new(EnvConfig {
+        width: 12,
+        height: 10,
+        border: false,
+        max_ticks: 5,
+        ..EnvConfig::default()
+    })
+    .unwrap();
+    env.reset(3);
+    for tick in 1..=5 {
+        let (_, _, done, info) = env.step(Direction::Left);
+        assert_eq!(done, tick == 5);
+        assert_eq!(info.truncated, tick == 5);
+        assert_eq!(info.ticks, tick);
+    }
+}
+
+#[test]
+fn an_opponent_plays_the_other_snake() {
+    let mut env = env(Encoding::Features).with_opponent(Box::new(GreedyBot::new(true)));
+    assert_eq!(env.game().players(), 1, "not until the next reset");
+    env.reset(4);
+    assert_eq!(env.game().players(), 2);
+
+    let before = env.game().body(1)[0];
+    env.step(Direction::Up);
+    assert_ne!(env.game().body(1)[0], before);
+}
+
+#[test]
+fn boards_too_small_to_start_on_are_turned_down() {
+    for (width, height) in [(2, 10), (4, 10), (10, 2), (0, 0)] {
+        let config = EnvConfig {
+            width,
+            height,
+            ..EnvConfig::default()
+        };
+        assert!(
+            SnakeEnv::new(config.clone()).is_err(),
+            "{}x{}",
+            width,
+            height
+        );
+        assert!(snake::vec_env::VecSnakeEnv::new(config, 1, 1).is_err());
+    }
+    let smallest = EnvConfig {
+        width: 5,
+        height: 3,
+        ..EnvConfig::default()
+    };
+    SnakeEnv::new(smallest).unwrap().reset(1);
+}
@@ -0,0 +1,80 @@
+use snake::controller::Controller;
+use snake::external::{parse_move, ProcessBot};
+use snake::snake::{Direction, SnakeGame};
+use std::time::{Duration, Instant};
+
+#[test]
+fn moves_come_in_every_shape() {
+    assert_eq!(parse_move("up"), Some(Direction::Up));
+    assert_eq!(parse_move("  DOWN \r"), Some(Direction::Down));
+    assert_eq!(parse_move(r#""left""#), Some(Direction::Left));
+    assert_eq!(parse_move(r#"{"move": "right"}"#), Some(Direction::Right));
+    assert_eq!(
+        parse_move(r#"{"move": "right", "shout": "hi"}"#),
+        Some(Direction::Right)
+    );
+    assert_eq!(parse_move("sideways"), None);
+    assert_eq!(parse_move(""), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_program_steers_from_the_board_it_is_sent() {
+    // down when told the board wraps, up when it has walls
+    let bot = r#"while read board; do case "$board" in *'"wrap":true'*) echo down;; *) echo '{"move":"up"}';; esac; done"#;
+    let game = SnakeGame::new_seeded(20, 10, 1);
+
+    let mut wrapping = ProcessBot::spawn(bot, false, Duration::from_secs(2)).unwrap();
+    assert_eq!(wrapping.next_direction(&game, 0, &[]), Direction::Down);
+    let mut walled = ProcessBot::spawn(bot, true, Duration::from_secs(2)).unwrap();
+    assert_eq!(walled.next_direction(&game, 0, &[]), Direction::Up);
+    assert_eq!(
+        walled.next_direction(&game, 0, &[]),
+        Direction::Up,
+        "every tick"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn too_slow_or_nonsense_goes_straight() {
+    let game = SnakeGame::new_seeded(20, 10, 1);
+    let going = game.direction_of(0);
+
+    let mut slow = ProcessBot::spawn(
+        "while read board; do sleep 1; echo up; done",
+        true,
+        Duration::from_millis(50),
+    )
+    .unwrap();
+    let start = Instant::now();
+    assert_eq!(slow.next_direction(&game, 0, &[]), going);
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    let mut confused = ProcessBot::spawn(
+        "while read board; do echo what; done",
+        true,
+        Duration::from_secs(2),
+    )
+    .unwrap();
+    assert_eq!(confused.next_direction(&game, 0, &[]), going);
+
+    let mut gone = ProcessBot::spawn("true", true, Duration::from_millis(200)).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(gone.next_direction(&game, 0, &[]), going);
+}
+
+// one that never reads its stdin fills the pipe, the game has to carry on without it
+#[cfg(unix)]
+#[test]
+fn a_bot_that_stops_reading_doesnt_hold_up_the_game() {
+    let game = SnakeGame::new_seeded(20, 10, 1);
+    let going = game.direction_of(0);
+    let mut deaf = ProcessBot::spawn("sleep 30", true, Duration::from_millis(1)).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..1_000 {
+        assert_eq!(deaf.next_direction(&game, 0, &[]), going);
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,109 @@
+use snake::controller::Controller;
+use snake::hamilton::{hamiltonian_cycle, HamiltonBot};
+use snake::snake::SnakeGame;
+
+// plays a single player game with nobody but the bot at the wheel, to the end
+fn play(width: u16, height: u16, border: bool, seed: u64) -> SnakeGame {
+    let mut game = SnakeGame::new_seeded(width, height, seed);
+    let mut bot = HamiltonBot::new(border);
+    while !game.game_over && game.ticks() < 100_000 {
+        let direction = bot.next_direction(&game, 0, &[]);
+        game.steer(0, direction);
+        game.tick(border);
+    }
+    game
+}
+
+// with borders the edges are the wall, the game is won once the inside is covered
+fn fills_the_board(width: u16, height: u16, border: bool) {
+    let playable = if border {
+        (width as usize - 2) * (height as usize - 2)
+    } else {
+        width as usize * height as usize
+    };
+    for seed in 0..3 {
+        let game = play(width, height, border, seed);
+        assert!(
+            game.game_over,
+            "{}x{} seed {} never ended",
+            width, height, seed
+        );
+        assert_eq!(
+            game.winner,
+            1,
+            "{}x{} seed {} crashed at {} long on tick {}",
+            width,
+            height,
+            seed,
+            game.body(0).len(),
+            game.ticks()
+        );
+        assert!(game.body(0).len() >= playable);
+    }
+}
+
+#[test]
+fn wins_with_borders() {
+    // the snake starts on an even row of one and an odd row of the other
+    fills_the_board(8, 8, true);
+    fills_the_board(8, 6, true);
+    fills_the_board(10, 7, true);
+}
+
+#[test]
+fn wins_wrapping_round() {
+    fills_the_board(8, 8, false);
+    fills_the_board(9, 7, false);
+}
+
+// the body starts lying along the cycle one way or the other. every move after that has to
+// carry on round it the same way, a shortcut only ever skips ahead
+#[test]
+fn only_ever_goes_forward_round_the_cycle() {
+    for (width, height, border) in [(8, 8, true), (8, 6, true), (8, 8, false), (9, 7, false)] {
+        let cycle = hamiltonian_cycle(width, height, border).unwrap();
+        let cells = cycle.len();
+        let at = |point| cycle.iter().position(|p| *p == point).unwrap();
+
+        let mut game = SnakeGame::new_seeded(width, height, 1);
+        let body = game.body(0);
+        let way = if (at(body[1]) + 1) % cells == at(body[0]) {
+            1
+        } else {
+            cells - 1
+        };
+        let mut bot = HamiltonBot::new(border);
+        while !game.game_over {
+            let from = at(game.body(0)[0]);
+            let direction = bot.next_direction(&game, 0, &[]);
+            game.steer(0, direction);
+            game.tick(border);
+            let to = at(game.body(0)[0]);
+            let ahead = (to + cells - from) * way % cells;
+            assert!(
+                (1..=cells / 2).contains(&ahead),
+                "{}x{} went {} round on tick {}",
+                width,
+                height,
+                ahead,
+                game.ticks()
+            );
+        }
+    }
+}
+
+#[test]
+fn cycles_visit_every_cell_once() {
+    for (width, height, border) in [(4, 4, true), (6, 5, true), (5, 5, false), (7, 3, false)] {
+        let cycle = hamiltonian_cycle(width, height, border).unwrap();
+        assert_eq!(cycle.len(), width as usize * height as usize);
+        for (i, point) in cycle.iter().enumerate() {
+            assert!(!cycle[..i].contains(point));
+            let next = cycle[(i + 1) % cycle.len()];
+            let (dx, dy) = (point.x.abs_diff(next.x), point.y.abs_diff(next.y));
+            let wrapped = !border && (dx == width - 1 || dy == height - 1);
+            assert!(dx + dy == 1 || wrapped, "{:?} to {:?}", point, next);
+        }
+    }
+    assert!(hamiltonian_cycle(5, 5, true).is_none());
+}
@@ -0,0 +1,64 @@
+use snake::http::{self, Request, Response};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+// a server that sends every body back, on a port nobody else has
+fn echo() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        http::serve(listener, |request: Request| Response::json(request.body))
+    });
+    address
+}
+
+// sends exactly these bytes and reads whatever comes back until the server hangs up
+fn raw(address: SocketAddr, sent: &[u8]) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream.write_all(sent).unwrap();
+    let mut answer = String::new();
+    stream.read_to_string(&mut answer).unwrap();
+    answer
+}
+
+#[test]
+fn a_request_is_answered() {
+    let answer = raw(
+        echo(),
+        b"POST / HTTP/1.1\r\nContent-Length: 7\r\n\r\n{\"a\":1}",
+    );
+    assert!(answer.starts_with("HTTP/1.1 200"), "{}", answer);
+    assert!(answer.ends_with("{\"a\":1}"));
+}
+
+#[test]
+fn a_line_that_never_ends_is_turned_away() {
+    let mut sent = b"GET /".to_vec();
+    sent.resize(8 << 10, b'a');
+    let answer = raw(echo(), &sent);
+    assert!(answer.starts_with("HTTP/1.1 400"), "{}", answer);
+    assert!(answer.contains("line too long"));
+}
+
+#[test]
+fn too_many_headers_are_turned_away() {
+    let mut sent = b"GET / HTTP/1.1\r\n".to_vec();
+    for i in 0..101 {
+        sent.extend(format!("x-{}: y\r\n", i).bytes());
+    }
+    let answer = raw(echo(), &sent);
+    assert!(answer.starts_with("HTTP/1.1 400"), "{}", answer);
+    assert!(answer.contains("too many headers"));
+}
+
+#[test]
+fn a_client_that_goes_quiet_is_given_up_on() {
+    let started = Instant::now();
+    let answer = raw(echo(), b"GET / HTTP/1.1\r\nHost: nowhere\r\n");
+    assert!(answer.starts_with("HTTP/1.1 400"), "{}", answer);
+    assert!(started.elapsed() < Duration::from_secs(9));
+}
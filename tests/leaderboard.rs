@@ -0,0 +1,96 @@
+mod common;
+
+use snake::http;
+use snake::leaderboard;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// a leaderboard of its own on a port nobody else has, and its url
+fn leaderboard(name: &str) -> (String, PathBuf) {
+    let file = std::env::temp_dir().join(format!(
+        "snake-leaderboard-{}-{}.json",
+        std::process::id(),
+        name
+    ));
+    let _ = std::fs::remove_file(&file);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let served = file.clone();
+    std::thread::spawn(move || leaderboard::serve(listener, served));
+    (url, file)
+}
+
+#[test]
+fn scores_go_in_and_come_back_out() {
+    let (url, file) = leaderboard("round-trip");
+    let replay = common::played(20, 15, true, 7);
+    assert!(replay.scores[0] > 0);
+
+    let made_it = leaderboard::submit(&url, &replay).unwrap();
+    assert_eq!(made_it.len(), 1);
+    assert_eq!(made_it[0].score, replay.scores[0]);
+    assert_eq!(made_it[0].mode, "1 player, borders, 50ms");
+
+    // the mode has spaces and a comma in it, so it only matches if it gets there whole
+    let top = leaderboard::top(&url, Some(&replay.mode()), 10).unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].id, made_it[0].id);
+    assert!(leaderboard::top(&url, Some("2 players, borders, 50ms"), 10)
+        .unwrap()
+        .is_empty());
+    assert_eq!(leaderboard::top(&url, None, 10).unwrap().len(), 1);
+
+    let back = leaderboard::replay(&url, made_it[0].id).unwrap();
+    assert_eq!(back.moves, replay.moves);
+    assert_eq!(back.seed, replay.seed);
+
+    // and it was saved as it went in
+    assert!(std::fs::read_to_string(&file).unwrap().contains("greedy"));
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn scores_that_dont_check_out_are_turned_away() {
+    let (url, file) = leaderboard("refused");
+    let mut replay = common::played(20, 15, true, 8);
+    replay.scores[0] += 1;
+    let refused = leaderboard::submit(&url, &replay).unwrap_err();
+    assert!(refused.to_string().contains("400"), "{}", refused);
+    assert!(leaderboard::top(&url, None, 10).unwrap().is_empty());
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn anything_else_is_not_found_or_bad() {
+    let (url, file) = leaderboard("routes");
+    let timeout = Duration::from_secs(2);
+    let answered = |result: std::io::Result<String>| result.unwrap_err().to_string();
+
+    assert!(answered(http::get(&format!("{}/nothing", url), timeout)).contains("404"));
+    assert!(answered(http::get(&format!("{}/replays/3", url), timeout)).contains("404"));
+    assert!(answered(http::get(&format!("{}/replays/x", url), timeout)).contains("404"));
+    assert!(answered(http::post(&format!("{}/scores", url), "{", timeout)).contains("400"));
+    assert!(answered(http::post(&format!("{}/elsewhere", url), "{}", timeout)).contains("404"));
+    // n that isnt a number is the default
+    assert_eq!(
+        http::get(&format!("{}/scores?n=lots&mode=a%20b+c", url), timeout).unwrap(),
+        "[]"
+    );
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn modes_survive_the_query_string() {
+    for text in ["1 player, borders, 50ms", "100% + more", "ünïcode/?&=", ""] {
+        let encoded = leaderboard::encode(text);
+        assert!(encoded
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'%'));
+        assert_eq!(leaderboard::decode(&encoded), text);
+    }
+    assert_eq!(leaderboard::encode("a b,c"), "a%20b%2Cc");
+    assert_eq!(leaderboard::decode("a+b%2cc"), "a b,c");
+    assert_eq!(leaderboard::decode("50%"), "50%");
+    assert_eq!(leaderboard::decode("%zz%4"), "%zz%4");
+}
@@ -0,0 +1,107 @@
+use snake::net::Snapshot;
+use snake::snake::{Direction, Multiplayer, SnakeGame};
+
+// plays the same moves on a game, every tick's hash until it ends
+fn hashes(mut game: SnakeGame, moves: &[[Direction; 2]]) -> Vec<u64> {
+    let mut hashes = Vec::new();
+    for both in moves {
+        if game.game_over {
+            break;
+        }
+        for (player, direction) in both.iter().enumerate() {
+            game.steer(player, *direction);
+        }
+        game.tick(false);
+        hashes.push(game.state_hash());
+    }
+    hashes
+}
+
+fn wander(ticks: usize) -> Vec<[Direction; 2]> {
+    use Direction::*;
+    let pattern = [
+        [Left, Left],
+        [Up, Down],
+        [Up, Down],
+        [Left, Right],
+        [Down, Up],
+    ];
+    pattern.iter().cycle().take(ticks).copied().collect()
+}
+
+#[test]
+fn the_same_seed_and_moves_hash_the_same() {
+    let moves = wander(200);
+    let one = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 7), &moves);
+    let other = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 7), &moves);
+    assert!(one.len() > 10);
+    assert_eq!(one, other);
+
+    // another seed puts the fruit somewhere else from the start
+    let seeded = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 8), &moves);
+    assert_ne!(one[0], seeded[0]);
+}
+
+#[test]
+fn one_different_move_shows_up_in_the_hash() {
+    let moves = wander(50);
+    let mut changed = moves.clone();
+    changed[10][1] = Direction::Up;
+
+    let one = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 3), &moves);
+    let other = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 3), &changed);
+    assert_eq!(one[..10], other[..10]);
+    assert_ne!(one[10], other[10], "the tick the games went different ways");
+}
+
+#[test]
+fn a_snapshot_carries_the_hash_and_survives_json() {
+    let mut game = SnakeGame::multiplayer_new_seeded(30, 20, 4);
+    game.tick(false);
+    let snapshot = Snapshot::of(&game, false);
+    assert_eq!(snapshot.hash, game.state_hash());
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let back: Snapshot = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.hash, snapshot.hash);
+    assert_eq!(back.growing, snapshot.growing);
+}
+
+#[test]
+fn going_back_to_a_copy_and_playing_again_ends_up_the_same() {
+    let moves = wander(60);
+    let played = hashes(SnakeGame::multiplayer_new_seeded(30, 20, 5), &moves);
+    assert_eq!(played.len(), moves.len(), "nobody crashed");
+
+    // run ahead on a guess, then go back and play what really happened, as rollback does
+    let mut game = SnakeGame::multiplayer_new_seeded(30, 20, 5);
+    let mut confirmed = game.clone();
+    let mut guessed = Vec::new();
+    for (tick, both) in moves.iter().enumerate() {
+        let guess = [both[0], Direction::Left];
+        for (player, direction) in guess.iter().enumerate() {
+            game.steer(player, *direction);
+        }
+        game.tick(false);
+        guessed.push(game.state_hash());
+
+        // every fifth tick the other snake's real moves turn up
+        if tick % 5 == 4 {
+            game = confirmed.clone();
+            let since = tick + 1 - 5;
+            for both in &moves[since..=tick] {
+                for (player, direction) in both.iter().enumerate() {
+                    game.steer(player, *direction);
+                }
+                game.tick(false);
+            }
+            assert_eq!(game.state_hash(), played[tick], "tick {}", tick + 1);
+            confirmed = game.clone();
+        }
+    }
+    assert_ne!(
+        guessed,
+        played[..guessed.len()],
+        "the guesses were wrong somewhere"
+    );
+}
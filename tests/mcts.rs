@@ -0,0 +1,66 @@
+use snake::board::{Board, SnakeView};
+use snake::controller::Controller;
+use snake::mcts::MctsBot;
+use snake::snake::{Direction, Multiplayer, Point, SnakeGame};
+use std::time::{Duration, Instant};
+
+fn point(x: u16, y: u16) -> Point {
+    Point { x, y }
+}
+
+#[test]
+fn it_doesnt_drive_into_a_wall() {
+    // heading left along the top row with the wall one cell away
+    let board = Board {
+        tick: 0,
+        width: 12,
+        height: 10,
+        wrap: false,
+        you: 0,
+        snakes: vec![
+            SnakeView {
+                body: vec![point(0, 0), point(1, 0), point(2, 0)],
+                direction: Direction::Left,
+                score: 0,
+            },
+            SnakeView {
+                body: vec![point(8, 8), point(9, 8), point(10, 8)],
+                direction: Direction::Left,
+                score: 0,
+            },
+        ],
+        fruits: vec![point(6, 5)],
+        game_over: false,
+        winner: 0,
+    };
+    let game = SnakeGame::from_board(&board).unwrap();
+    let mut bot = MctsBot::new(true, Duration::from_millis(30));
+    assert_eq!(bot.next_direction(&game, 0, &[]), Direction::Down);
+}
+
+#[test]
+fn it_keeps_to_its_budget_and_leaves_the_game_alone() {
+    let game = SnakeGame::multiplayer_new_seeded(20, 12, 3);
+    let hash = game.state_hash();
+    let mut bot = MctsBot::new(true, Duration::from_millis(40));
+
+    let start = Instant::now();
+    let direction = bot.next_direction(&game, 1, &[]);
+    assert!(start.elapsed() < Duration::from_millis(400));
+    assert_ne!(direction, game.direction_of(1).opposite());
+    assert_eq!(game.state_hash(), hash);
+}
+
+#[test]
+fn a_fork_plays_the_same_but_finds_its_own_fruit() {
+    let mut game = SnakeGame::new_seeded(20, 10, 6);
+    let fork = game.fork(99);
+    assert_eq!(fork.state_hash(), game.state_hash());
+    assert_eq!(fork.body(0), game.body(0));
+
+    // only where the next fruit goes can differ
+    game.tick(true);
+    let mut fork = fork;
+    fork.tick(true);
+    assert_eq!(fork.body(0), game.body(0));
+}
@@ -0,0 +1,152 @@
+use snake::net::{Connection, Message};
+use snake::netsim::Conditions;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+// sends pings 0..n through `conditions` a gap apart, what came out the other end in the
+// order it did and how long after the first went in
+fn through(conditions: &str, n: u64, gap: Duration) -> Vec<(u64, Duration)> {
+    let (sending, arriving) = mpsc::channel();
+    let (delivered, out) = mpsc::channel();
+    Conditions::parse(conditions)
+        .unwrap()
+        .carry(arriving, move |message| delivered.send(message).is_ok());
+
+    let start = Instant::now();
+    for id in 0..n {
+        sending.send(Message::Ping { id }).unwrap();
+        std::thread::sleep(gap);
+    }
+    drop(sending);
+    came_out(out, start)
+}
+
+fn came_out(out: Receiver<Message>, start: Instant) -> Vec<(u64, Duration)> {
+    out.iter()
+        .filter_map(|message| match message {
+            Message::Ping { id } => Some((id, start.elapsed())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn ids(arrived: &[(u64, Duration)]) -> Vec<u64> {
+    arrived.iter().map(|(id, _)| *id).collect()
+}
+
+#[test]
+fn settings_are_read() {
+    let conditions =
+        Conditions::parse("latency=120ms, jitter=0.5s,loss=2%,reorder=0.1,bandwidth=8kB,seed=3")
+            .unwrap();
+    assert_eq!(
+        conditions,
+        Conditions {
+            latency: Duration::from_millis(120),
+            jitter: Duration::from_millis(500),
+            loss: 0.02,
+            reorder: 0.1,
+            bandwidth: Some(8000),
+            seed: Some(3),
+        }
+    );
+    assert_eq!(
+        Conditions::parse("latency=40").unwrap().latency.as_millis(),
+        40
+    );
+    assert!(Conditions::parse("").unwrap().is_perfect());
+
+    for wrong in [
+        "latency",
+        "lag=10ms",
+        "loss=150%",
+        "jitter=-3ms",
+        "bandwidth=0",
+        "seed=x",
+    ] {
+        assert!(Conditions::parse(wrong).is_err(), "{}", wrong);
+    }
+}
+
+#[test]
+fn latency_holds_everything_back() {
+    let arrived = through("latency=80ms,jitter=20ms", 5, Duration::from_millis(10));
+    assert_eq!(ids(&arrived), [0, 1, 2, 3, 4]);
+    let (_, first) = arrived[0];
+    assert!(first >= Duration::from_millis(80), "{:?}", first);
+    assert!(first < Duration::from_millis(300), "{:?}", first);
+}
+
+#[test]
+fn jitter_alone_keeps_the_order() {
+    let arrived = through("jitter=30ms", 100, Duration::from_millis(1));
+    assert_eq!(ids(&arrived), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn reordered_ones_turn_up_late() {
+    let arrived = ids(&through(
+        "reorder=20%,seed=1",
+        100,
+        Duration::from_millis(1),
+    ));
+    let mut sorted = arrived.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..100).collect::<Vec<_>>(), "none went missing");
+    assert_ne!(arrived, sorted, "some were overtaken");
+}
+
+#[test]
+fn loss_loses_about_that_many() {
+    let arrived = through("loss=25%,seed=2", 400, Duration::ZERO);
+    assert!((240..=360).contains(&arrived.len()), "{}", arrived.len());
+    let ids = ids(&arrived);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(through("loss=100%", 20, Duration::ZERO).len(), 0);
+}
+
+#[test]
+fn bandwidth_queues_them_up() {
+    // {"type":"ping","id":10} and a newline is 24 bytes, 50 of them is 1200 at 4kB a second
+    let arrived = through("bandwidth=4kB", 50, Duration::ZERO);
+    assert_eq!(arrived.len(), 50);
+    let (_, last) = arrived[49];
+    assert!(last >= Duration::from_millis(250), "{:?}", last);
+}
+
+#[test]
+fn connections_go_both_ways_through_it() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let client = TcpStream::connect(address).unwrap();
+    let (server, _) = listener.accept().unwrap();
+
+    let conditions = Conditions::parse("latency=60ms").unwrap();
+    let mut client = Connection::new(client)
+        .and_then(|connection| connection.simulated(&conditions))
+        .unwrap();
+    let mut server = Connection::new(server).unwrap();
+
+    // the far end only answers, the time there and back is all this end's pretending
+    let sent = Instant::now();
+    client.send(&Message::Ping { id: 1 }).unwrap();
+    match server.inbox.recv_timeout(Duration::from_secs(2)) {
+        Ok(Message::Ping { id }) => server.send(&Message::Pong { id }).unwrap(),
+        other => panic!("{:?}", other),
+    }
+    assert!(sent.elapsed() >= Duration::from_millis(60));
+
+    assert!(matches!(
+        client.inbox.recv_timeout(Duration::from_secs(2)),
+        Ok(Message::Pong { id: 1 })
+    ));
+    let (id, back) = client.pong().unwrap();
+    assert_eq!(id, 1);
+    assert!(
+        back - sent >= Duration::from_millis(120),
+        "{:?}",
+        back - sent
+    );
+}
@@ -0,0 +1,50 @@
+use rand::SeedableRng;
+use snake::args::TrainArgs;
+use snake::neural::{Genome, INPUTS, OUTPUTS};
+use std::path::PathBuf;
+
+fn genome_file(name: &str, genome: &Genome) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("snake-genome-{}-{}.json", std::process::id(), name));
+    genome.save(&path).unwrap();
+    path
+}
+
+fn hidden(sizes: &str) -> Result<Vec<usize>, String> {
+    let args = ["--hidden", sizes].map(String::from);
+    TrainArgs::parse(args).map(|parsed| parsed.unwrap().training.hidden)
+}
+
+#[test]
+fn empty_hidden_layers_are_turned_down() {
+    assert_eq!(hidden("16"), Ok(vec![16]));
+    assert_eq!(hidden("24, 8"), Ok(vec![24, 8]));
+    assert!(hidden("0").is_err());
+    assert!(hidden("16,0").is_err());
+    assert!(hidden("16,").is_err());
+}
+
+#[test]
+fn genomes_that_dont_fit_their_layers_dont_load() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let genome = Genome::random(&[8, 4], &mut rng);
+    let path = genome_file("fits", &genome);
+    let loaded = Genome::load(&path).unwrap();
+    assert_eq!(loaded.forward(&[0.5; INPUTS]).len(), OUTPUTS);
+
+    // the same number of weights spread over an empty layer
+    let mut empty = genome.clone();
+    empty.layers = vec![INPUTS, 0, OUTPUTS];
+    empty.weights = vec![0.0; Genome::weight_count(&empty.layers)];
+    let mut short = genome.clone();
+    short.weights.pop();
+    let mut wrong_inputs = genome.clone();
+    wrong_inputs.layers[0] = INPUTS + 1;
+
+    for (name, genome) in [("empty", empty), ("short", short), ("inputs", wrong_inputs)] {
+        let path = genome_file(name, &genome);
+        assert!(Genome::load(&path).is_err(), "{} loaded", name);
+        let _ = std::fs::remove_file(path);
+    }
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,383 @@
+use snake::board::{Board, SnakeView};
+use snake::net::{Connection, GameEvent, Message, VERSION};
+use snake::snake::{Direction, Multiplayer, Point, SnakeGame};
+use snake::wire::{self, Body, Delta, Updates};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+// golden/vN.jsonl is what version N wrote, one message a line. whatever the protocol turns
+// into, every one of those has to keep being read. the newest version's file is also checked
+// the other way, so nothing it writes changes by accident. a new version gets a new file and
+// the old ones are never touched again
+//
+// v0 and v1 were printed by checkouts of the game from before versions 1 and 2, not by this
+// code. v0 starts with what builds older still wrote, a hello without a name, lockstep
+// without rollback and seats without away
+
+const GOLDEN: [(u32, &str); 3] = [
+    (0, include_str!("golden/v0.jsonl")),
+    (1, include_str!("golden/v1.jsonl")),
+    (2, include_str!("golden/v2.jsonl")),
+];
+
+fn point(x: u16, y: u16) -> Point {
+    Point { x, y }
+}
+
+fn board(tick: u64, bodies: [Vec<Point>; 2], fruits: Vec<Point>) -> Board {
+    let [first, second] = bodies;
+    Board {
+        tick,
+        width: 12,
+        height: 8,
+        wrap: true,
+        you: 1,
+        snakes: vec![
+            SnakeView {
+                body: first,
+                direction: Direction::Left,
+                score: 2,
+            },
+            SnakeView {
+                body: second,
+                direction: Direction::Up,
+                score: 0,
+            },
+        ],
+        fruits,
+        game_over: false,
+        winner: 0,
+    }
+}
+
+// what goes in golden/v2.jsonl, in order
+fn written_by_version_2() -> Vec<Message> {
+    let before = board(
+        3,
+        [
+            vec![point(4, 2), point(5, 2), point(6, 2)],
+            vec![point(7, 5), point(7, 6), point(7, 7)],
+        ],
+        vec![point(1, 1)],
+    );
+    // the first grew into the fruit's old spot, the second went through the top edge
+    let after = board(
+        4,
+        [
+            vec![point(3, 2), point(4, 2), point(5, 2), point(6, 2)],
+            vec![point(7, 0), point(7, 5), point(7, 6)],
+        ],
+        vec![point(9, 4)],
+    );
+    vec![
+        Message::Hello {
+            version: 2,
+            width: 80,
+            height: 24,
+            name: String::from("ann"),
+        },
+        Message::Version { version: 2 },
+        Message::Ping { id: 7 },
+        Message::Pong { id: 7 },
+        Message::Delta {
+            delta: Delta::between(None, &before),
+        },
+        Message::Delta {
+            delta: Delta::between(Some(&before), &after),
+        },
+        Message::Event {
+            tick: 120,
+            event: GameEvent::Away { player: 1 },
+        },
+        Message::Event {
+            tick: 180,
+            event: GameEvent::Back { player: 1 },
+        },
+    ]
+}
+
+fn read(version: usize) -> Vec<Message> {
+    let (version, golden) = GOLDEN[version];
+    golden
+        .lines()
+        .enumerate()
+        .map(|(line, json)| {
+            serde_json::from_str(json)
+                .unwrap_or_else(|e| panic!("v{} line {}: {}", version, line + 1, e))
+        })
+        .collect()
+}
+
+#[test]
+fn every_version_is_still_read() {
+    for version in 0..GOLDEN.len() {
+        read(version);
+    }
+
+    // and comes out meaning what it meant, what was left out gets its default
+    let v0 = read(0);
+    match &v0[2] {
+        Message::Welcome {
+            lockstep: Some(lockstep),
+            session: None,
+            ..
+        } => assert_eq!(
+            (lockstep.seed, lockstep.input_delay, lockstep.rollback),
+            (7, 2, 0)
+        ),
+        other => panic!("{:?}", other),
+    }
+    match &v0[3] {
+        Message::Rooms { rooms } => {
+            assert_eq!((rooms[0].id, rooms[0].rules.players), (3, 2));
+            assert!(rooms[0].seats[0].ready && !rooms[0].seats[0].away);
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(&v0[21], Message::Watch { room: None }));
+
+    let v1 = read(1);
+    match &v1[0] {
+        Message::Hello { version, name, .. } => assert_eq!((*version, name.as_str()), (1, "ann")),
+        other => panic!("{:?}", other),
+    }
+    match &v1[2] {
+        Message::Welcome {
+            you: 0,
+            border: true,
+            lockstep: None,
+            session: Some(session),
+            ..
+        } => assert_eq!(session, "9f3c6d2a11b04e77"),
+        other => panic!("{:?}", other),
+    }
+    match &v1[3] {
+        Message::Welcome {
+            lockstep: Some(lockstep),
+            session: None,
+            ..
+        } => assert_eq!(
+            (lockstep.seed, lockstep.input_delay, lockstep.rollback),
+            (7, 2, 4)
+        ),
+        other => panic!("{:?}", other),
+    }
+    match &v1[8] {
+        Message::Desync { tick: 9, snapshot } => {
+            assert_eq!(
+                (snapshot.hash, snapshot.growing.as_slice()),
+                (1234, &[0, 2][..])
+            );
+            assert_eq!(snapshot.board.snakes[1].body[0], point(7, 5));
+        }
+        other => panic!("{:?}", other),
+    }
+    match &v1[24] {
+        Message::Submit { replay } => {
+            assert_eq!((replay.seed, replay.moves.len()), (7, 6));
+            assert_eq!(replay.moves[0], [Direction::Left]);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn the_newest_version_is_written_as_before() {
+    let (version, golden) = GOLDEN[GOLDEN.len() - 1];
+    assert_eq!(version, VERSION, "a new version needs its own golden file");
+
+    let written = written_by_version_2();
+    assert_eq!(golden.lines().count(), written.len());
+    for (json, message) in golden.lines().zip(written) {
+        assert_eq!(serde_json::to_string(&message).unwrap(), json);
+    }
+}
+
+#[test]
+fn hellos_from_before_versions_are_version_0() {
+    let hello = GOLDEN[0].1.lines().next().unwrap();
+    match serde_json::from_str(hello) {
+        Ok(Message::Hello { version, name, .. }) => assert_eq!((version, name.as_str()), (0, "")),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn bodies_pack_into_runs() {
+    let straight: Vec<Point> = (0..20).map(|x| point(30 - x, 4)).collect();
+    let packed = wire::pack(&straight);
+    assert_eq!(packed.len(), 5); // the head, and one byte for 19 steps right
+    assert_eq!(wire::unpack(&packed).unwrap(), straight);
+
+    // through the edge of a wrapping board and one segment on top of another while it grows
+    let wrapped = vec![
+        point(0, 3),
+        point(11, 3),
+        point(10, 3),
+        point(10, 3),
+        point(10, 4),
+    ];
+    assert_eq!(wire::unpack(&wire::pack(&wrapped)).unwrap(), wrapped);
+
+    let long: Vec<Point> = (0..200).map(|y| point(5, y)).collect();
+    assert_eq!(wire::unpack(&wire::pack(&long)).unwrap(), long);
+
+    assert_eq!(wire::unpack(&[]).unwrap(), vec![]);
+    assert!(wire::unpack(&[1, 0, 1]).is_none()); // not even a head
+    assert!(wire::unpack(&[0, 0, 0, 0, 0x01]).is_none()); // up from y 0
+    assert!(wire::unpack(&[0, 0, 0, 0, 0, 9]).is_none()); // half a point
+}
+
+#[test]
+fn bodies_go_as_base64() {
+    let body = Body(vec![point(2, 1), point(3, 1), point(4, 1)]);
+    let json = serde_json::to_string(&body).unwrap();
+    assert_eq!(json, "\"AgABAMI=\"");
+    assert_eq!(serde_json::from_str::<Body>(&json).unwrap(), body);
+    assert!(serde_json::from_str::<Body>("\"not base64!\"").is_err());
+}
+
+#[test]
+fn deltas_rebuild_every_tick() {
+    for border in [false, true] {
+        let mut game = SnakeGame::multiplayer_new_seeded(30, 15, 11);
+        let mut updates = Updates::new(VERSION);
+        let mut seen: Option<Board> = None;
+
+        for tick in 0..600 {
+            // turn now and then so the bodies bend and wrap
+            if tick % 7 == 0 {
+                let turn = Direction::ALL[tick / 7 % 4];
+                for player in 0..2 {
+                    if turn != game.direction_of(player).opposite() {
+                        game.steer(player, turn);
+                    }
+                }
+            }
+            game.tick(border);
+
+            let board = Board::of(&game, border, 0);
+            let Message::Delta { delta } = updates.next(board.clone()) else {
+                panic!("version {} should get deltas", VERSION);
+            };
+            let rebuilt = delta
+                .apply(seen.as_ref())
+                .expect("it builds on the last one");
+            assert_eq!(
+                serde_json::to_string(&rebuilt).unwrap(),
+                serde_json::to_string(&board).unwrap(),
+                "tick {}",
+                tick
+            );
+            seen = Some(rebuilt);
+            if game.game_over {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn deltas_need_the_board_they_build_on() {
+    let first = board(3, [vec![point(4, 2)], vec![point(7, 5)]], vec![point(1, 1)]);
+    let second = board(4, [vec![point(3, 2)], vec![point(7, 4)]], vec![point(1, 1)]);
+    let delta = Delta::between(Some(&first), &second);
+    assert!(delta.apply(None).is_none());
+    assert!(delta.apply(Some(&second)).is_none());
+    assert!(delta.apply(Some(&first)).is_some());
+}
+
+#[test]
+fn older_clients_get_whole_states() {
+    let mut updates = Updates::new(1);
+    let first = board(3, [vec![point(4, 2)], vec![point(7, 5)]], vec![point(1, 1)]);
+    assert!(matches!(updates.next(first), Message::State { .. }));
+}
+
+#[test]
+fn a_line_that_never_ends_is_hung_up_on() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    let server = Connection::new(server).unwrap();
+
+    client.write_all(b"{\"type\":\"ping\",\"id\":1}\n").unwrap();
+    // never finished, it only stops once nobody is reading any more
+    std::thread::spawn(move || {
+        let endless = vec![b'x'; 1 << 20];
+        while client.write_all(&endless).is_ok() {}
+    });
+
+    let wait = Duration::from_secs(10);
+    assert!(matches!(
+        server.inbox.recv_timeout(wait),
+        Ok(Message::Ping { id: 1 })
+    ));
+    assert_eq!(
+        server.inbox.recv_timeout(wait).err(),
+        Some(RecvTimeoutError::Disconnected)
+    );
+}
+
+// what --host and --join talk over, one connection each way on localhost
+#[test]
+fn a_connection_carries_messages_both_ways() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut joining = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    let mut hosting = Connection::new(listener.accept().unwrap().0).unwrap();
+    let next = |connection: &Connection| {
+        connection
+            .inbox
+            .recv_timeout(Duration::from_secs(2))
+            .unwrap()
+    };
+
+    joining
+        .send(&Message::Hello {
+            version: VERSION,
+            width: 40,
+            height: 20,
+            name: String::from("bob"),
+        })
+        .unwrap();
+    assert!(matches!(next(&hosting), Message::Hello { width: 40, .. }));
+
+    let game = SnakeGame::multiplayer_new_seeded(40, 20, 1);
+    hosting
+        .send(&Message::State {
+            board: Board::of(&game, true, 1),
+        })
+        .unwrap();
+    match next(&joining) {
+        Message::State { board } => {
+            assert_eq!(board.you, 1);
+            assert_eq!(board.snakes[1].body, Vec::from(game.body(1).clone()));
+        }
+        other => panic!("{:?}", other),
+    }
+
+    for direction in [Direction::Up, Direction::Left] {
+        joining.send(&Message::Input { direction }).unwrap();
+    }
+    let inputs: Vec<Direction> = (0..2)
+        .filter_map(|_| match next(&hosting) {
+            Message::Input { direction } => Some(direction),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        inputs,
+        [Direction::Up, Direction::Left],
+        "in the order they were sent"
+    );
+
+    // the inbox says when the other side has gone
+    joining.outbox().unwrap().close();
+    assert_eq!(
+        hosting.inbox.recv_timeout(Duration::from_secs(2)).err(),
+        Some(RecvTimeoutError::Disconnected)
+    );
+}
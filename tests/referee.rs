@@ -0,0 +1,61 @@
+use snake::referee::{Foul, Referee};
+use snake::snake::{Direction, Multiplayer, SnakeGame};
+
+// a quarter turn from the way it is going
+fn aside(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up | Direction::Down => Direction::Left,
+        Direction::Left | Direction::Right => Direction::Up,
+    }
+}
+
+#[test]
+fn straight_back_is_a_foul() {
+    let game = SnakeGame::new_seeded(20, 10, 1);
+    let mut referee = Referee::new(1);
+    let going = game.direction_of(0);
+
+    assert_eq!(referee.judge(&game, 0, going), Ok(()));
+    assert_eq!(
+        referee.judge(&game, 0, going.opposite()),
+        Err(Foul::Reversal)
+    );
+    assert_eq!(referee.fouls[0], [Foul::Reversal]);
+}
+
+#[test]
+fn one_turn_a_tick() {
+    let mut game = SnakeGame::new_seeded(20, 10, 2);
+    let mut referee = Referee::new(1);
+    let going = game.direction_of(0);
+
+    // up then left while going right would be straight back by the next tick
+    let turn = aside(going);
+    assert_eq!(referee.judge(&game, 0, turn), Ok(()));
+    game.steer(0, turn);
+    assert_eq!(referee.judge(&game, 0, going), Err(Foul::SecondTurn));
+    assert_eq!(referee.judge(&game, 0, turn), Ok(()), "going on is no turn");
+
+    game.tick(true);
+    referee.tick();
+    assert_eq!(referee.judge(&game, 0, going), Ok(()));
+    assert_eq!(referee.fouls[0], [Foul::SecondTurn]);
+}
+
+#[test]
+fn too_many_moves_is_flooding() {
+    let game = SnakeGame::multiplayer_new_seeded(20, 10, 3);
+    let mut referee = Referee::new(2);
+    let going = game.direction_of(0);
+
+    let verdicts: Vec<_> = (0..40).map(|_| referee.judge(&game, 0, going)).collect();
+    assert!(verdicts[..30].iter().all(|verdict| verdict.is_ok()));
+    assert!(verdicts[30..]
+        .iter()
+        .all(|verdict| *verdict == Err(Foul::Flooding)));
+
+    // only the one that sent them is cut off
+    assert_eq!(referee.judge(&game, 1, game.direction_of(1)), Ok(()));
+    assert_eq!(referee.fouls[0].len(), 10);
+    assert!(referee.fouls[1].is_empty());
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use snake::replay::Replay;
+use snake::snake::SnakeGame;
+
+// why verify turned it down
+fn refused(replay: &Replay) -> String {
+    match replay.verify() {
+        Ok(_) => panic!("it checked out"),
+        Err(reason) => reason,
+    }
+}
+
+#[test]
+fn a_recorded_game_checks_out() {
+    for (seed, border) in [(1, true), (2, false), (3, true)] {
+        let replay = common::played(20, 12, border, seed);
+        let Ok(game) = replay.verify() else {
+            panic!("seed {} did not check out", seed);
+        };
+        assert!(game.game_over);
+        assert_eq!(game.score(0), replay.scores[0]);
+        assert_eq!(game.ticks(), replay.moves.len() as u64);
+    }
+}
+
+#[test]
+fn claiming_more_than_was_scored_is_refused() {
+    let mut replay = common::played(20, 12, true, 4);
+    replay.scores[0] += 1;
+    let reason = refused(&replay);
+    assert!(reason.contains("not the"), "{}", reason);
+}
+
+#[test]
+fn moves_after_the_end_are_refused() {
+    let mut replay = common::played(20, 12, true, 5);
+    let last = replay.moves.last().unwrap().clone();
+    replay.moves.push(last);
+    let reason = refused(&replay);
+    assert!(reason.contains("after the game ended"), "{}", reason);
+
+    // and so is stopping before it
+    let mut replay = common::played(20, 12, true, 5);
+    replay.moves.pop();
+    assert_eq!(refused(&replay), "the game never ended");
+}
+
+#[test]
+fn turning_back_is_refused() {
+    let mut replay = common::played(20, 12, true, 6);
+    let going = SnakeGame::new_seeded(20, 12, 6).direction_of(0);
+    replay.moves[0][0] = going.opposite();
+    assert_eq!(refused(&replay), "snake 1 turned back on tick 1");
+}
+
+#[test]
+fn boards_and_players_the_game_doesnt_have_are_refused() {
+    let mut replay = common::played(20, 12, true, 7);
+    replay.width = 5;
+    assert!(replay.verify().is_err());
+
+    let mut replay = common::played(20, 12, true, 7);
+    replay.names.push(String::from("nobody"));
+    replay.names.push(String::from("else"));
+    assert!(replay.verify().is_err());
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use snake::replay::Replay;
+use snake::scores::{Checked, HighScores};
+
+// games that scored something, each with a different seed
+fn scoring(count: usize) -> Vec<Replay> {
+    (1..)
+        .map(|seed| common::played(20, 12, true, seed))
+        .filter(|replay| replay.scores[0] > 0)
+        .take(count)
+        .collect()
+}
+
+#[test]
+fn ten_are_kept_in_each_mode() {
+    let mut scores = HighScores::default();
+    let replays = scoring(12);
+
+    let mut ids = Vec::new();
+    for replay in &replays {
+        let made_it = scores.submit(Checked::new(replay.clone()).unwrap());
+        ids.push(made_it.first().map(|high| high.id));
+    }
+    let mode = replays[0].mode();
+    let top = scores.top(Some(&mode), 100);
+    assert_eq!(top.len(), 10);
+    assert!(top.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+    // the two left out are the worst, and their replays went with them
+    let mut claimed: Vec<u16> = replays.iter().map(|replay| replay.scores[0]).collect();
+    claimed.sort_unstable_by(|a, b| b.cmp(a));
+    let kept: Vec<u16> = top.iter().map(|high| high.score).collect();
+    assert_eq!(kept, claimed[..10]);
+    for high in &top {
+        assert!(scores.replay(high.id).is_some());
+    }
+    let dropped = (0..ids.len() as u64).filter(|id| top.iter().all(|high| high.id != *id));
+    for id in dropped {
+        assert!(scores.replay(id).is_none());
+    }
+
+    // another mode has a table of its own, and doesnt push anything out of this one
+    let mut slower = replays[0].clone();
+    slower.speed_ms = 100;
+    let made_it = scores.submit(Checked::new(slower.clone()).unwrap());
+    assert_eq!(made_it.len(), 1);
+    assert_eq!(made_it[0].mode, slower.mode());
+    assert_eq!(scores.top(Some(&mode), 100).len(), 10);
+    assert_eq!(scores.top(None, 100).len(), 11);
+}
+
+#[test]
+fn a_score_too_low_doesnt_make_it() {
+    let mut scores = HighScores::default();
+    let mut replays = scoring(11);
+    replays.sort_by_key(|replay| std::cmp::Reverse(replay.scores[0]));
+    let worst = replays.pop().unwrap();
+    for replay in replays {
+        assert_eq!(scores.submit(Checked::new(replay).unwrap()).len(), 1);
+    }
+    assert!(scores.submit(Checked::new(worst).unwrap()).is_empty());
+}
+
+#[test]
+fn unchecked_replays_never_get_in() {
+    let mut replay = common::played(20, 12, true, 1);
+    replay.scores[0] += 10;
+    assert!(Checked::new(replay).is_err());
+}
@@ -0,0 +1,170 @@
+use snake::net::{Connection, GameEvent, Message, RoomRules, VERSION};
+use snake::server;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+fn server(grace: Duration) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || server::serve(listener, None, grace));
+    address
+}
+
+fn connect(address: SocketAddr, name: &str) -> Connection {
+    let mut connection = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    connection
+        .send(&Message::Hello {
+            version: VERSION,
+            width: 20,
+            height: 20,
+            name: String::from(name),
+        })
+        .unwrap();
+    connection
+}
+
+// reads until `wanted` picks something out, everything else is skipped
+fn wait_for<T>(connection: &Connection, mut wanted: impl FnMut(Message) -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match connection.inbox.recv_timeout(wait) {
+            Ok(message) => {
+                if let Some(found) = wanted(message) {
+                    return found;
+                }
+            }
+            Err(e) => panic!("never came: {}", e),
+        }
+    }
+}
+
+// a one player game on a board that wraps, so it goes on until the player is gone.
+// the room and the session for its seat
+fn start_game(address: SocketAddr) -> (Connection, u32, String) {
+    let mut player = connect(address, "ann");
+    let rules = RoomRules {
+        players: 1,
+        border: false,
+        speed_ms: 100,
+    };
+    player
+        .send(&Message::CreateRoom {
+            name: String::from("test"),
+            rules,
+        })
+        .unwrap();
+    let room = wait_for(&player, |message| match message {
+        Message::Room { room } => Some(room.id),
+        _ => None,
+    });
+    player.send(&Message::Ready { ready: true }).unwrap();
+    let session = wait_for(&player, |message| match message {
+        Message::Welcome { session, .. } => session,
+        _ => None,
+    });
+    (player, room, session)
+}
+
+fn drop_out(connection: Connection) {
+    connection.outbox().unwrap().close();
+}
+
+fn rejoin(address: SocketAddr, session: &str) -> (Connection, Result<usize, String>) {
+    let mut connection = connect(address, "ann");
+    connection
+        .send(&Message::Rejoin {
+            session: String::from(session),
+        })
+        .unwrap();
+    let answer = wait_for(&connection, |message| match message {
+        Message::Welcome { you, .. } => Some(Ok(you)),
+        Message::Refused { reason } => Some(Err(reason)),
+        _ => None,
+    });
+    (connection, answer)
+}
+
+#[test]
+fn a_dropped_player_gets_their_seat_back() {
+    let address = server(Duration::from_secs(30));
+    let (player, room, session) = start_game(address);
+
+    let mut watcher = connect(address, "watcher");
+    watcher.send(&Message::Watch { room: Some(room) }).unwrap();
+    wait_for(&watcher, |message| match message {
+        Message::Delta { .. } | Message::State { .. } => Some(()),
+        _ => None,
+    });
+
+    drop_out(player);
+    let event = |message| match message {
+        Message::Event { event, .. } => Some(event),
+        _ => None,
+    };
+    assert_eq!(wait_for(&watcher, event), GameEvent::Away { player: 0 });
+
+    let (_back, answer) = rejoin(address, &session);
+    assert_eq!(answer, Ok(0));
+    assert_eq!(wait_for(&watcher, event), GameEvent::Back { player: 0 });
+
+    // the seat is taken again, the same session cant have it twice
+    let (_again, answer) = rejoin(address, &session);
+    assert!(answer.is_err());
+}
+
+#[test]
+fn a_seat_is_given_up_once_the_grace_runs_out() {
+    let address = server(Duration::from_millis(300));
+    let (player, _, session) = start_game(address);
+
+    drop_out(player);
+    std::thread::sleep(Duration::from_secs(1));
+    let (mut late, answer) = rejoin(address, &session);
+    assert!(answer.is_err());
+
+    // nobody came back so the game ended, and the room went with its last player
+    late.send(&Message::ListRooms).unwrap();
+    let rooms = wait_for(&late, |message| match message {
+        Message::Rooms { rooms } => Some(rooms),
+        _ => None,
+    });
+    assert!(rooms.is_empty(), "{:?}", rooms);
+}
+
+#[test]
+fn dropping_out_of_a_room_before_the_game_leaves_it() {
+    let address = server(Duration::from_secs(30));
+    let mut player = connect(address, "ann");
+    let rules = RoomRules {
+        players: 2,
+        border: true,
+        speed_ms: 100,
+    };
+    player
+        .send(&Message::CreateRoom {
+            name: String::from("waiting"),
+            rules,
+        })
+        .unwrap();
+    wait_for(&player, |message| match message {
+        Message::Room { .. } => Some(()),
+        _ => None,
+    });
+    drop_out(player);
+
+    let mut other = connect(address, "bob");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        other.send(&Message::ListRooms).unwrap();
+        let rooms = wait_for(&other, |message| match message {
+            Message::Rooms { rooms } => Some(rooms),
+            _ => None,
+        });
+        if rooms.is_empty() {
+            break;
+        }
+        assert!(Instant::now() < deadline, "the room was kept");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,160 @@
+use snake::board::{Board, SnakeView};
+use snake::snake::{Direction, Point, SnakeGame};
+
+fn point(x: u16, y: u16) -> Point {
+    Point { x, y }
+}
+
+fn snake(body: &[(u16, u16)], direction: Direction, score: u16) -> SnakeView {
+    SnakeView {
+        body: body.iter().map(|(x, y)| point(*x, *y)).collect(),
+        direction,
+        score,
+    }
+}
+
+// a game set up just so, from the outside view of one
+fn game(snakes: Vec<SnakeView>, fruits: &[(u16, u16)]) -> SnakeGame {
+    let board = Board {
+        tick: 0,
+        width: 20,
+        height: 10,
+        wrap: false,
+        you: 0,
+        snakes,
+        fruits: fruits.iter().map(|(x, y)| point(*x, *y)).collect(),
+        game_over: false,
+        winner: 0,
+    };
+    SnakeGame::from_board(&board).unwrap()
+}
+
+#[test]
+fn steering_straight_back_is_ignored() {
+    let mut game = SnakeGame::new_seeded(20, 10, 1);
+    assert_eq!(game.direction_of(0), Direction::Left);
+    game.steer(0, Direction::Right);
+    assert_eq!(game.direction_of(0), Direction::Left);
+    game.steer(0, Direction::Up);
+    assert_eq!(game.direction_of(0), Direction::Up);
+    // what counts is the way it is going now, not the way it went last tick
+    game.steer(0, Direction::Right);
+    assert_eq!(game.direction_of(0), Direction::Right);
+}
+
+#[test]
+fn a_tick_moves_the_head_one_cell() {
+    let mut game = SnakeGame::new_seeded(20, 10, 1);
+    let before: Vec<Point> = game.body(0).iter().copied().collect();
+    game.steer(0, Direction::Up);
+    game.tick(true);
+
+    let after: Vec<Point> = game.body(0).iter().copied().collect();
+    assert_eq!(after[0], point(before[0].x, before[0].y - 1));
+    assert_eq!(
+        after[1..],
+        before[..2],
+        "the rest follows, the tail comes off"
+    );
+    assert_eq!(game.ticks(), 1);
+    assert!(!game.game_over);
+}
+
+#[test]
+fn a_fruit_is_a_point_and_two_segments() {
+    let mut game = game(
+        vec![snake(&[(5, 5), (6, 5), (7, 5)], Direction::Left, 0)],
+        &[(4, 5)],
+    );
+    game.tick(true);
+    assert_eq!(game.score(0), 1);
+    assert_eq!(game.growing(0), 2);
+    assert_ne!(
+        game.fruits()[0],
+        point(4, 5),
+        "a new one went somewhere else"
+    );
+
+    game.tick(true);
+    game.tick(true);
+    assert_eq!(game.body(0).len(), 5);
+    assert_eq!(game.growing(0), 0);
+}
+
+#[test]
+fn walls_end_it_and_wrapping_goes_round() {
+    let edge = || {
+        game(
+            vec![snake(&[(0, 5), (1, 5), (2, 5)], Direction::Left, 0)],
+            &[(10, 2)],
+        )
+    };
+    let mut walled = edge();
+    walled.tick(true);
+    assert!(walled.game_over);
+
+    let mut wrapped = edge();
+    wrapped.tick(false);
+    assert!(!wrapped.game_over);
+    assert_eq!(wrapped.body(0)[0], point(19, 5));
+}
+
+#[test]
+fn running_into_itself_ends_it() {
+    let mut game = game(
+        vec![snake(
+            &[(5, 5), (5, 6), (6, 6), (6, 5), (6, 4)],
+            Direction::Up,
+            0,
+        )],
+        &[(10, 2)],
+    );
+    game.steer(0, Direction::Right);
+    game.tick(true);
+    assert!(game.game_over);
+}
+
+#[test]
+fn head_on_goes_to_whoever_ate_more() {
+    for (scores, winner) in [((1, 3), 2), ((3, 1), 1), ((2, 2), 3)] {
+        let mut game = game(
+            vec![
+                snake(&[(5, 5), (4, 5), (3, 5)], Direction::Right, scores.0),
+                snake(&[(7, 5), (8, 5), (9, 5)], Direction::Left, scores.1),
+            ],
+            &[],
+        );
+        game.tick(true);
+        assert!(game.game_over);
+        assert_eq!(game.winner, winner, "scores {:?}", scores);
+    }
+}
+
+// with borders the edges are the wall, so covering the inside is as far as it goes
+#[test]
+fn with_borders_a_full_inside_wins() {
+    let board = Board {
+        tick: 0,
+        width: 5,
+        height: 4,
+        wrap: false,
+        you: 0,
+        snakes: vec![snake(
+            &[(2, 2), (3, 2), (3, 1), (2, 1), (1, 1)],
+            Direction::Left,
+            0,
+        )],
+        fruits: vec![point(1, 2)],
+        game_over: false,
+        winner: 0,
+    };
+    let mut game = SnakeGame::from_board(&board).unwrap();
+    game.tick(true);
+    assert_eq!(game.fruits()[0], point(1, 1), "the one cell left inside");
+
+    game.steer(0, Direction::Up);
+    game.tick(true);
+    assert!(game.game_over);
+    assert_eq!(game.winner, 1);
+    assert_eq!(game.body(0).len(), 6);
+}
@@ -0,0 +1,127 @@
+use snake::net::{Connection, Message, RoomRules};
+use snake::spectate::Broadcast;
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+fn watcher(address: std::net::SocketAddr) -> Connection {
+    let mut connection = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    connection.send(&Message::Watch { room: None }).unwrap();
+    connection
+}
+
+fn next(connection: &Connection) -> Message {
+    connection
+        .inbox
+        .recv_timeout(Duration::from_secs(2))
+        .unwrap()
+}
+
+// watchers cant pass themselves off as anyone else, whatever they put on their messages
+#[test]
+fn watchers_chat_under_the_name_they_are_given() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let rules = RoomRules {
+        players: 1,
+        border: true,
+        speed_ms: 50,
+    };
+    let mut broadcast = Broadcast::new(listener, String::from("ann's game"), rules, &["ann"]);
+
+    let mut first = watcher(address);
+    let second = watcher(address);
+    let start = Instant::now();
+    while broadcast.watchers() < 2 {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        broadcast.accept();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(matches!(next(&first), Message::Room { .. }));
+    assert!(matches!(next(&second), Message::Room { .. }));
+
+    let forged = Message::Chat {
+        from: String::from("ann"),
+        text: String::from("i give up"),
+    };
+    first.send(&forged).unwrap();
+    let heard = loop {
+        let heard = broadcast.heard();
+        if !heard.is_empty() {
+            break heard;
+        }
+        assert!(start.elapsed() < Duration::from_secs(4));
+        std::thread::sleep(Duration::from_millis(5));
+    };
+
+    let said = |message: &Message| match message {
+        Message::Chat { from, text } => (from.clone(), text.clone()),
+        other => panic!("{:?}", other),
+    };
+    let expected = (String::from("watcher 1"), String::from("i give up"));
+    assert_eq!(said(&heard[0]), expected);
+    assert_eq!(said(&next(&second)), expected);
+    assert!(first
+        .inbox
+        .recv_timeout(Duration::from_millis(100))
+        .is_err());
+}
+
+// watchers can come in at any point and see the game as it is now, players cant
+#[test]
+fn late_watchers_see_the_game_as_it_is_now() {
+    use snake::snake::SnakeGame;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let rules = RoomRules {
+        players: 1,
+        border: false,
+        speed_ms: 50,
+    };
+    let mut broadcast = Broadcast::new(listener, String::from("ann's game"), rules, &["ann"]);
+    let mut game = SnakeGame::new_seeded(20, 10, 1);
+    for _ in 0..5 {
+        game.tick(false);
+        broadcast.send(&game, false);
+    }
+
+    let late = watcher(address);
+    let mut player = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    player
+        .send(&Message::Hello {
+            version: snake::net::VERSION,
+            width: 20,
+            height: 10,
+            name: String::from("bob"),
+        })
+        .unwrap();
+    let start = Instant::now();
+    while broadcast.watchers() < 1 {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        game.tick(false);
+        broadcast.send(&game, false);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(matches!(next(&player), Message::Refused { .. }));
+
+    match next(&late) {
+        Message::Room { room } => assert_eq!(room.seats[0].name, "ann"),
+        other => panic!("{:?}", other),
+    }
+    game.tick(false);
+    broadcast.send(&game, false);
+    // the ticks it turned up during may come first, the last one is the game now
+    let board = loop {
+        match next(&late) {
+            Message::State { board } if board.tick == game.ticks() => break board,
+            Message::State { .. } => {}
+            other => panic!("{:?}", other),
+        }
+    };
+    assert_eq!(board.snakes[0].body, Vec::from(game.body(0).clone()));
+
+    broadcast.finish(&game, false);
+    assert!(matches!(next(&late), Message::State { .. }));
+    assert!(matches!(next(&late), Message::Bye));
+    assert_eq!(broadcast.watchers(), 0);
+}
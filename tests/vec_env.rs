@@ -0,0 +1,82 @@
+use snake::env::{Encoding, EnvConfig};
+use snake::vec_env::VecSnakeEnv;
+
+#[test]
+fn only_encodings_it_can_do_are_taken() {
+    for (encoding, size) in [(Encoding::Grid, 5 * 22 * 22), (Encoding::Features, 15)] {
+        let config = EnvConfig {
+            encoding: encoding.clone(),
+            ..EnvConfig::default()
+        };
+        let Ok(env) = VecSnakeEnv::new(config, 4, 1) else {
+            panic!("{:?} was turned down", encoding);
+        };
+        let mut out = Vec::new();
+        env.observe(&mut out);
+        assert_eq!(env.observation_size(), size);
+        assert_eq!(out.len(), 4 * size);
+    }
+
+    let config = EnvConfig {
+        encoding: Encoding::Window(3),
+        ..EnvConfig::default()
+    };
+    assert!(VecSnakeEnv::new(config, 4, 1).is_err());
+}
+
+// the same moves on a VecSnakeEnv board and on a SnakeGame with the fruit put where the
+// board put it, they must eat, grow and die together
+#[test]
+fn plays_the_same_as_snake_game() {
+    use snake::ai::GreedyBot;
+    use snake::controller::Controller;
+    use snake::snake::SnakeGame;
+
+    for border in [true, false] {
+        let config = EnvConfig {
+            width: 12,
+            height: 8,
+            border,
+            max_ticks: u64::MAX,
+            encoding: Encoding::Features,
+            ..EnvConfig::default()
+        };
+        let mut env = VecSnakeEnv::new(config, 1, 3).unwrap();
+        let mut game = SnakeGame::new_seeded(12, 8, 0);
+        let mut bot = GreedyBot::new(border);
+        let (mut deaths, mut eaten) = (0, 0);
+
+        for tick in 0..5_000 {
+            game.place_fruit(Some(env.fruit(0)), border).unwrap();
+            let direction = bot.next_direction(&game, 0, &[]);
+            env.step(&[direction]);
+            game.steer(0, direction);
+            game.tick(border);
+
+            assert_eq!(env.dones[0], game.game_over, "tick {}", tick);
+            if env.dones[0] {
+                assert_eq!(env.finished_scores[0], game.score(0) as u32);
+                eaten += game.score(0);
+                deaths += 1;
+                game = SnakeGame::new_seeded(12, 8, 0);
+                continue;
+            }
+            assert_eq!(env.score(0), game.score(0) as u32, "tick {}", tick);
+            assert_eq!(env.length(0) as usize, game.body(0).len(), "tick {}", tick);
+            assert_eq!(env.head(0), game.body(0)[0], "tick {}", tick);
+        }
+        assert!(
+            deaths > 2 && eaten > 20,
+            "{} deaths {} eaten",
+            deaths,
+            eaten
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "one action per board")]
+fn every_board_needs_an_action() {
+    let mut env = VecSnakeEnv::new(EnvConfig::default(), 4, 1).unwrap();
+    env.step(&[snake::snake::Direction::Left; 3]);
+}
@@ -0,0 +1,127 @@
+use snake::websocket::{accept_key, read_message, write_text};
+use std::io::{Cursor, ErrorKind};
+
+// a frame as a browser would send it
+fn frame(last: bool, opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) -> Vec<u8> {
+    let mut frame = vec![if last { 0x80 } else { 0 } | opcode];
+    let masked = if mask.is_some() { 0x80 } else { 0 };
+    match payload.len() {
+        length @ 0..=125 => frame.push(masked | length as u8),
+        length @ 126..=0xffff => {
+            frame.push(masked | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(masked | 127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    match mask {
+        Some(mask) => {
+            frame.extend_from_slice(&mask);
+            frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        }
+        None => frame.extend_from_slice(payload),
+    }
+    frame
+}
+
+fn read_all(bytes: Vec<u8>) -> Vec<Option<Vec<u8>>> {
+    let mut reader = Cursor::new(bytes);
+    let mut messages = Vec::new();
+    while (reader.position() as usize) < reader.get_ref().len() {
+        messages.push(read_message(&mut reader).unwrap());
+    }
+    messages
+}
+
+#[test]
+fn the_handshake_answers_like_the_rfc() {
+    // the example in section 1.3
+    let key = "dGhlIHNhbXBsZSBub25jZQ==";
+    assert_eq!(accept_key(key), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    assert_eq!(accept_key(" dGhlIHNhbXBsZSBub25jZQ==\r"), accept_key(key));
+}
+
+#[test]
+fn masked_frames_are_unmasked() {
+    // "Hello" masked, from section 5.7
+    let bytes = vec![
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+    assert_eq!(read_all(bytes), [Some(b"Hello".to_vec())]);
+
+    let long = vec![b'x'; 70_000];
+    let bytes = frame(true, 0x1, &long, Some([1, 2, 3, 4]));
+    assert_eq!(read_all(bytes), [Some(long)]);
+}
+
+#[test]
+fn fragments_come_back_as_one_message() {
+    // "Hel" and "lo" from section 5.7, with a ping in between that is passed over
+    let mut bytes = frame(false, 0x1, b"Hel", Some([9, 8, 7, 6]));
+    bytes.extend(frame(true, 0x9, b"ping", Some([1, 1, 1, 1])));
+    bytes.extend(frame(true, 0x0, b"lo", Some([5, 4, 3, 2])));
+    bytes.extend(frame(true, 0x1, b"next", None));
+    bytes.extend(frame(true, 0x8, b"", Some([0, 0, 0, 0])));
+
+    assert_eq!(
+        read_all(bytes),
+        [Some(b"Hello".to_vec()), Some(b"next".to_vec()), None]
+    );
+}
+
+#[test]
+fn what_is_written_reads_back() {
+    for length in [0, 5, 125, 126, 300, 0xffff, 0x10000] {
+        let text: String = "snake".chars().cycle().take(length).collect();
+        let mut bytes = Vec::new();
+        write_text(&mut bytes, &text).unwrap();
+        assert_eq!(bytes[0], 0x81, "one whole text frame");
+        assert_eq!(bytes[1] & 0x80, 0, "servers dont mask");
+        assert_eq!(read_all(bytes), [Some(text.into_bytes())]);
+    }
+}
+
+#[test]
+fn oversized_messages_are_refused_before_they_are_read() {
+    // only the header, the length says more than it will take
+    let mut bytes = vec![0x82, 127];
+    bytes.extend_from_slice(&(1u64 << 40).to_be_bytes());
+    let refused = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(refused.kind(), ErrorKind::InvalidData);
+
+    // and pieces that are each fine but too long together
+    let mut bytes = frame(false, 0x2, &vec![0; 20 << 20], None);
+    bytes.extend_from_slice(&[0x80, 127]);
+    bytes.extend_from_slice(&(20u64 << 20).to_be_bytes());
+    let refused = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(refused.kind(), ErrorKind::InvalidData);
+
+    // a length that would wrap round once added to what has come so far
+    let mut bytes = frame(false, 0x1, b"hi", None);
+    bytes.extend_from_slice(&[0x80, 127]);
+    bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+    let refused = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(refused.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn pieces_only_go_on_a_message_that_has_started() {
+    let bytes = frame(true, 0x0, b"lo", Some([1, 2, 3, 4]));
+    let refused = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(refused.kind(), ErrorKind::InvalidData);
+
+    let mut bytes = frame(false, 0x1, b"Hel", Some([1, 2, 3, 4]));
+    bytes.extend(frame(true, 0x1, b"lo", Some([5, 6, 7, 8])));
+    let refused = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(refused.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn a_frame_cut_short_is_an_error() {
+    let mut bytes = frame(true, 0x1, b"Hello", Some([1, 2, 3, 4]));
+    bytes.truncate(bytes.len() - 2);
+    let cut = read_message(&mut Cursor::new(bytes)).unwrap_err();
+    assert_eq!(cut.kind(), ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,197 @@
+// the browser end of snake-server. it speaks the same messages as the terminal game,
+// see net.rs, one per websocket frame
+
+const VERSION = 1;
+const $ = (id) => document.getElementById(id);
+const show = (...ids) => {
+  for (const id of ["name", "lobby", "room", "game"]) {
+    $(id).classList.toggle("hidden", !ids.includes(id));
+  }
+};
+
+let socket = null;
+let you = null; // which snake is ours while playing, null otherwise
+let watching = false;
+let room = null;
+let ready = false;
+let seats = [];
+
+const send = (message) => socket.send(JSON.stringify(message));
+
+// how many characters fit on screen, the server makes the board no bigger
+function size() {
+  const probe = document.createElement("span");
+  probe.textContent = "x";
+  $("board").appendChild(probe);
+  const { width, height } = probe.getBoundingClientRect();
+  probe.remove();
+  return {
+    width: Math.max(20, Math.min(200, Math.floor((window.innerWidth - 40) / width))),
+    height: Math.max(10, Math.min(100, Math.floor((window.innerHeight - 160) / height))),
+  };
+}
+
+function connect(name) {
+  const scheme = location.protocol === "https:" ? "wss" : "ws";
+  socket = new WebSocket(`${scheme}://${location.host}/`);
+  socket.onopen = () => {
+    $("game").classList.remove("hidden"); // so size() can measure the board's font
+    send({ type: "hello", version: VERSION, name, ...size() });
+    send({ type: "list_scores" });
+    show("lobby");
+  };
+  socket.onmessage = (event) => receive(JSON.parse(event.data));
+  socket.onclose = () => {
+    $("notice").textContent = "The server went away, reload to try again";
+  };
+}
+
+function receive(message) {
+  switch (message.type) {
+    case "rooms":
+      room = null;
+      ready = false;
+      you = null;
+      watching = false;
+      lobby(message.rooms);
+      show("lobby");
+      break;
+    case "room":
+      room = message.room;
+      seats = room.seats;
+      roomView();
+      if (watching) show("game");
+      else if (you === null) show(room.playing ? "game" : "room");
+      break;
+    case "welcome":
+      you = message.you;
+      $("chat").textContent = "";
+      show("game");
+      break;
+    case "state":
+      draw(message.board);
+      if (message.board.game_over) {
+        you = null;
+        ready = false;
+        send({ type: "list_scores" });
+        setTimeout(() => room && !room.playing && !watching && show("room"), 2000);
+      }
+      break;
+    case "chat":
+      heard(message.from, message.text);
+      break;
+    case "high_scores":
+      $("scores").textContent = message.scores.length ? "High scores" : "";
+      for (const high of message.scores.slice(0, 5)) {
+        const line = document.createElement("div");
+        line.textContent = `${high.name} ${high.score}  ${high.mode}`;
+        $("scores").appendChild(line);
+      }
+      break;
+    case "refused":
+      $("notice").textContent = message.reason;
+      break;
+  }
+}
+
+function lobby(rooms) {
+  $("rooms").textContent = rooms.length ? "" : "None yet";
+  for (const each of rooms) {
+    const full = each.playing || each.seats.length >= each.rules.players;
+    const button = document.createElement("button");
+    button.textContent = `${full ? "Watch" : "Join"} ${each.name} (${each.seats.length}/${each.rules.players})`;
+    button.onclick = () => {
+      $("notice").textContent = "";
+      watching = full;
+      send(full ? { type: "watch", room: each.id } : { type: "join_room", room: each.id });
+    };
+    $("rooms").appendChild(button);
+    $("rooms").appendChild(document.createElement("br"));
+  }
+}
+
+function roomView() {
+  $("room-name").textContent = room.name;
+  $("seats").textContent = "";
+  for (const seat of room.seats) {
+    const line = document.createElement("div");
+    line.textContent = `${seat.name}  ${seat.away ? "away" : seat.ready ? "ready" : "not ready"}`;
+    $("seats").appendChild(line);
+  }
+  $("ready").textContent = ready ? "Not ready after all" : "Ready";
+}
+
+// the board like the terminal draws it, * and $ for the heads
+function draw(board) {
+  const rows = Array.from({ length: board.height }, () => Array(board.width).fill(" "));
+  const put = (point, glyph) => {
+    if (point.y < board.height && point.x < board.width) rows[point.y][point.x] = glyph;
+  };
+  for (const fruit of board.fruits) put(fruit, "a");
+  board.snakes.forEach((snake, player) => {
+    snake.body.forEach((segment, i) => put(segment, i > 0 ? "o" : player === 0 ? "*" : "$"));
+  });
+  $("board").textContent = rows.map((row) => row.join("")).join("\n");
+
+  const name = (player) => seats[player]?.name ?? `P${player + 1}`;
+  const hud = board.snakes.map((snake, player) => `${name(player)}: ${snake.score}`);
+  if (you !== null && board.snakes.length > 1) hud.push(`you are ${you === 0 ? "*" : "$"}`);
+  if (board.game_over) {
+    hud.push(board.winner === 3 ? "It's a draw" : board.winner ? `${name(board.winner - 1)} won` : "Game over");
+  }
+  $("hud").textContent = hud.join("   ");
+}
+
+function heard(from, text) {
+  const line = document.createElement("div");
+  line.textContent = `${from}: ${text}`;
+  $("chat").appendChild(line);
+  while ($("chat").childNodes.length > 4) $("chat").firstChild.remove();
+}
+
+const directions = {
+  ArrowUp: "up", ArrowDown: "down", ArrowLeft: "left", ArrowRight: "right",
+  w: "up", s: "down", a: "left", d: "right",
+};
+document.addEventListener("keydown", (event) => {
+  if (document.activeElement === $("say") || document.activeElement === $("who")) return;
+  if (event.key === "t") {
+    event.preventDefault();
+    $("say").focus();
+  } else if (you !== null && directions[event.key]) {
+    event.preventDefault();
+    send({ type: "input", direction: directions[event.key] });
+  }
+});
+$("say").addEventListener("keydown", (event) => {
+  if (event.key === "Enter" && $("say").value.trim()) {
+    send({ type: "chat", text: $("say").value.trim() });
+    heard("you", $("say").value.trim());
+    $("say").value = "";
+    $("say").blur();
+  } else if (event.key === "Escape") {
+    $("say").blur();
+  }
+});
+
+$("go").onclick = () => $("who").value.trim() && connect($("who").value.trim());
+$("who").addEventListener("keydown", (event) => event.key === "Enter" && $("go").onclick());
+$("create").onclick = () => {
+  $("notice").textContent = "";
+  send({
+    type: "create_room",
+    name: `${$("who").value.trim()}'s room`,
+    rules: {
+      players: Number($("players").value),
+      border: $("border").checked,
+      speed_ms: Number($("speed").value),
+    },
+  });
+};
+$("ready").onclick = () => {
+  ready = !ready;
+  send({ type: "ready", ready });
+  roomView();
+};
+$("leave").onclick = () => send({ type: "leave_room" });
+$("stop").onclick = () => send({ type: "leave_room" });